@@ -0,0 +1,418 @@
+#[allow(non_snake_case)]
+pub mod AdaptiveHuffman {
+    use crate::{
+        bit_io::MsbBitReader,
+        compressed_buffer::{Bit, CompressedBuffer},
+        error::{CompressorError, Result},
+        utils::check_decoded_size,
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://www.ittc.ku.edu/~jsv/Papers/Vit87.jacmACMversion.pdf
+    // https://en.wikipedia.org/wiki/Adaptive_Huffman_coding
+
+    /// 256 leaves for the bytes, one for the symbols not seen yet, and their 256 parents
+    const POSITIONS: usize = 2 * 257 - 1;
+
+    /// position of the root, the heaviest node
+    const ROOT: usize = POSITIONS - 1;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Content {
+        Leaf(u8),
+        /// children at the positions `2 * pair` (bit 0) and `2 * pair + 1` (bit 1)
+        Internal(usize),
+        /// leaf of the symbols not seen yet (NYT), followed by the byte itself
+        NotYetTransmitted,
+    }
+
+    /// Huffman tree updated after each symbol, the same on both sides.
+    ///
+    /// The nodes are listed by weight in positions, two siblings being side by side, which makes it
+    /// a Huffman tree (sibling property). For a same weight, the leaves come before the internal
+    /// nodes, so that the tree stays as shallow as possible (Vitter's algorithm V).
+    ///
+    /// ```text
+    /// after "abb": positions   508  509  510  511  512
+    ///              nodes       NYT  a:1  (1)  b:2  (3)
+    ///
+    ///        (3)
+    ///       /   \
+    ///     (1)   b:2
+    ///    /   \
+    ///  NYT   a:1
+    /// ```
+    struct Tree {
+        weights: [u64; POSITIONS],
+        contents: [Content; POSITIONS],
+        /// position of the parent of the children at `2 * pair` and `2 * pair + 1`
+        parents: [usize; POSITIONS / 2],
+        /// position of the leaf of each byte, 0 when it was not seen yet
+        leaves: [usize; 256],
+        /// position of the NYT leaf, the lightest node
+        not_yet_transmitted: usize,
+    }
+
+    impl Tree {
+        fn new() -> Self {
+            let mut contents = [Content::Internal(0); POSITIONS];
+            contents[ROOT] = Content::NotYetTransmitted;
+
+            Tree {
+                weights: [0; POSITIONS],
+                contents,
+                parents: [0; POSITIONS / 2],
+                leaves: [0; 256],
+                not_yet_transmitted: ROOT,
+            }
+        }
+
+        fn parent(&self, position: usize) -> Option<usize> {
+            (position != ROOT).then(|| self.parents[position / 2])
+        }
+
+        fn is_leaf(&self, position: usize) -> bool {
+            !matches!(self.contents[position], Content::Internal(_))
+        }
+
+        /// @**returns** Option\<usize\> => position of the leaf of the byte, if it was seen before
+        fn leaf(&self, byte: u8) -> Option<usize> {
+            Some(self.leaves[byte as usize]).filter(|&position| position != 0)
+        }
+
+        /// bits from the root to the node, pushed in `code` from the last one
+        fn code(&self, mut position: usize, code: &mut Vec<Bit>) {
+            code.clear();
+            while let Some(parent) = self.parent(position) {
+                code.push(if position & 1 == 0 {
+                    Bit::ZERO
+                } else {
+                    Bit::ONE
+                });
+                position = parent;
+            }
+        }
+
+        /// give the node of a position to another one, its children following it
+        fn set(&mut self, position: usize, content: Content, weight: u64) {
+            self.contents[position] = content;
+            self.weights[position] = weight;
+
+            match content {
+                Content::Leaf(byte) => self.leaves[byte as usize] = position,
+                Content::Internal(pair) => self.parents[pair] = position,
+                Content::NotYetTransmitted => self.not_yet_transmitted = position,
+            }
+        }
+
+        fn swap(&mut self, a: usize, b: usize) {
+            let (content_a, weight_a) = (self.contents[a], self.weights[a]);
+            self.set(a, self.contents[b], self.weights[b]);
+            self.set(b, content_a, weight_a);
+        }
+
+        /// move the node of a position to `to`, the nodes in between going down one position
+        fn slide(&mut self, from: usize, to: usize) {
+            for position in from..to {
+                self.swap(position, position + 1);
+            }
+        }
+
+        /// highest position of the nodes of the same weight and kind (leaf or internal) as this one
+        fn leader(&self, position: usize) -> usize {
+            let (weight, leaf) = (self.weights[position], self.is_leaf(position));
+
+            let mut leader = position;
+            while leader < ROOT
+                && self.weights[leader + 1] == weight
+                && self.is_leaf(leader + 1) == leaf
+            {
+                leader += 1;
+            }
+
+            leader
+        }
+
+        /// replace the NYT leaf by a parent of the NYT leaf and of a leaf of the new byte
+        ///
+        /// @**returns** (usize, usize) => (position of the parent, position of the new leaf)
+        fn add_leaf(&mut self, byte: u8) -> (usize, usize) {
+            let parent = self.not_yet_transmitted;
+            let pair = parent / 2 - 1;
+
+            self.set(parent, Content::Internal(pair), 0);
+            self.set(2 * pair, Content::NotYetTransmitted, 0);
+            self.set(2 * pair + 1, Content::Leaf(byte), 0);
+
+            (parent, 2 * pair + 1)
+        }
+
+        /// add one to the weight of the byte and of its ancestors, keeping the nodes in order
+        fn update(&mut self, byte: u8) {
+            // the sibling of the NYT leaf weighs as much as their parent, it is incremented last
+            let mut leaf_to_increment = None;
+
+            let mut position = match self.leaf(byte) {
+                None => {
+                    let (parent, leaf) = self.add_leaf(byte);
+                    leaf_to_increment = Some(leaf);
+                    parent
+                }
+                Some(leaf) => {
+                    let leader = self.leader(leaf);
+                    self.swap(leaf, leader);
+
+                    if leader == self.not_yet_transmitted + 1 {
+                        leaf_to_increment = Some(leader);
+                        self.parents[leader / 2]
+                    } else {
+                        leader
+                    }
+                }
+            };
+
+            while let Some(next) = self.slide_and_increment(position) {
+                position = next;
+            }
+
+            if let Some(leaf) = leaf_to_increment {
+                self.slide_and_increment(leaf);
+            }
+        }
+
+        /// add one to the weight of a node, moving it after the nodes it becomes heavier than
+        ///
+        /// ```text
+        /// a leaf goes after the internal nodes of its weight,
+        /// an internal node after the leaves of its weight + 1
+        /// ```
+        ///
+        /// @**returns** Option\<usize\> => position of the next node to increment
+        fn slide_and_increment(&mut self, position: usize) -> Option<usize> {
+            let leader = self.leader(position);
+            self.swap(position, leader);
+
+            let weight = self.weights[leader];
+            let leaf = self.is_leaf(leader);
+            let former_parent = self.parent(leader);
+
+            // the following block the node goes after
+            let (block_weight, block_leaf) = if leaf {
+                (weight, false)
+            } else {
+                (weight + 1, true)
+            };
+            let mut last = leader;
+            while last < ROOT
+                && self.weights[last + 1] == block_weight
+                && self.is_leaf(last + 1) == block_leaf
+            {
+                last += 1;
+            }
+
+            self.slide(leader, last);
+            self.weights[last] += 1;
+
+            if leaf {
+                self.parent(last)
+            } else {
+                former_parent
+            }
+        }
+    }
+
+    /// encode an array of bytes with a Huffman tree updated after each byte, in one pass
+    ///
+    /// A byte seen for the first time is written as the code of the NYT leaf followed by the byte.
+    ///
+    /// @**returns** Vec\<u8\> => varsize number of bytes followed by the codes
+    pub fn encode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut tree = Tree::new();
+        let mut buffer = CompressedBuffer::new();
+        let mut code = Vec::new();
+
+        for &byte in input {
+            match tree.leaf(byte) {
+                Some(leaf) => tree.code(leaf, &mut code),
+                None => tree.code(tree.not_yet_transmitted, &mut code),
+            }
+
+            for &bit in code.iter().rev() {
+                buffer.push_bit(bit);
+            }
+            if tree.leaf(byte).is_none() {
+                buffer.push_bits(byte as usize, 8);
+            }
+
+            tree.update(byte);
+        }
+
+        let mut encoded = encode_varsize(input.len());
+        encoded.append(&mut buffer.buffer);
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode`]
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let (size, start) = get_first_decoded(input)?;
+        check_decoded_size(size)?;
+
+        let mut tree = Tree::new();
+        let mut reader = MsbBitReader::new(&input[start..]);
+        let mut decoded = Vec::new();
+
+        while decoded.len() < size {
+            let mut position = ROOT;
+            let byte = loop {
+                match tree.contents[position] {
+                    Content::Leaf(byte) => break byte,
+                    Content::Internal(pair) => position = 2 * pair + reader.read_bit()? as usize,
+                    Content::NotYetTransmitted => {
+                        let byte = reader.read_bits(8)? as u8;
+                        if tree.leaf(byte).is_some() {
+                            return Err(CompressorError::InvalidData(format!(
+                                "byte {byte} sent again as a new byte"
+                            )));
+                        }
+                        break byte;
+                    }
+                }
+            };
+
+            tree.update(byte);
+            decoded.push(byte);
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::canonical_huffman::code_lengths;
+        use crate::test_utils::{pseudo_random, random_bytes};
+        use crate::utils::MAX_BLOCK_SIZE;
+
+        /// the nodes are in order, the weights add up and the tree is as good as a static Huffman tree
+        fn check(tree: &Tree, counts: &[usize; 256]) {
+            let mut cost = 0;
+
+            for position in tree.not_yet_transmitted..POSITIONS {
+                if position > tree.not_yet_transmitted {
+                    let (previous, weight) = (tree.weights[position - 1], tree.weights[position]);
+                    assert!(previous <= weight);
+                    if previous == weight {
+                        assert!(tree.is_leaf(position - 1) || !tree.is_leaf(position));
+                    }
+                }
+
+                match tree.contents[position] {
+                    Content::Internal(pair) => {
+                        assert_eq!(position, tree.parents[pair]);
+                        assert!(2 * pair + 1 < position);
+                        let children = tree.weights[2 * pair] + tree.weights[2 * pair + 1];
+                        assert_eq!(children, tree.weights[position]);
+                    }
+                    Content::Leaf(byte) => {
+                        assert_eq!(position, tree.leaves[byte as usize]);
+                        assert_eq!(counts[byte as usize] as u64, tree.weights[position]);
+
+                        let mut code = Vec::new();
+                        tree.code(position, &mut code);
+                        cost += code.len() * counts[byte as usize];
+                    }
+                    Content::NotYetTransmitted => assert_eq!(0, tree.weights[position]),
+                }
+            }
+
+            // the NYT leaf weighs 0, as an epsilon compared to the bytes
+            let mut frequencies: Vec<usize> = counts.iter().map(|&count| count << 20).collect();
+            frequencies.push(1);
+            let lengths = code_lengths(&frequencies, 31);
+            let best: usize = (0..257)
+                .map(|symbol| lengths[symbol] as usize * frequencies[symbol])
+                .sum();
+            assert_eq!(best >> 20, cost);
+        }
+
+        #[test]
+        fn tree_stays_huffman() {
+            let skewed: Vec<u8> = pseudo_random(7)
+                .take(3000)
+                .map(|seed| ((seed >> 16) % 1000).trailing_zeros() as u8 + b'a')
+                .collect();
+
+            for text in [
+                b"abb".to_vec(),
+                b"abracadabra".to_vec(),
+                "AAABBCCDACCAA".repeat(20).bytes().collect(),
+                (0..=255).cycle().take(600).collect(),
+                skewed,
+            ] {
+                let mut tree = Tree::new();
+                let mut counts = [0; 256];
+                for &byte in &text {
+                    tree.update(byte);
+                    counts[byte as usize] += 1;
+                    check(&tree, &counts);
+                }
+            }
+        }
+
+        #[test]
+        fn ahuff_round_trip() {
+            let random = random_bytes(20_000);
+
+            for text in [
+                Vec::new(),
+                vec![0],
+                vec![b'A'; 1000],
+                "RGGRRRGGGB\n".bytes().collect(),
+                (0..=255).cycle().take(3 * 256).collect(),
+                random,
+            ] {
+                let encoded = encode(&text).unwrap();
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+        }
+
+        #[test]
+        fn ahuff_small_input() {
+            // "abb": a as new (no bits for the NYT leaf alone), b as new (NYT: 0), b (11)
+            let encoded = encode(b"abb").unwrap();
+            assert_eq!(vec![3, 0b0110_0001, 0b0011_0001, 0b0110_0000], encoded);
+
+            // smaller than the static tree for a short text
+            let text: Vec<u8> = "the quick brown fox".bytes().collect();
+            let static_huffman =
+                crate::algorithms::huffman_tree::HuffmanTree::encode_with_metadatas(&text).unwrap();
+            assert!(encode(&text).unwrap().len() < static_huffman.len());
+        }
+
+        #[test]
+        fn ahuff_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".repeat(10).bytes().collect();
+            let encoded = encode(&text).unwrap();
+
+            for end in 0..encoded.len() {
+                assert!(decode(&encoded[..end]).is_err());
+            }
+
+            let mut oversized = encode_varsize(MAX_BLOCK_SIZE + 1);
+            oversized.push(0);
+            assert!(matches!(
+                decode(&oversized),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            // 'a' then 'a' again as a new byte
+            assert!(matches!(
+                decode(&[2, 0b0110_0001, 0b0011_0000, 0b1000_0000]),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,507 @@
+#[allow(non_snake_case)]
+pub mod AnsEncoder {
+    use crate::{
+        compressed_buffer::{Bit, CompressedBuffer},
+        error::{CompressorError, Result},
+        utils::{check_decoded_size, check_input_size},
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://arxiv.org/abs/1311.2540
+    // https://github.com/rygorous/ryg_rans
+    // https://github.com/Cyan4973/FiniteStateEntropy
+
+    /// the normalized frequencies add up to 2^PROB_BITS
+    const PROB_BITS: u32 = 12;
+    const PROB_TOTAL: u32 = 1 << PROB_BITS;
+
+    /// lower bound of the rANS states, they stay in [RANS_L, RANS_L * 256) between symbols
+    const RANS_L: u32 = 1 << 23;
+
+    /// number of rANS states, each coding every other symbol
+    const RANS_STREAMS: usize = 2;
+
+    /// (symbol, normalized frequency) of the symbols present in the input
+    type Frequencies = Vec<(u8, u32)>;
+
+    /// Scale the count of each symbol so they add up to [`PROB_TOTAL`], every symbol keeps at least 1.
+    ///
+    /// ## Example:
+    ///
+    /// ```text
+    /// AAAB => [(A, 3072), (B, 1024)]
+    /// ```
+    pub fn normalize_frequencies(input: &[u8]) -> Frequencies {
+        let mut counts = [0usize; 256];
+        for &c in input {
+            counts[c as usize] += 1;
+        }
+
+        let total = input.len() as u64;
+        let mut frequencies: Frequencies = (0..=255)
+            .zip(counts)
+            .filter(|&(_, count)| count > 0)
+            .map(|(c, count)| {
+                let scaled = count as u64 * PROB_TOTAL as u64 / total;
+                (c, (scaled as u32).max(1))
+            })
+            .collect();
+
+        // the rounding is given to (or taken from) the most frequent symbols
+        let mut by_frequency: Vec<usize> = (0..frequencies.len()).collect();
+        by_frequency.sort_by_key(|&i| std::cmp::Reverse(frequencies[i].1));
+
+        let mut sum: u32 = frequencies.iter().map(|&(_, frequency)| frequency).sum();
+        if sum < PROB_TOTAL && !frequencies.is_empty() {
+            frequencies[by_frequency[0]].1 += PROB_TOTAL - sum;
+        }
+        for &i in by_frequency.iter().cycle() {
+            if sum <= PROB_TOTAL {
+                break;
+            }
+            if frequencies[i].1 > 1 {
+                frequencies[i].1 -= 1;
+                sum -= 1;
+            }
+        }
+
+        frequencies
+    }
+
+    /// number of symbols, then each symbol with its normalized frequency, as varsize
+    fn frequencies_as_bytes(size: usize, frequencies: &[(u8, u32)]) -> Vec<u8> {
+        let mut bytes = encode_varsize(size);
+        if size == 0 {
+            return bytes;
+        }
+
+        bytes.extend_from_slice(&encode_varsize(frequencies.len()));
+        for &(c, frequency) in frequencies {
+            bytes.push(c);
+            bytes.extend_from_slice(&encode_varsize(frequency as usize));
+        }
+
+        bytes
+    }
+
+    /// @**returns** (usize, Vec\<(u8, u32)\>, usize) => (number of symbols, frequencies, index following them)
+    fn frequencies_from_bytes(input: &[u8]) -> Result<(usize, Frequencies, usize)> {
+        let (size, mut i) = get_first_decoded(input)?;
+        if size == 0 {
+            return Ok((0, Vec::new(), i));
+        }
+
+        // a symbol with all the probability is decoded without reading anything, the size cannot
+        // be checked against the encoded data
+        check_decoded_size(size)?;
+
+        let (num_symbols, last_byte_found) = get_first_decoded(&input[i..])?;
+        i += last_byte_found;
+
+        let mut frequencies = Vec::new();
+        for _ in 0..num_symbols.min(256) {
+            let c = *input.get(i).ok_or(CompressorError::TruncatedInput)?;
+            let (frequency, last_byte_found) = get_first_decoded(&input[i + 1..])?;
+            i += last_byte_found + 1;
+
+            frequencies.push((c, u32::try_from(frequency).unwrap_or(u32::MAX)));
+        }
+
+        let mut seen = [false; 256];
+        let mut sum: u64 = 0;
+        for &(c, frequency) in &frequencies {
+            if frequency == 0 || std::mem::replace(&mut seen[c as usize], true) {
+                return Err(CompressorError::InvalidData(String::from(
+                    "invalid normalized frequencies",
+                )));
+            }
+            sum += frequency as u64;
+        }
+
+        if num_symbols > 256 || sum != PROB_TOTAL as u64 {
+            return Err(CompressorError::InvalidData(format!(
+                "normalized frequencies add up to {sum} instead of {PROB_TOTAL}"
+            )));
+        }
+
+        Ok((size, frequencies, i))
+    }
+
+    /// (start, frequency) of each byte, the absent ones have a frequency of 0
+    fn cumulative_frequencies(frequencies: &[(u8, u32)]) -> [(u32, u32); 256] {
+        let mut cumulative = [(0, 0); 256];
+
+        let mut start = 0;
+        for &(c, frequency) in frequencies {
+            cumulative[c as usize] = (start, frequency);
+            start += frequency;
+        }
+
+        cumulative
+    }
+
+    /// symbol of each slot of the normalized frequencies
+    fn slot_symbols(frequencies: &[(u8, u32)]) -> Vec<u8> {
+        frequencies
+            .iter()
+            .flat_map(|&(c, frequency)| std::iter::repeat_n(c, frequency as usize))
+            .collect()
+    }
+
+    /// encode an array of bytes using range asymmetric numeral systems
+    ///
+    /// The symbols are shared between [`RANS_STREAMS`] states so consecutive symbols don't depend on each
+    /// other. The symbols are encoded in reverse so they come out in order when decoding.
+    ///
+    /// @**returns** Vec\<u8\> => number of symbols, normalized frequencies, final states and encoded bytes
+    pub fn encode_rans(input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        let frequencies = normalize_frequencies(input);
+        let mut encoded = frequencies_as_bytes(input.len(), &frequencies);
+        if input.is_empty() {
+            return Ok(encoded);
+        }
+
+        let cumulative = cumulative_frequencies(&frequencies);
+
+        // bytes in the reverse order they will be read
+        let mut reversed = Vec::new();
+        let mut states = [RANS_L; RANS_STREAMS];
+        for (i, &c) in input.iter().enumerate().rev() {
+            let state = &mut states[i % RANS_STREAMS];
+            let (start, frequency) = cumulative[c as usize];
+
+            // renormalize, so the state stays in range once the symbol is added
+            let max_state = ((RANS_L >> PROB_BITS) << 8) * frequency;
+            while *state >= max_state {
+                reversed.push(*state as u8);
+                *state >>= 8;
+            }
+
+            *state = ((*state / frequency) << PROB_BITS) + (*state % frequency) + start;
+        }
+
+        for state in states.iter().rev() {
+            reversed.extend_from_slice(&state.to_le_bytes());
+        }
+
+        encoded.extend(reversed.iter().rev());
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode_rans`]
+    pub fn decode_rans(input: &[u8]) -> Result<Vec<u8>> {
+        let (size, frequencies, i) = frequencies_from_bytes(input)?;
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let cumulative = cumulative_frequencies(&frequencies);
+        let symbols = slot_symbols(&frequencies);
+
+        let mut bytes = input[i..].iter();
+        let mut states = [0u32; RANS_STREAMS];
+        for state in states.iter_mut() {
+            for _ in 0..4 {
+                let byte = *bytes.next().ok_or(CompressorError::TruncatedInput)?;
+                *state = (*state << 8) | byte as u32;
+            }
+        }
+
+        let mut decoded = Vec::new();
+        for i in 0..size {
+            let state = &mut states[i % RANS_STREAMS];
+
+            let c = symbols[(*state & (PROB_TOTAL - 1)) as usize];
+            let (start, frequency) = cumulative[c as usize];
+            *state = frequency * (*state >> PROB_BITS) + (*state & (PROB_TOTAL - 1)) - start;
+
+            while *state < RANS_L {
+                let byte = *bytes.next().ok_or(CompressorError::TruncatedInput)?;
+                *state = (*state << 8) | byte as u32;
+            }
+
+            decoded.push(c);
+        }
+
+        // every state is back to where the encoder started
+        if bytes.len() > 0 || states.iter().any(|&state| state != RANS_L) {
+            return Err(CompressorError::InvalidData(String::from(
+                "rANS states do not match the encoded data",
+            )));
+        }
+
+        Ok(decoded)
+    }
+
+    /// Tables of the tANS finite state machine, the states of the encoder are in [PROB_TOTAL, 2 * PROB_TOTAL)
+    /// and the ones of the decoder are the same minus PROB_TOTAL.
+    struct TansTables {
+        /// symbol of each decoder state
+        symbols: Vec<u8>,
+        /// number of bits to read after each decoder state
+        num_bits: Vec<u32>,
+        /// next decoder state before adding the read bits
+        next_states: Vec<u32>,
+        /// encoder state reached from each value in [frequency, 2 * frequency) of each symbol,
+        /// the values of a symbol start at its cumulative frequency
+        encoder_states: Vec<u32>,
+        /// (start in encoder_states, frequency) of each byte
+        cumulative: [(u32, u32); 256],
+    }
+
+    impl TansTables {
+        fn new(frequencies: &[(u8, u32)]) -> Self {
+            let size = PROB_TOTAL as usize;
+
+            // spreading the symbols over the table, so each one is found all around it
+            let mut symbols = vec![0; size];
+            let step = (size >> 1) + (size >> 3) + 3;
+            let mut position = 0;
+            for &(c, frequency) in frequencies {
+                for _ in 0..frequency {
+                    symbols[position] = c;
+                    position = (position + step) & (size - 1);
+                }
+            }
+
+            let cumulative = cumulative_frequencies(frequencies);
+
+            let mut next_values = [0u32; 256];
+            for &(c, frequency) in frequencies {
+                next_values[c as usize] = frequency;
+            }
+
+            let mut num_bits = vec![0; size];
+            let mut next_states = vec![0; size];
+            let mut encoder_states = vec![0; size];
+            for (state, &c) in symbols.iter().enumerate() {
+                let (start, frequency) = cumulative[c as usize];
+                let value = next_values[c as usize];
+                next_values[c as usize] += 1;
+
+                num_bits[state] = PROB_BITS - value.ilog2();
+                next_states[state] = (value << num_bits[state]) - PROB_TOTAL;
+                encoder_states[(start + value - frequency) as usize] = state as u32 + PROB_TOTAL;
+            }
+
+            TansTables {
+                symbols,
+                num_bits,
+                next_states,
+                encoder_states,
+                cumulative,
+            }
+        }
+    }
+
+    /// encode an array of bytes using table asymmetric numeral systems (like FSE)
+    ///
+    /// The symbols are encoded in reverse, the final state is stored first followed by the bits of
+    /// each symbol in the order they will be read.
+    ///
+    /// @**returns** Vec\<u8\> => number of symbols, normalized frequencies and encoded bits
+    pub fn encode_tans(input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        let frequencies = normalize_frequencies(input);
+        let mut encoded = frequencies_as_bytes(input.len(), &frequencies);
+        if input.is_empty() {
+            return Ok(encoded);
+        }
+
+        let tables = TansTables::new(&frequencies);
+
+        // (bits, number of bits) of each symbol, in reverse order
+        let mut chunks = Vec::with_capacity(input.len());
+        let mut state = PROB_TOTAL;
+        for &c in input.iter().rev() {
+            let (start, frequency) = tables.cumulative[c as usize];
+
+            // number of bits to remove for the state to be in [frequency, 2 * frequency)
+            let mut num_bits = state.ilog2() - frequency.ilog2();
+            if state >> num_bits < frequency {
+                num_bits -= 1;
+            }
+
+            chunks.push((state & ((1 << num_bits) - 1), num_bits));
+            state = tables.encoder_states[(start + (state >> num_bits) - frequency) as usize];
+        }
+        chunks.push((state - PROB_TOTAL, PROB_BITS));
+
+        let mut compressed_buffer = CompressedBuffer::new();
+        for &(bits, num_bits) in chunks.iter().rev() {
+            for i in (0..num_bits).rev() {
+                let bit = if bits >> i & 1 == 0 {
+                    Bit::ZERO
+                } else {
+                    Bit::ONE
+                };
+                compressed_buffer.push_bit(bit);
+            }
+        }
+
+        encoded.extend_from_slice(&compressed_buffer.buffer);
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode_tans`]
+    pub fn decode_tans(input: &[u8]) -> Result<Vec<u8>> {
+        let (size, frequencies, i) = frequencies_from_bytes(input)?;
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let tables = TansTables::new(&frequencies);
+
+        let encoded = &input[i..];
+        let mut bit_index = 0;
+        let mut read_bits = |num_bits: u32| -> Result<u32> {
+            let mut bits = 0;
+            for _ in 0..num_bits {
+                let byte = encoded
+                    .get(bit_index / 8)
+                    .ok_or(CompressorError::TruncatedInput)?;
+                bits = (bits << 1) | (byte >> (7 - bit_index % 8) & 1) as u32;
+                bit_index += 1;
+            }
+
+            Ok(bits)
+        };
+
+        let mut state = read_bits(PROB_BITS)? as usize;
+        let mut decoded = Vec::new();
+        for _ in 0..size {
+            decoded.push(tables.symbols[state]);
+            state = (tables.next_states[state] + read_bits(tables.num_bits[state])?) as usize;
+        }
+
+        // the encoder started from the first state
+        if state != 0 {
+            return Err(CompressorError::InvalidData(String::from(
+                "tANS state does not match the encoded data",
+            )));
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::test_utils::{pseudo_random, sample_texts};
+        use crate::utils::MAX_BLOCK_SIZE;
+
+        fn texts() -> Vec<Vec<u8>> {
+            let random = pseudo_random(42)
+                .take(100_000)
+                .map(|seed| ((seed >> 16) % 13) as u8 * ((seed >> 30) as u8 + 1))
+                .collect();
+
+            let mut texts = sample_texts(random);
+            // more symbols than slots for the least frequent ones
+            texts.push([vec![b'A'; 100_000], (0..=255).collect()].concat());
+            texts
+        }
+
+        #[test]
+        fn ans_normalize_frequencies() {
+            assert_eq!(
+                vec![(b'A', 3072), (b'B', 1024)],
+                normalize_frequencies(b"AAAB")
+            );
+
+            for text in texts().iter().filter(|text| !text.is_empty()) {
+                let frequencies = normalize_frequencies(text);
+                let sum: u32 = frequencies.iter().map(|&(_, frequency)| frequency).sum();
+
+                assert_eq!(PROB_TOTAL, sum);
+                assert!(frequencies.iter().all(|&(_, frequency)| frequency > 0));
+            }
+        }
+
+        #[test]
+        fn rans_round_trip() {
+            for text in texts() {
+                let encoded = encode_rans(&text).unwrap();
+                assert_eq!(text, decode_rans(&encoded).unwrap());
+            }
+        }
+
+        #[test]
+        fn tans_round_trip() {
+            for text in texts() {
+                let encoded = encode_tans(&text).unwrap();
+                assert_eq!(text, decode_tans(&encoded).unwrap());
+            }
+        }
+
+        #[test]
+        fn ans_close_to_entropy() {
+            let text: Vec<u8> = "AAAAACCCCadkjahsdkjashdkjashdjkashdkjashdCCCBBB"
+                .repeat(200)
+                .bytes()
+                .collect();
+
+            let mut counts = [0usize; 256];
+            for &c in &text {
+                counts[c as usize] += 1;
+            }
+            let entropy_bytes: f64 = counts
+                .iter()
+                .filter(|&&count| count > 0)
+                .map(|&count| -(count as f64) * (count as f64 / text.len() as f64).log2())
+                .sum::<f64>()
+                / 8.0;
+
+            for encoded in [encode_rans(&text).unwrap(), encode_tans(&text).unwrap()] {
+                assert!((encoded.len() as f64) < entropy_bytes * 1.02 + 64.0);
+            }
+        }
+
+        #[test]
+        fn ans_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".bytes().collect();
+
+            for (encode, decode) in [
+                (
+                    encode_rans as fn(&[u8]) -> Result<Vec<u8>>,
+                    decode_rans as fn(&[u8]) -> _,
+                ),
+                (encode_tans, decode_tans),
+            ] {
+                let encoded = encode(&text).unwrap();
+
+                assert!(matches!(
+                    decode(&encoded[..encoded.len() - 2]),
+                    Err(CompressorError::TruncatedInput)
+                ));
+                assert!(decode(&[]).is_err());
+
+                // frequencies not adding up to the total
+                let mut corrupted = encoded.clone();
+                corrupted[4] ^= 1;
+                assert!(matches!(
+                    decode(&corrupted),
+                    Err(CompressorError::InvalidData(_))
+                ));
+
+                // a single symbol is decoded without reading anything, whatever the size given
+                let encoded = encode(&[b'a'; 10]).unwrap();
+                let header = frequencies_as_bytes(10, &[(b'a', PROB_TOTAL)]);
+                assert_eq!(header, encoded[..header.len()]);
+                for size in [MAX_BLOCK_SIZE + 1, 20_000_000_000, usize::MAX] {
+                    let mut inflated = frequencies_as_bytes(size, &[(b'a', PROB_TOTAL)]);
+                    inflated.extend_from_slice(&encoded[header.len()..]);
+                    assert!(matches!(
+                        decode(&inflated),
+                        Err(CompressorError::InvalidData(_))
+                    ));
+                }
+            }
+        }
+    }
+}
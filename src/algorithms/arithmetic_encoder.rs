@@ -1,14 +1,394 @@
 #[allow(non_snake_case)]
 pub mod ArithmeticEncoder {
+    use crate::{
+        error::{CompressorError, Result},
+        fenwick_tree::FenwickTree,
+        range_coder::{MAX_TOTAL, RangeDecoder, RangeEncoder},
+        utils::{check_decoded_size, check_input_size},
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    use super::BigUintArithmeticEncoder;
+
+    /// first byte of the data encoded with the fixed precision
+    ///
+    /// the first byte of the [`BigUintArithmeticEncoder`] is a bit index, so at most 7
+    const FIXED_PRECISION: u8 = 8;
+
+    /// (frequency of each char, encoded bytes)
+    type Encoded = (Vec<(u8, isize)>, Vec<u8>);
+
+    /// Cumulative frequencies of the chars, scaled down to fit in the precision of the range coder.
+    ///
+    /// The frequencies never change, so they are kept in a plain table instead of a [`FenwickTree`].
+    struct StaticModel {
+        /// count of all the chars before each char, followed by the total count
+        cumulative: [u64; 257],
+    }
+
+    impl StaticModel {
+        fn new(frequency: &[(u8, isize)]) -> Self {
+            let total: u64 = frequency.iter().map(|&(_, count)| count as u64).sum();
+
+            // every char keeps a count of at least 1, which can add up to 256
+            let max_total = MAX_TOTAL - 256;
+
+            let mut counts = [0; 256];
+            for &(c, count) in frequency {
+                let count = count as u64;
+                counts[c as usize] += if total <= max_total {
+                    count
+                } else {
+                    let scaled = count as u128 * max_total as u128 / total as u128;
+                    (scaled as u64).max(1)
+                };
+            }
+
+            let mut cumulative = [0; 257];
+            for (i, count) in counts.into_iter().enumerate() {
+                cumulative[i + 1] = cumulative[i] + count;
+            }
+
+            StaticModel { cumulative }
+        }
+
+        fn get_bounds(&self, symbol: u8) -> (u64, u64) {
+            let i = symbol as usize;
+            (self.cumulative[i], self.cumulative[i + 1])
+        }
+
+        fn get_total_count(&self) -> u64 {
+            self.cumulative[256]
+        }
+
+        /// find the char whose bounds contain the range (low <= range < high)
+        fn search_range(&self, range: u64) -> Option<u8> {
+            let after = self.cumulative.partition_point(|&count| count <= range);
+            u8::try_from(after.checked_sub(1)?).ok()
+        }
+    }
+
+    // NOTE: good ressources for implementation
+    // https://github.com/tommyod/arithmetic-coding/blob/main/arithmetic_coding.py
+    // https://dl.acm.org/doi/10.1145/214762.214771
+    /// encode an array of bytes using the arithmetic encoding
+    ///
+    /// @**returns** (Vec\<(u8, isize)\>, Vec\<u8\>) => (
+    ///   single chars in order with their frequency,
+    ///   the encoded bits
+    /// )
+    pub fn encode(input: &[u8]) -> Result<Encoded> {
+        check_input_size(input)?;
+
+        let mut counts = [0; 256];
+        for &c in input {
+            counts[c as usize] += 1;
+        }
+
+        let frequency: Vec<(u8, isize)> = (0..=255)
+            .zip(counts)
+            .filter(|&(_, count)| count > 0)
+            .collect();
+
+        if frequency.is_empty() {
+            return Ok((frequency, Vec::new()));
+        }
+
+        let cum_freq = StaticModel::new(&frequency);
+        let total_count = cum_freq.get_total_count();
+
+        let mut encoder = RangeEncoder::new();
+        for &symbol in input {
+            let (symbol_low, symbol_high) = cum_freq.get_bounds(symbol);
+            encoder.encode(symbol_low, symbol_high, total_count);
+        }
+
+        Ok((frequency, encoder.finish()))
+    }
+
+    /// decode the bits of [`encode`], the frequencies adding up to more than a block are rejected
+    ///
+    /// a single char takes the whole range and is decoded without reading any bit, so nothing but
+    /// the frequencies limit the size
+    pub fn decode(frequency: &[(u8, isize)], encoded: &[u8]) -> Result<Vec<u8>> {
+        let size = frequency
+            .iter()
+            .try_fold(0usize, |size, &(_, count)| {
+                usize::try_from(count)
+                    .ok()
+                    .and_then(|count| size.checked_add(count))
+            })
+            .ok_or_else(|| CompressorError::InvalidData(String::from("invalid frequencies")))?;
+        check_decoded_size(size)?;
+
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        if encoded.is_empty() {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        let cum_freq = StaticModel::new(frequency);
+        let total_count = cum_freq.get_total_count();
+
+        let mut decoder = RangeDecoder::new(encoded);
+        let mut decoded = Vec::new();
+        while decoded.len() < size {
+            let symbol = cum_freq
+                .search_range(decoder.target(total_count))
+                .ok_or_else(|| {
+                    CompressorError::InvalidData(String::from(
+                        "encoded value does not match any symbol",
+                    ))
+                })?;
+
+            let (symbol_low, symbol_high) = cum_freq.get_bounds(symbol);
+            decoder.consume(symbol_low, symbol_high, total_count)?;
+            decoded.push(symbol);
+        }
+
+        Ok(decoded)
+    }
+
+    pub fn encode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        let (frequency, encoded) = encode(input)?;
+
+        // convert frequency to a frequency with variable size
+        let mut compressed_frequency = Vec::new();
+        for (char, num) in frequency {
+            compressed_frequency.push(char);
+            let var_num = encode_varsize(num as usize);
+            compressed_frequency.extend_from_slice(&var_num);
+        }
+        let compressed_frequency_size = encode_varsize(compressed_frequency.len());
+
+        // capacity => format + sizeof_frequency + frequency + encoded.len()
+        let mut encoded_with_meta_datas = Vec::with_capacity(
+            1 + compressed_frequency_size.len() + compressed_frequency.len() + encoded.len(),
+        );
+
+        encoded_with_meta_datas.push(FIXED_PRECISION);
+        encoded_with_meta_datas.extend_from_slice(&compressed_frequency_size);
+        encoded_with_meta_datas.extend_from_slice(&compressed_frequency);
+        encoded_with_meta_datas.extend_from_slice(&encoded);
+
+        Ok(encoded_with_meta_datas)
+    }
+
+    /// decode the data of [`encode_with_metadatas`], or of the [`BigUintArithmeticEncoder`]
+    pub fn decode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        match *input.first().ok_or(CompressorError::TruncatedInput)? {
+            FIXED_PRECISION => (),
+            0..=7 => return BigUintArithmeticEncoder::decode_with_metadatas(input),
+            format => {
+                return Err(CompressorError::InvalidData(format!(
+                    "unknown arithmetic coding format {format}"
+                )));
+            }
+        }
+
+        // reformating the frequency
+        let (freq_size, last_byte_found) = get_first_decoded(&input[1..])?;
+        let mut frequency = Vec::new();
+
+        let mut i = last_byte_found + 1;
+        let freq_end = i
+            .checked_add(freq_size)
+            .filter(|&end| end <= input.len())
+            .ok_or(CompressorError::TruncatedInput)?;
+        while i < freq_end {
+            // getting the character
+            let c = input[i];
+
+            // getting the frequency of the found character
+            let (num, last_byte_found) = get_first_decoded(&input[i + 1..freq_end])?;
+
+            // adding the frequency to the list and update the index
+            frequency.push((c, num as isize));
+            i += last_byte_found + 1;
+        }
+
+        // remaining bytes are the encoded content
+        decode(&frequency, &input[i..])
+    }
+
+    /// the counts are halved past this total, so the most recent symbols weigh more
+    const ADAPTIVE_MAX_TOTAL: isize = 1 << 16;
+
+    /// every byte starts with the same count, as nothing is known about the input
+    fn uniform_model() -> FenwickTree<u8> {
+        FenwickTree::new((0..=255).map(|c| (c, 1)).collect())
+    }
+
+    /// adapting probabilities to the symbol that was just coded
+    fn update_model(model: &mut FenwickTree<u8>, symbol: u8) {
+        model.add_count(symbol);
+
+        if model.get_total_count() > ADAPTIVE_MAX_TOTAL {
+            model.halve_counts();
+        }
+    }
+
+    /// encode an array of bytes using the adaptive arithmetic encoding
+    ///
+    /// The model starts uniform and is updated after each symbol, the decoder does the same updates
+    /// so only the number of symbols is stored before the encoded bits.
+    ///
+    /// @**returns** Vec\<u8\> => varsize number of symbols followed by the encoded bits
+    pub fn encode_adaptive(input: &[u8]) -> Result<Vec<u8>> {
+        let mut model = uniform_model();
+        let mut encoder = RangeEncoder::new();
+
+        for &symbol in input {
+            let (symbol_low, symbol_high) = model.get_bounds(symbol);
+            let total_count = model.get_total_count() as u64;
+            encoder.encode(symbol_low as u64, symbol_high as u64, total_count);
+
+            update_model(&mut model, symbol);
+        }
+
+        let mut encoded = encode_varsize(input.len());
+        encoded.extend_from_slice(&encoder.finish());
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode_adaptive`]
+    pub fn decode_adaptive(input: &[u8]) -> Result<Vec<u8>> {
+        let (size, last_byte_found) = get_first_decoded(input)?;
+        check_decoded_size(size)?;
+
+        let mut model = uniform_model();
+        let mut decoder = RangeDecoder::new(&input[last_byte_found..]);
+
+        let mut decoded = Vec::new();
+        while decoded.len() < size {
+            let total_count = model.get_total_count() as u64;
+            let symbol = model
+                .search_range(decoder.target(total_count) as isize)
+                .ok_or_else(|| {
+                    CompressorError::InvalidData(String::from(
+                        "encoded value does not match any symbol",
+                    ))
+                })?;
+
+            let (symbol_low, symbol_high) = model.get_bounds(symbol);
+            decoder.consume(symbol_low as u64, symbol_high as u64, total_count)?;
+            decoded.push(symbol);
+
+            update_model(&mut model, symbol);
+        }
+
+        Ok(decoded)
+    }
+
+    /// largest alphabet of [`encode_adaptive_symbols`], the halved counts stay at least at 1 so
+    /// they must leave room for the counts of the recent symbols
+    pub const MAX_SYMBOLS: usize = (MAX_TOTAL / 4) as usize;
+
+    /// added to the count of a coded symbol, more than 1 for a large alphabet to quickly move away
+    /// from its uniform counts
+    const SYMBOL_INCREMENT: isize = 32;
+
+    fn update_symbol_model(model: &mut FenwickTree<u32>, symbol: u32) {
+        model.increase_count(symbol, SYMBOL_INCREMENT);
+
+        if model.get_total_count() > MAX_TOTAL as isize - SYMBOL_INCREMENT {
+            model.halve_counts();
+        }
+    }
+
+    /// encode symbols wider than bytes using the adaptive arithmetic encoding, the symbols from 0 to
+    /// the largest one starting with the same count
+    ///
+    /// @**returns** Vec\<u8\> => varsize number of symbols and size of the alphabet followed by the encoded bits
+    pub fn encode_adaptive_symbols(symbols: &[u32]) -> Result<Vec<u8>> {
+        let num_symbols = symbols.iter().max().map_or(0, |&max| max as usize + 1);
+        if num_symbols > MAX_SYMBOLS {
+            return Err(CompressorError::InvalidArgument(format!(
+                "alphabet of {num_symbols} symbols is larger than the {MAX_SYMBOLS} symbols of adaptive arithmetic coding"
+            )));
+        }
+
+        let mut model = FenwickTree::new((0..num_symbols as u32).map(|c| (c, 1)).collect());
+        let mut encoder = RangeEncoder::new();
+
+        for &symbol in symbols {
+            let (symbol_low, symbol_high) = model.get_bounds(symbol);
+            let total_count = model.get_total_count() as u64;
+            encoder.encode(symbol_low as u64, symbol_high as u64, total_count);
+
+            update_symbol_model(&mut model, symbol);
+        }
+
+        let mut encoded = encode_varsize(symbols.len());
+        encoded.extend_from_slice(&encode_varsize(num_symbols));
+        encoded.extend_from_slice(&encoder.finish());
+
+        Ok(encoded)
+    }
+
+    /// decode symbols encoded with [`encode_adaptive_symbols`]
+    pub fn decode_adaptive_symbols(input: &[u8]) -> Result<Vec<u32>> {
+        let (size, mut i) = get_first_decoded(input)?;
+        let (num_symbols, last_byte_found) = get_first_decoded(&input[i..])?;
+        i += last_byte_found;
+
+        if num_symbols > MAX_SYMBOLS || (num_symbols == 0 && size > 0) {
+            return Err(CompressorError::InvalidData(format!(
+                "invalid alphabet of {num_symbols} symbols"
+            )));
+        }
+        check_decoded_size(size)?;
+
+        let mut model = FenwickTree::new((0..num_symbols as u32).map(|c| (c, 1)).collect());
+        let mut decoder = RangeDecoder::new(&input[i..]);
+
+        let mut decoded = Vec::new();
+        while decoded.len() < size {
+            let total_count = model.get_total_count() as u64;
+            let symbol = model
+                .search_range(decoder.target(total_count) as isize)
+                .ok_or_else(|| {
+                    CompressorError::InvalidData(String::from(
+                        "encoded value does not match any symbol",
+                    ))
+                })?;
+
+            let (symbol_low, symbol_high) = model.get_bounds(symbol);
+            decoder.consume(symbol_low as u64, symbol_high as u64, total_count)?;
+            decoded.push(symbol);
+
+            update_symbol_model(&mut model, symbol);
+        }
+
+        Ok(decoded)
+    }
+}
+
+/// First version of the arithmetic coder, with a precision growing with the input.
+///
+/// Every interval is computed with big numbers, which makes it very slow on anything larger than
+/// a few KB. It is only kept to read the data it encoded and to check [`ArithmeticEncoder`] against it.
+#[allow(non_snake_case)]
+pub(crate) mod BigUintArithmeticEncoder {
     use num_bigint::BigUint;
-    use std::collections::HashMap;
 
+    use crate::{
+        error::{CompressorError, Result},
+        fenwick_tree::FenwickTree,
+        varsize::get_first_decoded,
+    };
+
+    #[cfg(test)]
     use crate::{
         bit_queue::BitQueue,
         compressed_buffer::{Bit, CompressedBuffer},
-        fenwick_tree::FenwickTree,
-        varsize::{encode_varsize, get_first_decoded},
+        varsize::encode_varsize,
     };
+    #[cfg(test)]
+    use std::collections::HashMap;
 
     struct Bounderies {
         pub TOP_VALUE: BigUint,
@@ -17,6 +397,30 @@ pub mod ArithmeticEncoder {
         pub THIRD_QUARTER: BigUint,
     }
 
+    /// (bit index of the last byte, frequency of each char, encoded bytes)
+    #[cfg(test)]
+    type Encoded = (u8, Vec<(u8, isize)>, Vec<u8>);
+
+    /// Most bits of the bounderies: the intervals of each of the `size` symbols are computed with
+    /// numbers of that many bits, so decoding takes a time quadratic in the precision. The
+    /// frequencies read from the input are rejected past it, before the big numbers are allocated,
+    /// which only leaves a few KiB for the files written before the fixed precision.
+    const MAX_PRECISION: usize = 1 << 15;
+
+    /// number of bits used for the bounderies
+    ///
+    /// at least 2 bits are needed for the half to be in the initial range (a single byte input would have 1)
+    fn precision(size: usize, num_chars: usize) -> Result<usize> {
+        size.checked_mul(num_chars)
+            .filter(|&num_bits| num_bits <= MAX_PRECISION)
+            .map(|num_bits| num_bits.max(2))
+            .ok_or_else(|| {
+                CompressorError::InvalidData(format!(
+                    "arithmetic coding of {size} symbols among {num_chars} chars needs more than {MAX_PRECISION} bits"
+                ))
+            })
+    }
+
     impl Bounderies {
         pub fn new(num_bits: usize) -> Self {
             let mut TOP_VALUE = BigUint::from(1u8);
@@ -44,7 +448,12 @@ pub mod ArithmeticEncoder {
     ///   single chars in order,
     ///   the encoded indexes
     /// )
-    pub fn encode(input: &[u8]) -> (u8, Vec<(u8, isize)>, Vec<u8>) {
+    #[cfg(test)]
+    pub fn encode(input: &[u8]) -> Result<Encoded> {
+        if input.is_empty() {
+            return Ok((0, Vec::new(), Vec::new()));
+        }
+
         let mut frequency: HashMap<u8, usize> = HashMap::new();
 
         // get frequency
@@ -60,13 +469,13 @@ pub mod ArithmeticEncoder {
             frequency.iter().map(|(&k, &v)| (k, v as isize)).collect();
         // FIXME use proper sort (not alphabetical)
         // sorted_freq.sort_by(|a, b| a.1.cmp(&b.1));
-        sorted_freq.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted_freq.sort_by_key(|a| a.0);
 
         // NOTE possibly make mut if adaptative
         let cum_freq = FenwickTree::new(sorted_freq.clone());
 
         let size = cum_freq.total_sum() as usize;
-        let num_bits: usize = size * cum_freq.len();
+        let num_bits = precision(size, cum_freq.len())?;
         let bounderies = Bounderies::new(num_bits);
 
         let mut low = BigUint::ZERO;
@@ -83,7 +492,7 @@ pub mod ArithmeticEncoder {
                 println!("Insufficient precision to encode low-probability symbols.");
             }
 
-            assert!(&low <= &high && high <= bounderies.TOP_VALUE);
+            assert!(low <= high && high <= bounderies.TOP_VALUE);
             assert!(low < bounderies.HALF && bounderies.HALF <= high);
             assert!(&high - &low > bounderies.FIRST_QUARTER);
 
@@ -133,7 +542,11 @@ pub mod ArithmeticEncoder {
         bit_queue += 1;
 
         // end of encoding
-        let bit = if low > bounderies.FIRST_QUARTER { 1 } else { 0 };
+        let bit = if low >= bounderies.FIRST_QUARTER {
+            1
+        } else {
+            0
+        };
         let new_bits = bit_queue.bit_followed_by_inverted(bit);
         encoded.extend_from_slice(&new_bits);
 
@@ -145,20 +558,49 @@ pub mod ArithmeticEncoder {
 
         let encoded_buffer = compressed_buffer.get_buffer();
 
-        (
+        Ok((
             compressed_buffer.get_current_bit_index(),
             sorted_freq,
             encoded_buffer,
-        )
+        ))
     }
 
-    pub fn decode(last_byte_offset: u8, frequency: Vec<(u8, isize)>, encoded: &[u8]) -> Vec<u8> {
+    pub fn decode(
+        last_byte_offset: u8,
+        frequency: Vec<(u8, isize)>,
+        encoded: &[u8],
+    ) -> Result<Vec<u8>> {
         // let mut cummul = 0;
 
-        let cum_freq = FenwickTree::new(frequency);
+        if frequency.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        let size = cum_freq.total_sum() as usize;
-        let num_bits: usize = size * cum_freq.len();
+        if encoded.is_empty() {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        if last_byte_offset > 7 {
+            return Err(CompressorError::InvalidData(format!(
+                "invalid bit index {last_byte_offset} for the last byte"
+            )));
+        }
+
+        // the frequencies come from the input, their total must be checked before being summed up
+        let size = frequency
+            .iter()
+            .try_fold(0usize, |total, &(_, count)| {
+                usize::try_from(count)
+                    .ok()
+                    .and_then(|count| total.checked_add(count))
+            })
+            .ok_or_else(|| {
+                CompressorError::InvalidData(String::from("arithmetic coding frequencies overflow"))
+            })?;
+        let num_bits = precision(size, frequency.len())?;
+        let single_char = (frequency.len() == 1).then(|| frequency[0].0);
+
+        let cum_freq = FenwickTree::new(frequency);
         let bounderies = Bounderies::new(num_bits);
 
         let mut low = BigUint::ZERO;
@@ -168,9 +610,8 @@ pub mod ArithmeticEncoder {
         let mut value = BigUint::ZERO;
         let mut offset = 0;
 
-        // shifting the bits of every encoded bytes execpt the last one
-        let first_bytes = &encoded[0..encoded.len() - 1];
-        for &byte in first_bytes {
+        // shifting the bits of every encoded bytes, the unused bits of the last one are zeros
+        for &byte in encoded {
             for i in (0..8).rev() {
                 let bit = (byte >> i) & 1;
                 value = (value << 1) + bit as usize;
@@ -178,26 +619,36 @@ pub mod ArithmeticEncoder {
             }
         }
 
-        // shifting n bits of the last encoded bytes, where n is 8 - (num of unused bits + 1)
-        let last_byte = encoded.last().unwrap();
-        for i in (8 - (last_byte_offset + 1)..8).rev() {
-            let bit = (last_byte >> i) & 1;
-            value = (value << 1) + bit as usize;
-            offset += 1;
+        // shifting the value by the number of bits in order to scale the encoded value
+        if offset <= num_bits {
+            value <<= num_bits - offset;
+        } else {
+            // the unused bits of the last byte can go past the precision for very small inputs
+            let excess = offset - num_bits;
+            if value
+                .trailing_zeros()
+                .is_some_and(|zeros| zeros < excess as u64)
+            {
+                return Err(CompressorError::InvalidData(String::from(
+                    "more encoded bits than the frequencies allow",
+                )));
+            }
+            value >>= excess;
         }
 
-        // shifting the value by the number of bits in order to scale the encoded value
-        value <<= num_bits - offset;
+        // a single char takes the whole range, every symbol is that char
+        if let Some(c) = single_char {
+            return Ok(vec![c; size]);
+        }
 
         let mut decoded = Vec::new();
-        let mut i = 0;
         loop {
             let range = &high - &low + 1u8;
             let total_count = cum_freq.get_total_count() as usize;
 
             // scalling value
             let scaled_value: BigUint =
-                ((&value - &low + 1 as usize) * total_count - 1 as usize) / &range;
+                ((&value - &low + 1_usize) * total_count - 1_usize) / &range;
 
             let scaled_value = match scaled_value.trailing_zeros() {
                 Some(_) => {
@@ -207,7 +658,13 @@ pub mod ArithmeticEncoder {
                 None => 0,
             };
 
-            let symbol = cum_freq.search_range(scaled_value as isize).unwrap();
+            let symbol = cum_freq
+                .search_range(scaled_value as isize)
+                .ok_or_else(|| {
+                    CompressorError::InvalidData(String::from(
+                        "encoded value does not match any symbol",
+                    ))
+                })?;
             decoded.push(symbol);
 
             // updating high and low
@@ -239,9 +696,8 @@ pub mod ArithmeticEncoder {
                 // shift all bits one to the left
                 low *= 2u8;
                 high = 2u8 * high + 1u8;
-                i += 1;
-                let bit = if i < encoded.len() - 1 { encoded[i] } else { 0 };
-                value = 2 as usize * &value + bit as usize;
+                // every encoded bit is already in the value, only zeros follow
+                value *= 2u8;
             }
 
             // All the symbols are decoded
@@ -250,11 +706,12 @@ pub mod ArithmeticEncoder {
             }
         }
 
-        decoded
+        Ok(decoded)
     }
 
-    pub fn encode_with_metadatas(input: &[u8]) -> Vec<u8> {
-        let (bits_offset, frequency, encoded) = encode(input);
+    #[cfg(test)]
+    pub fn encode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        let (bits_offset, frequency, encoded) = encode(input)?;
 
         // convert frequency to a frequency with variable size
         let mut compressed_frequency = Vec::new();
@@ -275,23 +732,27 @@ pub mod ArithmeticEncoder {
         encoded_with_meta_datas.extend_from_slice(&compressed_frequency);
         encoded_with_meta_datas.extend_from_slice(&encoded);
 
-        encoded_with_meta_datas
+        Ok(encoded_with_meta_datas)
     }
 
-    pub fn decode_with_metadatas(input: &[u8]) -> Vec<u8> {
-        let last_byte_offset = input[0];
+    pub fn decode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        let last_byte_offset = *input.first().ok_or(CompressorError::TruncatedInput)?;
 
         // reformating the frequency
-        let (freq_size, last_byte_found) = get_first_decoded(&input[1..]);
+        let (freq_size, last_byte_found) = get_first_decoded(&input[1..])?;
         let mut frequency = Vec::new();
 
         let mut i = last_byte_found + 1;
-        while i < freq_size + 1 {
+        let freq_end = i
+            .checked_add(freq_size)
+            .filter(|&end| end <= input.len())
+            .ok_or(CompressorError::TruncatedInput)?;
+        while i < freq_end {
             // getting the character
             let c = input[i];
 
             // getting the frequency of the found character
-            let (num, last_byte_found) = get_first_decoded(&input[i + 1..]);
+            let (num, last_byte_found) = get_first_decoded(&input[i + 1..freq_end])?;
 
             // adding the frequency to the list and update the index
             frequency.push((c, num as isize));
@@ -301,9 +762,7 @@ pub mod ArithmeticEncoder {
         // remaining bytes are the encoded content
         let encoded = &input[i..];
 
-        let decoded = decode(last_byte_offset, frequency, encoded);
-
-        decoded
+        decode(last_byte_offset, frequency, encoded)
     }
 }
 
@@ -313,6 +772,10 @@ mod tests {
     use crate::algorithms::arithmetic_encoder::ArithmeticEncoder::{
         decode_with_metadatas, encode_with_metadatas,
     };
+    use crate::error::CompressorError;
+    use crate::test_utils::pseudo_random;
+    use crate::utils::MAX_BLOCK_SIZE;
+    use crate::varsize::encode_varsize;
 
     use super::*;
 
@@ -320,8 +783,8 @@ mod tests {
     fn arithmetic_simple_test() {
         let text: Vec<u8> = "RGGRRRGGGB\n".bytes().collect();
 
-        let (offset, freq, encoded) = ArithmeticEncoder::encode(&text);
-        let decoded = ArithmeticEncoder::decode(offset, freq, &encoded);
+        let (freq, encoded) = ArithmeticEncoder::encode(&text).unwrap();
+        let decoded = ArithmeticEncoder::decode(&freq, &encoded).unwrap();
 
         assert_eq!(text, decoded);
     }
@@ -337,23 +800,23 @@ mod tests {
             text.extend_from_slice(&echantillon);
         }
 
-        let (offset, freq, encoded) = ArithmeticEncoder::encode(&text);
-        let decoded = ArithmeticEncoder::decode(offset, freq, &encoded);
+        let (freq, encoded) = ArithmeticEncoder::encode(&text).unwrap();
+        let decoded = ArithmeticEncoder::decode(&freq, &encoded).unwrap();
 
         assert_eq!(text, decoded);
     }
 
     #[test]
     fn arithmetic_extensive_test() {
-        let echantillon: Vec<u8> = (0..256).into_iter().map(|x| x as u8).collect();
+        let echantillon: Vec<u8> = (0..256).map(|x| x as u8).collect();
         let mut text: Vec<u8> = Vec::new();
 
         for _ in 0..3 {
             text.extend_from_slice(&echantillon);
         }
 
-        let (offset, freq, encoded) = ArithmeticEncoder::encode(&text);
-        let decoded = ArithmeticEncoder::decode(offset, freq, &encoded);
+        let (freq, encoded) = ArithmeticEncoder::encode(&text).unwrap();
+        let decoded = ArithmeticEncoder::decode(&freq, &encoded).unwrap();
 
         assert_eq!(text, decoded);
     }
@@ -362,9 +825,215 @@ mod tests {
     fn arithmetic_encode_n_decode_with_metadatas() {
         let text: Vec<u8> = "RGGRRRGGGB\n".bytes().collect();
 
-        let encoded = encode_with_metadatas(&text);
-        let decoded = decode_with_metadatas(&encoded);
+        let encoded = encode_with_metadatas(&text).unwrap();
+        let decoded = decode_with_metadatas(&encoded).unwrap();
 
         assert_eq!(text, decoded);
     }
+
+    #[test]
+    fn arithmetic_empty_and_truncated() {
+        let encoded = encode_with_metadatas(&[]).unwrap();
+        assert!(decode_with_metadatas(&encoded).unwrap().is_empty());
+
+        // the last byte has more than 2 bits used
+        let block = [0, 1, 67, 1, 66, 1, 0, 2, 67, 1, 2, 1, 1, 1];
+        let encoded = encode_with_metadatas(&block).unwrap();
+        assert_eq!(&block, &decode_with_metadatas(&encoded).unwrap()[..]);
+
+        for input in [&[0u8][..], &[0xFF], &[7, 7]] {
+            let encoded = encode_with_metadatas(input).unwrap();
+            assert_eq!(input, &decode_with_metadatas(&encoded).unwrap()[..]);
+        }
+
+        let text: Vec<u8> = "RGGRRRGGGB\n".bytes().collect();
+        let encoded = encode_with_metadatas(&text).unwrap();
+
+        // frequency table cut in half
+        assert!(matches!(
+            decode_with_metadatas(&encoded[..6]),
+            Err(CompressorError::TruncatedInput)
+        ));
+        assert!(matches!(
+            decode_with_metadatas(&[]),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn arithmetic_same_as_big_uint() {
+        let echantillon: Vec<u8> = "AAAAACCCCadkjahsdkjashdkjashdjkashdkjashdCCCBBB"
+            .bytes()
+            .collect();
+        let text: Vec<u8> = echantillon.repeat(20);
+
+        let (offset, reference_freq, reference) = BigUintArithmeticEncoder::encode(&text).unwrap();
+        let (freq, encoded) = ArithmeticEncoder::encode(&text).unwrap();
+
+        // same model, the fixed precision only costs a few bits
+        assert_eq!(reference_freq, freq);
+        assert!(encoded.len() <= reference.len() + 1);
+        assert_eq!(
+            text,
+            BigUintArithmeticEncoder::decode(offset, reference_freq, &reference).unwrap()
+        );
+    }
+
+    #[test]
+    fn arithmetic_decode_big_uint_format() {
+        let text: Vec<u8> = "RGGRRRGGGB\n".bytes().collect();
+
+        for input in [&text[..], &[], &[0xFF]] {
+            let encoded = BigUintArithmeticEncoder::encode_with_metadatas(input).unwrap();
+            assert_eq!(input, &decode_with_metadatas(&encoded).unwrap()[..]);
+        }
+
+        assert!(matches!(
+            decode_with_metadatas(&[9, 0]),
+            Err(CompressorError::InvalidData(_))
+        ));
+
+        // a single char decoded without reading any bit, as many times as the frequency says
+        for count in [MAX_BLOCK_SIZE + 1, 1 << 34] {
+            let mut encoded = vec![8, 0, b'a'];
+            encoded.extend_from_slice(&encode_varsize(count));
+            encoded[1] = (encoded.len() - 2) as u8;
+            encoded.push(0);
+            assert!(matches!(
+                decode_with_metadatas(&encoded),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+
+        // frequencies asking for a precision of too many bits to decode quickly, or overflowing
+        for count in [100_000, 1 << 40, usize::MAX / 2, usize::MAX] {
+            let mut frequency = vec![b'a'];
+            frequency.extend_from_slice(&encode_varsize(count));
+            frequency.push(b'b');
+            frequency.extend_from_slice(&encode_varsize(count));
+
+            let mut encoded = vec![0];
+            encoded.extend_from_slice(&encode_varsize(frequency.len()));
+            encoded.extend_from_slice(&frequency);
+            encoded.push(0x55);
+            assert!(matches!(
+                decode_with_metadatas(&encoded),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn arithmetic_large_input() {
+        // pseudo random bytes, the frequencies are too large for the range coder and get scaled
+        let text: Vec<u8> = pseudo_random(42)
+            .take(1 << 21)
+            .map(|seed| ((seed >> 16) % 11) as u8 * ((seed >> 28) as u8 + 1))
+            .collect();
+
+        let encoded = encode_with_metadatas(&text).unwrap();
+        assert!(encoded.len() < text.len());
+        assert_eq!(text, decode_with_metadatas(&encoded).unwrap());
+    }
+
+    #[test]
+    fn adaptive_arithmetic_round_trip() {
+        let mut texts: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            vec![0],
+            vec![0xFF; 5000],
+            "RGGRRRGGGB\n".bytes().collect(),
+            (0..=255).cycle().take(3 * 256).collect(),
+        ];
+
+        // pseudo random bytes, the counts get halved along the way
+        texts.push(
+            pseudo_random(42)
+                .take(200_000)
+                .map(|seed| ((seed >> 16) % 7) as u8 * 31)
+                .collect(),
+        );
+
+        for text in texts {
+            let encoded = ArithmeticEncoder::encode_adaptive(&text).unwrap();
+            assert_eq!(text, ArithmeticEncoder::decode_adaptive(&encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn adaptive_arithmetic_no_frequency_table() {
+        let text: Vec<u8> = "RGGRRRGGGB\n".bytes().collect();
+
+        // smaller than the static coder on small inputs
+        let adaptive = ArithmeticEncoder::encode_adaptive(&text).unwrap();
+        let with_table = encode_with_metadatas(&text).unwrap();
+        assert!(adaptive.len() < with_table.len());
+
+        // a long run ends up costing a fraction of a bit per byte
+        let encoded = ArithmeticEncoder::encode_adaptive(&[b'A'; 10_000]).unwrap();
+        assert!(encoded.len() < 250, "{}", encoded.len());
+    }
+
+    #[test]
+    fn adaptive_arithmetic_truncated() {
+        let text: Vec<u8> = "AAAAACCCCadkjahsdkjashdkjashdjkashdkjashdCCCBBB"
+            .bytes()
+            .collect();
+        let encoded = ArithmeticEncoder::encode_adaptive(&text).unwrap();
+
+        assert!(matches!(
+            ArithmeticEncoder::decode_adaptive(&encoded[..10]),
+            Err(CompressorError::TruncatedInput)
+        ));
+        assert!(matches!(
+            ArithmeticEncoder::decode_adaptive(&[]),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn adaptive_arithmetic_oversized() {
+        for size in [MAX_BLOCK_SIZE + 1, usize::MAX] {
+            let mut encoded = encode_varsize(size);
+            encoded.push(0);
+            assert!(matches!(
+                ArithmeticEncoder::decode_adaptive(&encoded),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            let mut encoded = encode_varsize(size);
+            encoded.extend_from_slice(&encode_varsize(2));
+            encoded.push(0);
+            assert!(matches!(
+                ArithmeticEncoder::decode_adaptive_symbols(&encoded),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn adaptive_arithmetic_symbols() {
+        // pseudo random codes of a large dictionary, the counts get halved along the way
+        let codes: Vec<u32> = pseudo_random(42)
+            .take(100_000)
+            .map(|seed| (seed >> 16) % 300 * ((seed >> 30) + 1) * 50)
+            .collect();
+
+        for symbols in [Vec::new(), vec![0], vec![70_000; 1000], codes] {
+            let encoded = ArithmeticEncoder::encode_adaptive_symbols(&symbols).unwrap();
+            assert_eq!(
+                symbols,
+                ArithmeticEncoder::decode_adaptive_symbols(&encoded).unwrap()
+            );
+        }
+
+        assert!(matches!(
+            ArithmeticEncoder::encode_adaptive_symbols(&[ArithmeticEncoder::MAX_SYMBOLS as u32]),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ArithmeticEncoder::decode_adaptive_symbols(&[1, 0]),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
 }
@@ -1,86 +1,84 @@
 #[allow(non_snake_case)]
 pub mod BurrowsWheeler {
 
-    pub fn encode(input: &[u8]) -> (usize, Vec<u8>) {
-        let lenght = input.len();
-        let mut table = vec![Vec::<u8>::with_capacity(lenght); lenght];
-
-        let mut current_row: Vec<u8> = input.iter().map(|&c| c).collect();
-        for i in 0..lenght {
-            // adding current_row to table
-            table[i] = current_row.clone();
-
-            // updating current_row
-            let first_value = current_row[0];
-            for i in 0..lenght - 1 {
-                current_row[i] = current_row[i + 1];
-            }
+    use std::collections::HashMap;
 
-            // updating last value
-            let last_index = lenght - 1;
-            current_row[last_index] = first_value;
-        }
+    use rayon::prelude::*;
 
-        // sort lexicographically per default
-        table.sort();
+    use crate::{
+        error::{CompressorError, Result},
+        suffix_array::suffix_array,
+        varsize::{encode_varsize, get_first_decoded},
+    };
 
-        let index = table.iter().position(|row| row == input).unwrap();
+    /// order of the rotations of the input, without building them
+    ///
+    /// the rotations starting at i < n of the input doubled are sorted like its suffixes
+    /// starting at i, as their first n chars are the same
+    fn sorted_rotations(input: &[u8]) -> Vec<usize> {
+        let lenght = input.len();
 
-        let transformed: Vec<u8> = table.iter().map(|row| row[lenght - 1]).collect();
+        let mut doubled = Vec::with_capacity(2 * lenght);
+        doubled.extend_from_slice(input);
+        doubled.extend_from_slice(input);
 
-        (index, transformed)
+        suffix_array(&doubled)
+            .into_iter()
+            .filter(|&i| i < lenght)
+            .collect()
     }
 
-    use std::{
-        collections::HashMap,
-        sync::{Arc, Mutex},
-    };
+    /// @**returns** (usize, Vec\<u8\>) => (row of the input in the sorted rotations, last column of the sorted rotations)
+    pub fn encode(input: &[u8]) -> Result<(usize, Vec<u8>)> {
+        let lenght = input.len();
+        if lenght == 0 {
+            return Ok((0, Vec::new()));
+        }
 
-    use rayon::prelude::*;
+        let rotations = sorted_rotations(input);
 
-    use crate::varsize::{encode_varsize, get_first_decoded};
+        // the input is the rotation starting at 0
+        let index = rotations.iter().position(|&i| i == 0).unwrap();
 
-    /// same functionality but works in parallel
-    pub fn encode_par(input: &[u8]) -> (usize, Vec<u8>) {
-        let lenght = input.len();
-
-        // per-row mutexes to reduce contention
-        let table: Vec<Arc<Mutex<Vec<u8>>>> = (0..lenght)
-            .map(|_| Arc::new(Mutex::new(input.to_vec())))
-            .collect();
-        let table = Arc::new(table);
-
-        // NOTE: could possibly use num_cpus::get_physical();
-        // let optimal_threads = num_cpus::get_physical();
-        let optimal_threads = num_cpus::get();
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(optimal_threads) // Set optimal for your CPU
-            .build()
-            .unwrap();
-
-        // updating rows in parallel
-        (0..lenght).into_par_iter().for_each(|i| {
-            let mut row = table[i].lock().unwrap();
-            row.rotate_left(i);
-        });
-
-        // OPTIMIZE possible unecessary table copy
-        let mut table: Vec<Vec<u8>> = table
+        let transformed: Vec<u8> = rotations
             .iter()
-            .map(|row| row.lock().unwrap().clone())
+            .map(|&i| input[(i + lenght - 1) % lenght])
             .collect();
 
-        table.sort();
+        Ok((index, transformed))
+    }
 
-        let index = table.iter().position(|row| row == input).unwrap();
+    /// same functionality but the last column is gathered in parallel
+    pub fn encode_par(input: &[u8]) -> Result<(usize, Vec<u8>)> {
+        let lenght = input.len();
+        if lenght == 0 {
+            return Ok((0, Vec::new()));
+        }
 
-        let transformed: Vec<u8> = table.iter().map(|row| row[lenght - 1]).collect();
+        let rotations = sorted_rotations(input);
 
-        (index, transformed)
+        // the input is the rotation starting at 0
+        let index = rotations.par_iter().position_any(|&i| i == 0).unwrap();
+
+        let transformed: Vec<u8> = rotations
+            .par_iter()
+            .map(|&i| input[(i + lenght - 1) % lenght])
+            .collect();
+
+        Ok((index, transformed))
     }
 
-    pub fn decode(index: usize, input: &[u8]) -> Vec<u8> {
+    pub fn decode(index: usize, input: &[u8]) -> Result<Vec<u8>> {
         let lenght = input.len();
+        if lenght == 0 {
+            return Ok(Vec::new());
+        }
+
+        if index >= lenght {
+            return Err(CompressorError::InvalidData(format!(
+                "burrows-wheeler row index {index} is out of bounds for {lenght} rows"
+            )));
+        }
 
         // counting every occurence in order
         let mut last_col_freq: Vec<(u8, usize)> = Vec::with_capacity(lenght);
@@ -121,14 +119,14 @@ pub mod BurrowsWheeler {
             last_col_index = first_col_index;
         }
 
-        decoded
+        Ok(decoded)
     }
 
-    pub fn encode_with_metadata(input: &[u8], parallel: bool) -> Vec<u8> {
+    pub fn encode_with_metadata(input: &[u8], parallel: bool) -> Result<Vec<u8>> {
         let (row_index, encoded_input) = if parallel {
-            encode_par(&input)
+            encode_par(input)?
         } else {
-            encode(&input)
+            encode(input)?
         };
 
         // adding index row at the beginning
@@ -136,26 +134,14 @@ pub mod BurrowsWheeler {
 
         encoded.extend_from_slice(&encoded_input);
 
-        encoded
+        Ok(encoded)
     }
 
-    pub fn decode_with_metadata(input: &[u8]) -> Vec<u8> {
-        let (index, index_end_found) = get_first_decoded(&input);
+    pub fn decode_with_metadata(input: &[u8]) -> Result<Vec<u8>> {
+        let (index, index_end_found) = get_first_decoded(input)?;
         let encoded = &input[index_end_found..];
         decode(index, encoded)
     }
-
-    /// DEBUG function
-    #[allow(dead_code)]
-    fn print_table(table: &Vec<Vec<u8>>) {
-        for row in table {
-            print!("[");
-            for c in row {
-                print!("'{}',", *c as char);
-            }
-            println!("]");
-        }
-    }
 }
 
 #[cfg(test)]
@@ -165,10 +151,29 @@ mod tests {
 
     use super::*;
 
+    /// reference implementation sorting every rotation of the input
+    fn encode_rotations(input: &[u8]) -> (usize, Vec<u8>) {
+        let lenght = input.len();
+
+        let mut table: Vec<Vec<u8>> = (0..lenght)
+            .map(|i| {
+                let mut row = input.to_vec();
+                row.rotate_left(i);
+                row
+            })
+            .collect();
+        table.sort();
+
+        let index = table.iter().position(|row| row == input).unwrap_or(0);
+        let transformed = table.iter().map(|row| row[lenght - 1]).collect();
+
+        (index, transformed)
+    }
+
     #[test]
     fn burrows_wheeler_encode() {
         let text: Vec<u8> = "BANANA".bytes().collect();
-        let (index, transformed) = BurrowsWheeler::encode(&text);
+        let (index, transformed) = BurrowsWheeler::encode(&text).unwrap();
 
         let result: Vec<u8> = "NNBAAA".bytes().collect();
         assert_eq!((3, result), (index, transformed));
@@ -177,12 +182,52 @@ mod tests {
     #[test]
     fn burrows_wheeler_encode_sync() {
         let text: Vec<u8> = "BANANA".bytes().collect();
-        let (index, transformed) = BurrowsWheeler::encode_par(&text);
+        let (index, transformed) = BurrowsWheeler::encode_par(&text).unwrap();
 
         let result: Vec<u8> = "NNBAAA".bytes().collect();
         assert_eq!((3, result), (index, transformed));
     }
 
+    #[test]
+    fn burrows_wheeler_same_as_rotations() {
+        let texts = [
+            "BANANA",
+            "mississippi",
+            "AAABBCCDACCAA BANANA\0BANDANA \u{FF}",
+            "abababababab",
+            "aaaaaaaa",
+            "a",
+        ];
+
+        for text in texts {
+            let text: Vec<u8> = text.bytes().collect();
+            let (expected_index, expected) = encode_rotations(&text);
+
+            for (index, transformed) in [
+                BurrowsWheeler::encode(&text).unwrap(),
+                BurrowsWheeler::encode_par(&text).unwrap(),
+            ] {
+                assert_eq!(expected, transformed);
+
+                // a periodic input is in several rows, any of them decodes to the input
+                assert_eq!(expected[index], expected[expected_index]);
+                assert_eq!(text, BurrowsWheeler::decode(index, &transformed).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn burrows_wheeler_large_input() {
+        let text: Vec<u8> = (0..1_000_000u32)
+            .map(|i| (i % 7 * (i % 13)) as u8)
+            .collect();
+
+        let encoded = BurrowsWheeler::encode_with_metadata(&text, true).unwrap();
+        let decoded = BurrowsWheeler::decode_with_metadata(&encoded).unwrap();
+
+        assert_eq!(text, decoded);
+    }
+
     #[test]
     fn bwt_encode_benchmark() {
         // comment to test
@@ -199,18 +244,18 @@ mod tests {
 
         // ENCODING
         let start = Instant::now();
-        let encoded = BurrowsWheeler::encode(&text);
+        let encoded = BurrowsWheeler::encode(&text).unwrap();
         let duration_seq = start.elapsed();
         println!("Time elapsed for sequential encoding: {:?}", duration_seq);
 
         let start = Instant::now();
-        let _encoded_par = BurrowsWheeler::encode_par(&text);
+        let _encoded_par = BurrowsWheeler::encode_par(&text).unwrap();
         let duration_seq = start.elapsed();
         println!("Time elapsed for parallel encoding: {:?}", duration_seq);
 
         // DECODING
         let start = Instant::now();
-        let _ = BurrowsWheeler::decode(encoded.0, &encoded.1);
+        let _ = BurrowsWheeler::decode(encoded.0, &encoded.1).unwrap();
         let duration_seq = start.elapsed();
         println!("Time elapsed for sequential decoding: {:?}", duration_seq);
     }
@@ -218,13 +263,13 @@ mod tests {
     #[test]
     fn burrows_wheeler_decode() {
         let text: Vec<u8> = "NNBAAA".bytes().collect();
-        let transformed = BurrowsWheeler::decode(3, &text);
+        let transformed = BurrowsWheeler::decode(3, &text).unwrap();
         let result: Vec<u8> = "BANANA".bytes().collect();
         assert_eq!(result, transformed);
 
         let text: Vec<u8> = "ACAACG".bytes().collect();
-        let (index, encoded) = BurrowsWheeler::encode(&text);
-        let decoded = BurrowsWheeler::decode(index, &encoded);
+        let (index, encoded) = BurrowsWheeler::encode(&text).unwrap();
+        let decoded = BurrowsWheeler::decode(index, &encoded).unwrap();
         assert_eq!(text, decoded);
     }
 
@@ -233,13 +278,22 @@ mod tests {
         let text: Vec<u8> = "BANANA".bytes().collect();
 
         // sequential
-        let encoded = BurrowsWheeler::encode_with_metadata(&text, false);
-        let decoded = BurrowsWheeler::decode_with_metadata(&encoded);
+        let encoded = BurrowsWheeler::encode_with_metadata(&text, false).unwrap();
+        let decoded = BurrowsWheeler::decode_with_metadata(&encoded).unwrap();
         assert_eq!(text, decoded);
 
         // parallel
-        let encoded = BurrowsWheeler::encode_with_metadata(&text, true);
-        let decoded = BurrowsWheeler::decode_with_metadata(&encoded);
+        let encoded = BurrowsWheeler::encode_with_metadata(&text, true).unwrap();
+        let decoded = BurrowsWheeler::decode_with_metadata(&encoded).unwrap();
         assert_eq!(text, decoded);
     }
+
+    #[test]
+    fn burrows_wheeler_decode_invalid_index() {
+        let text: Vec<u8> = "NNBAAA".bytes().collect();
+        assert!(BurrowsWheeler::decode(6, &text).is_err());
+
+        let encoded = [0x86];
+        assert!(BurrowsWheeler::decode_with_metadata(&encoded).is_err());
+    }
 }
@@ -0,0 +1,771 @@
+#[allow(non_snake_case)]
+pub mod Bzip2 {
+    use std::{
+        io::{self, BufRead, Read, Write},
+        mem,
+    };
+
+    use rayon::prelude::*;
+
+    use crate::{
+        algorithms::{
+            burrows_wheeler::BurrowsWheeler, move_to_front::MoveToFront,
+            zero_run_length::ZeroRunLength,
+        },
+        bit_io::MsbStreamReader,
+        canonical_huffman::{CanonicalDecoder, canonical_codes, code_lengths},
+        checksum::{crc32_bzip2, crc32_bzip2_continue},
+        compressed_buffer::CompressedBuffer,
+        error::{CompressorError, Result, warn_or_fail},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://github.com/dsnet/compress/blob/master/doc/bzip2-format.pdf
+    // https://en.wikipedia.org/wiki/Bzip2
+
+    /// first bytes of a bzip2 stream, followed by the level
+    pub const MAGIC: [u8; 3] = *b"BZh";
+
+    /// blocks of 900k bytes, the same as `bzip2`
+    pub const DEFAULT_LEVEL: u8 = 9;
+
+    /// first 48 bits of a block (BCD of pi) and of the end of the stream (BCD of sqrt(pi))
+    const BLOCK_MAGIC: u64 = 0x3141_5926_5359;
+    const END_MAGIC: u64 = 0x1772_4538_5090;
+
+    /// digits of the length of a run of zeros, in bijective base 2
+    const RUNA: u16 = ZeroRunLength::RUNA as u16;
+    const RUNB: u16 = ZeroRunLength::RUNB as u16;
+
+    /// number of symbols coded with the same Huffman table
+    const GROUP_SIZE: usize = 50;
+
+    const MIN_TABLES: usize = 2;
+    const MAX_TABLES: usize = 6;
+
+    /// longest codes written, `bunzip2` reads codes of up to 20 bits
+    const MAX_CODE_LENGTH: u8 = 17;
+    const MAX_READ_CODE_LENGTH: u32 = 20;
+
+    /// passes choosing the table of each group, then building the tables from the groups
+    const TABLE_ITERATIONS: usize = 4;
+
+    /// return the level if it is from 1 to 9
+    pub fn check_level(level: u8) -> Result<u8> {
+        if !(1..=9).contains(&level) {
+            return Err(CompressorError::InvalidArgument(format!(
+                "bzip2 level {level} is not between 1 and 9"
+            )));
+        }
+
+        Ok(level)
+    }
+
+    /// largest size of a block after the initial run-length encoding
+    fn max_block_size(level: u8) -> usize {
+        100_000 * level as usize - 19
+    }
+
+    fn undo_initial_run_length(block: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::with_capacity(block.len());
+
+        let mut last = None;
+        let mut run = 0;
+        for &byte in block {
+            if run == 4 {
+                decoded.extend(std::iter::repeat_n(last.unwrap_or_default(), byte as usize));
+                run = 0;
+                continue;
+            }
+
+            if Some(byte) == last {
+                run += 1;
+            } else {
+                last = Some(byte);
+                run = 1;
+            }
+            decoded.push(byte);
+        }
+
+        decoded
+    }
+
+    /// A block ready to be written
+    struct Block {
+        crc: u32,
+        /// row of the block in its sorted rotations
+        orig_ptr: usize,
+        in_use: [bool; 256],
+        symbols: Vec<u16>,
+        /// code lengths of each table
+        tables: Vec<Vec<u8>>,
+        /// table of each group of symbols
+        selectors: Vec<u8>,
+    }
+
+    /// Code lengths of the tables and table of each group of 50 symbols.
+    ///
+    /// The tables start by each covering a range of symbols of about the same total frequency, then
+    /// every group picks its cheapest table and the tables are built again from their groups.
+    fn tables(symbols: &[u16], alpha_size: usize) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let num_tables = match symbols.len() {
+            0..200 => 2,
+            200..600 => 3,
+            600..1200 => 4,
+            1200..2400 => 5,
+            _ => MAX_TABLES,
+        };
+
+        let mut frequencies = vec![0usize; alpha_size];
+        for &symbol in symbols {
+            frequencies[symbol as usize] += 1;
+        }
+
+        // cheap symbols of each table
+        let mut tables = Vec::with_capacity(num_tables);
+        let mut remaining = symbols.len();
+        let mut start = 0;
+        for table in 0..num_tables {
+            let target = remaining / (num_tables - table);
+            let mut end = start;
+            let mut total = 0;
+            while end < alpha_size && (total < target || end == start) {
+                total += frequencies[end];
+                end += 1;
+            }
+            if table == num_tables - 1 {
+                end = alpha_size;
+            }
+
+            let lengths = (0..alpha_size)
+                .map(|symbol| {
+                    if (start..end).contains(&symbol) {
+                        0
+                    } else {
+                        15
+                    }
+                })
+                .collect::<Vec<u8>>();
+            tables.push(lengths);
+
+            remaining = remaining.saturating_sub(total);
+            start = end;
+        }
+
+        let mut selectors = vec![0u8; symbols.len().div_ceil(GROUP_SIZE)];
+        for _ in 0..TABLE_ITERATIONS {
+            let mut table_frequencies = vec![vec![0usize; alpha_size]; num_tables];
+
+            for (group, selector) in symbols.chunks(GROUP_SIZE).zip(selectors.iter_mut()) {
+                let cost = |lengths: &Vec<u8>| -> usize {
+                    group
+                        .iter()
+                        .map(|&symbol| lengths[symbol as usize] as usize)
+                        .sum()
+                };
+                let best = (0..num_tables)
+                    .min_by_key(|&table| cost(&tables[table]))
+                    .expect("at least 2 tables");
+
+                *selector = best as u8;
+                for &symbol in group {
+                    table_frequencies[best][symbol as usize] += 1;
+                }
+            }
+
+            // every symbol needs a code
+            tables = table_frequencies
+                .iter()
+                .map(|frequencies| {
+                    let frequencies: Vec<usize> = frequencies
+                        .iter()
+                        .map(|&frequency| frequency.max(1))
+                        .collect();
+                    code_lengths(&frequencies, MAX_CODE_LENGTH)
+                })
+                .collect();
+        }
+
+        (tables, selectors)
+    }
+
+    /// @**returns** Block => the block ready to be written, `crc` being the CRC of its bytes before
+    /// the initial run-length encoding
+    fn compress_block(crc: u32, block: &[u8]) -> Result<Block> {
+        let (orig_ptr, transformed) = BurrowsWheeler::encode(block)?;
+
+        let mut in_use = [false; 256];
+        for &byte in block {
+            in_use[byte as usize] = true;
+        }
+
+        // bytes in use numbered in order, MTF then starts from them in order
+        let mut numbers = [0u8; 256];
+        let mut num_in_use = 0;
+        for byte in 0..256 {
+            if in_use[byte] {
+                numbers[byte] = num_in_use as u8;
+                num_in_use += 1;
+            }
+        }
+        let numbered: Vec<u8> = transformed
+            .iter()
+            .map(|&byte| numbers[byte as usize])
+            .collect();
+
+        let mtf = MoveToFront::encode(&numbered)?;
+        let alpha_size = num_in_use + 2;
+        // the symbols of the bytes in use, RUNA, RUNB and EOB
+        let symbols = ZeroRunLength::encode_runs(&mtf, (alpha_size - 1) as u16);
+        let (tables, selectors) = tables(&symbols, alpha_size);
+
+        Ok(Block {
+            crc,
+            orig_ptr,
+            in_use,
+            symbols,
+            tables,
+            selectors,
+        })
+    }
+
+    fn write_block(buffer: &mut CompressedBuffer, block: &Block) {
+        buffer.push_bits(BLOCK_MAGIC as usize, 48);
+        buffer.push_bits(block.crc as usize, 32);
+        // not randomized
+        buffer.push_bits(0, 1);
+        buffer.push_bits(block.orig_ptr, 24);
+
+        // bytes in use: which ranges of 16 bytes, then which bytes of these ranges
+        let ranges: Vec<&[bool]> = block.in_use.chunks(16).collect();
+        for range in &ranges {
+            buffer.push_bits(range.contains(&true) as usize, 1);
+        }
+        for range in ranges.iter().filter(|range| range.contains(&true)) {
+            for &used in *range {
+                buffer.push_bits(used as usize, 1);
+            }
+        }
+
+        buffer.push_bits(block.tables.len(), 3);
+        buffer.push_bits(block.selectors.len(), 15);
+        let mut tables: Vec<u8> = (0..block.tables.len() as u8).collect();
+        for &selector in &block.selectors {
+            // MTF of the tables, in unary
+            let position = tables.iter().position(|&table| table == selector).unwrap();
+            buffer.push_bits((1 << (position + 1)) - 2, position as u32 + 1);
+            tables[..=position].rotate_right(1);
+        }
+
+        // code lengths as differences from the previous one
+        for lengths in &block.tables {
+            let mut current = lengths[0];
+            buffer.push_bits(current as usize, 5);
+            for &length in lengths {
+                while current < length {
+                    buffer.push_bits(0b10, 2);
+                    current += 1;
+                }
+                while current > length {
+                    buffer.push_bits(0b11, 2);
+                    current -= 1;
+                }
+                buffer.push_bits(0, 1);
+            }
+        }
+
+        let codes: Vec<Vec<u32>> = block
+            .tables
+            .iter()
+            .map(|lengths| canonical_codes(lengths))
+            .collect();
+        for (group, &selector) in block.symbols.chunks(GROUP_SIZE).zip(&block.selectors) {
+            let (codes, lengths) = (&codes[selector as usize], &block.tables[selector as usize]);
+            for &symbol in group {
+                let symbol = symbol as usize;
+                buffer.push_bits(codes[symbol] as usize, lengths[symbol] as u32);
+            }
+        }
+    }
+
+    /// encode an array of bytes as a bzip2 stream, readable by `bunzip2`
+    ///
+    /// The level sets the size of the blocks sorted by the Burrows-Wheeler transform, from 100k to
+    /// 900k bytes.
+    ///
+    /// ```text
+    /// [BZh][level] ([block magic][CRC][row][bytes in use][tables][selectors][symbols])... [end magic][CRC of the stream]
+    /// ```
+    pub fn encode(input: &[u8], level: u8) -> Result<Vec<u8>> {
+        let mut writer = Bzip2Writer::new(Vec::new(), level)?;
+        writer.write_all(input)?;
+        writer.finish()
+    }
+
+    /// Compress everything written to it as a bzip2 stream, as [`encode`] does.
+    ///
+    /// Runs of 4 to 255 identical bytes are written as 4 bytes followed by the number of bytes left,
+    /// filling blocks of 100k to 900k bytes depending on the level. As many blocks as there are
+    /// threads in the rayon pool are compressed in parallel, so the memory used only depends on the
+    /// level. [`Bzip2Writer::finish`] must be called to write the end of the stream, dropping the
+    /// writer also does it but ignores the errors.
+    pub struct Bzip2Writer<W: Write> {
+        /// None once finished
+        inner: Option<W>,
+        level: u8,
+        /// block being filled, after the initial run-length encoding
+        block: Vec<u8>,
+        /// CRC of the bytes of the block before the initial run-length encoding
+        block_crc: u32,
+        /// byte of the run being read and its length, 0 before the first byte
+        run: (u8, usize),
+        /// full blocks waiting to be compressed together, with their CRC
+        pending: Vec<(u32, Vec<u8>)>,
+        /// blocks written so far, the last byte being partially filled
+        buffer: CompressedBuffer,
+        stream_crc: u32,
+    }
+
+    impl<W: Write> Bzip2Writer<W> {
+        /// write the header of the stream, the level going from 1 (100k blocks) to 9 (900k blocks)
+        pub fn new(mut inner: W, level: u8) -> Result<Self> {
+            let level = check_level(level)?;
+
+            inner.write_all(&MAGIC)?;
+            inner.write_all(&[b'0' + level])?;
+
+            Ok(Bzip2Writer {
+                inner: Some(inner),
+                level,
+                block: Vec::new(),
+                block_crc: 0,
+                run: (0, 0),
+                pending: Vec::new(),
+                buffer: CompressedBuffer::new(),
+                stream_crc: 0,
+            })
+        }
+
+        /// compress the remaining data, write the end of the stream and return the inner writer
+        pub fn finish(mut self) -> Result<W> {
+            self.write_end()?;
+
+            Ok(self.inner.take().expect("the writer is only finished once"))
+        }
+
+        fn inner(&mut self) -> &mut W {
+            self.inner
+                .as_mut()
+                .expect("the writer is not used after being finished")
+        }
+
+        fn push(&mut self, byte: u8) -> Result<()> {
+            let (run_byte, run_length) = self.run;
+            if run_length > 0 && byte == run_byte && run_length < 255 {
+                self.run.1 += 1;
+                return Ok(());
+            }
+
+            self.end_run();
+            if self.block.len() >= max_block_size(self.level) {
+                self.end_block()?;
+            }
+            self.run = (byte, 1);
+
+            Ok(())
+        }
+
+        /// add the run being read to the block
+        fn end_run(&mut self) {
+            let (byte, run) = mem::take(&mut self.run);
+            if run == 0 {
+                return;
+            }
+
+            if run < 4 {
+                self.block.extend(std::iter::repeat_n(byte, run));
+            } else {
+                self.block.extend_from_slice(&[byte; 4]);
+                self.block.push((run - 4) as u8);
+            }
+            self.block_crc = crc32_bzip2_continue(self.block_crc, &[byte; 255][..run]);
+        }
+
+        fn end_block(&mut self) -> Result<()> {
+            let crc = mem::take(&mut self.block_crc);
+            self.pending.push((crc, mem::take(&mut self.block)));
+
+            if self.pending.len() >= rayon::current_num_threads() {
+                self.write_pending()?;
+            }
+
+            Ok(())
+        }
+
+        /// compress the pending blocks in parallel and write them
+        fn write_pending(&mut self) -> Result<()> {
+            let blocks = mem::take(&mut self.pending)
+                .par_iter()
+                .map(|(crc, block)| compress_block(*crc, block))
+                .collect::<Result<Vec<_>>>()?;
+
+            for block in &blocks {
+                write_block(&mut self.buffer, block);
+                self.stream_crc = self.stream_crc.rotate_left(1) ^ block.crc;
+            }
+
+            let bytes = self.buffer.take_bytes();
+            self.inner().write_all(&bytes)?;
+
+            Ok(())
+        }
+
+        fn write_end(&mut self) -> Result<()> {
+            self.end_run();
+            if !self.block.is_empty() {
+                self.end_block()?;
+            }
+            self.write_pending()?;
+
+            self.buffer.push_bits(END_MAGIC as usize, 48);
+            self.buffer.push_bits(self.stream_crc as usize, 32);
+
+            let bytes = mem::take(&mut self.buffer.buffer);
+            self.inner().write_all(&bytes)?;
+            self.inner().flush()?;
+
+            Ok(())
+        }
+    }
+
+    impl<W: Write> Write for Bzip2Writer<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for &byte in buf {
+                self.push(byte)?;
+            }
+
+            Ok(buf.len())
+        }
+
+        /// end the block being filled, compress the pending ones and flush the inner writer
+        fn flush(&mut self) -> io::Result<()> {
+            self.end_run();
+            if !self.block.is_empty() {
+                self.end_block()?;
+            }
+            self.write_pending()?;
+            self.inner().flush()
+        }
+    }
+
+    impl<W: Write> Drop for Bzip2Writer<W> {
+        fn drop(&mut self) {
+            if self.inner.is_some() {
+                let _ = self.write_end();
+            }
+        }
+    }
+
+    fn read_u48(reader: &mut MsbStreamReader<impl Read>) -> Result<u64> {
+        let high = reader.read_bits(24)? as u64;
+        let low = reader.read_bits(24)? as u64;
+
+        Ok(high << 24 | low)
+    }
+
+    /// decode the block following its magic
+    ///
+    /// @**returns** (u32, Vec\<u8\>) => (CRC written before the block, decoded block)
+    fn decode_block(reader: &mut MsbStreamReader<impl Read>, level: u8) -> Result<(u32, Vec<u8>)> {
+        let invalid = |message: &str| CompressorError::InvalidData(format!("bzip2 {message}"));
+
+        let expected = reader.read_bits(32)?;
+        if reader.read_bit()? == 1 {
+            return Err(invalid("randomized blocks are not supported"));
+        }
+        let orig_ptr = reader.read_bits(24)? as usize;
+
+        let ranges = reader.read_bits(16)?;
+        let mut in_use = Vec::new();
+        for range in (0..16).filter(|range| ranges >> (15 - range) & 1 == 1) {
+            let used = reader.read_bits(16)?;
+            in_use.extend(
+                (0..16)
+                    .filter(|byte| used >> (15 - byte) & 1 == 1)
+                    .map(|byte| (range * 16 + byte) as u8),
+            );
+        }
+        if in_use.is_empty() {
+            return Err(invalid("block without any byte"));
+        }
+        let alpha_size = in_use.len() + 2;
+        let end_of_block = alpha_size - 1;
+
+        let num_tables = reader.read_bits(3)? as usize;
+        if !(MIN_TABLES..=MAX_TABLES).contains(&num_tables) {
+            return Err(invalid(&format!("block with {num_tables} huffman tables")));
+        }
+        let num_selectors = reader.read_bits(15)? as usize;
+        if num_selectors == 0 {
+            return Err(invalid("block without selectors"));
+        }
+
+        let mut tables: Vec<u8> = (0..num_tables as u8).collect();
+        let mut selectors = Vec::with_capacity(num_selectors);
+        for _ in 0..num_selectors {
+            let mut position = 0;
+            while reader.read_bit()? == 1 {
+                position += 1;
+                if position >= num_tables {
+                    return Err(invalid("selector of a missing table"));
+                }
+            }
+            tables[..=position].rotate_right(1);
+            selectors.push(tables[0]);
+        }
+
+        let mut decoders = Vec::with_capacity(num_tables);
+        for _ in 0..num_tables {
+            let mut length = reader.read_bits(5)?;
+            let mut lengths = Vec::with_capacity(alpha_size);
+            for _ in 0..alpha_size {
+                loop {
+                    if !(1..=MAX_READ_CODE_LENGTH).contains(&length) {
+                        return Err(invalid(&format!("huffman code of {length} bits")));
+                    }
+                    if reader.read_bit()? == 0 {
+                        break;
+                    }
+                    if reader.read_bit()? == 0 {
+                        length += 1;
+                    } else {
+                        length -= 1;
+                    }
+                }
+                lengths.push(length as u8);
+            }
+            decoders.push(CanonicalDecoder::new(&lengths)?);
+        }
+
+        // MTF values, the runs of zeros being written back
+        let max_size = 100_000 * level as usize;
+        let mut mtf = Vec::new();
+        let mut run = 0;
+        let mut run_digit = 1;
+        let mut decoded_symbols = 0;
+        loop {
+            let selector = *selectors
+                .get(decoded_symbols / GROUP_SIZE)
+                .ok_or_else(|| invalid("block with more groups than selectors"))?;
+            let symbol = decoders[selector as usize].decode(|| reader.read_bit())? as u16;
+            decoded_symbols += 1;
+
+            if symbol == RUNA || symbol == RUNB {
+                run += run_digit << symbol;
+                run_digit <<= 1;
+                if run > max_size {
+                    return Err(invalid("block larger than its level"));
+                }
+                continue;
+            }
+
+            mtf.resize(mtf.len() + run, 0);
+            run = 0;
+            run_digit = 1;
+
+            if symbol as usize == end_of_block {
+                break;
+            }
+            mtf.push((symbol - 1) as u8);
+
+            if mtf.len() > max_size {
+                return Err(invalid("block larger than its level"));
+            }
+        }
+
+        let numbered = MoveToFront::decode(&mtf)?;
+        let transformed = numbered
+            .iter()
+            .map(|&number| in_use.get(number as usize).copied())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid("block with a byte not in use"))?;
+
+        let block = BurrowsWheeler::decode(orig_ptr, &transformed)?;
+        let decoded = undo_initial_run_length(&block);
+
+        Ok((expected, decoded))
+    }
+
+    /// decode every bzip2 stream of the input, one after the other
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        decode_to(input, &mut decoded, false)?;
+
+        Ok(decoded)
+    }
+
+    /// decode every bzip2 stream read from `input`, each block being written to `output` once decoded
+    ///
+    /// A block or stream whose CRC does not match its decoded data is only a warning when forced.
+    pub fn decode_to(mut input: impl BufRead, output: &mut impl Write, force: bool) -> Result<()> {
+        let mut stream = 1;
+        while stream == 1 || !input.fill_buf()?.is_empty() {
+            let mut header = [0; 4];
+            input.read_exact(&mut header)?;
+            let level = header[3].wrapping_sub(b'0');
+            if header[..3] != MAGIC || !(1..=9).contains(&level) {
+                return Err(CompressorError::InvalidData(String::from(
+                    "not a bzip2 stream",
+                )));
+            }
+
+            let mut reader = MsbStreamReader::new(&mut input);
+            let mut stream_crc: u32 = 0;
+            let mut block_number = 1;
+            loop {
+                match read_u48(&mut reader)? {
+                    BLOCK_MAGIC => {
+                        let (expected, block) = decode_block(&mut reader, level)?;
+
+                        let found = crc32_bzip2(&block);
+                        if expected != found {
+                            let error = CompressorError::ChecksumMismatch {
+                                what: format!("bzip2 block {block_number}"),
+                                expected,
+                                found,
+                            };
+                            warn_or_fail(error, force)?;
+                        }
+
+                        stream_crc = stream_crc.rotate_left(1) ^ found;
+                        output.write_all(&block)?;
+                        block_number += 1;
+                    }
+                    END_MAGIC => break,
+                    _ => {
+                        return Err(CompressorError::InvalidData(String::from(
+                            "bzip2 block without its magic number",
+                        )));
+                    }
+                }
+            }
+
+            let expected = reader.read_bits(32)?;
+            if expected != stream_crc {
+                let error = CompressorError::ChecksumMismatch {
+                    what: format!("bzip2 stream {stream}"),
+                    expected,
+                    found: stream_crc,
+                };
+                warn_or_fail(error, force)?;
+            }
+
+            reader.align_to_byte();
+            stream += 1;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+
+        #[test]
+        fn bzip2_round_trip() {
+            let text: Vec<u8> = "AAABBCCDACCAA".repeat(1000).bytes().collect();
+
+            for level in [1, 5, DEFAULT_LEVEL] {
+                let encoded = encode(&text, level).unwrap();
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+
+            for text in [vec![], vec![0], vec![b'a'; 100_000], (0..=255).collect()] {
+                let encoded = encode(&text, DEFAULT_LEVEL).unwrap();
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+        }
+
+        #[test]
+        fn bzip2_many_blocks() {
+            // runs of every length around the ones of the initial run-length encoding
+            let text: Vec<u8> = (0..250_000u32)
+                .flat_map(|i| {
+                    let byte = (i.wrapping_mul(2_654_435_761) >> 24) as u8;
+                    std::iter::repeat_n(byte, (i % 300) as usize / 40 + (i % 7 == 0) as usize * 250)
+                })
+                .take(450_000)
+                .collect();
+
+            let encoded = encode(&text, 1).unwrap();
+            assert_eq!(text, decode(&encoded).unwrap());
+
+            // the same blocks when written in parts
+            let mut writer = Bzip2Writer::new(Vec::new(), 1).unwrap();
+            for chunk in text.chunks(9_999) {
+                writer.write_all(chunk).unwrap();
+            }
+            assert_eq!(encoded, writer.finish().unwrap());
+
+            // flushing ends the block being filled
+            let mut writer = Bzip2Writer::new(Vec::new(), 1).unwrap();
+            for chunk in text.chunks(100_000) {
+                writer.write_all(chunk).unwrap();
+                writer.flush().unwrap();
+            }
+            assert_eq!(text, decode(&writer.finish().unwrap()).unwrap());
+
+            // streams are decoded one after the other
+            let mut streams = encode(b"hello ", 1).unwrap();
+            streams.extend_from_slice(&encode(b"world", 9).unwrap());
+            assert_eq!(b"hello world".to_vec(), decode(&streams).unwrap());
+        }
+
+        #[test]
+        fn bzip2_known_stream() {
+            // `echo -n hello | bzip2`
+            let encoded = [
+                0x42, 0x5A, 0x68, 0x39, 0x31, 0x41, 0x59, 0x26, 0x53, 0x59, 0x19, 0x31, 0x65, 0x3D,
+                0x00, 0x00, 0x00, 0x81, 0x00, 0x02, 0x44, 0xA0, 0x00, 0x21, 0x9A, 0x68, 0x33, 0x4D,
+                0x07, 0x33, 0x8B, 0xB9, 0x22, 0x9C, 0x28, 0x48, 0x0C, 0x98, 0xB2, 0x9E, 0x80,
+            ];
+            assert_eq!(b"hello".to_vec(), decode(&encoded).unwrap());
+        }
+
+        #[test]
+        fn bzip2_zero_runs() {
+            assert_eq!(
+                vec![4, RUNA, RUNA, 2, RUNB, RUNA, 9],
+                ZeroRunLength::encode_runs(&[3, 0, 0, 0, 1, 0, 0, 0, 0], 9u16)
+            );
+        }
+
+        #[test]
+        fn bzip2_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".repeat(10).bytes().collect();
+            let encoded = encode(&text, DEFAULT_LEVEL).unwrap();
+
+            for end in 0..encoded.len() {
+                assert!(decode(&encoded[..end]).is_err());
+            }
+
+            let mut corrupted = encoded.clone();
+            corrupted[10] ^= 1;
+            assert!(matches!(
+                decode(&corrupted),
+                Err(CompressorError::ChecksumMismatch { .. })
+            ));
+
+            // only a warning when forced
+            let mut decoded = Vec::new();
+            decode_to(&corrupted[..], &mut decoded, true).unwrap();
+            assert_eq!(text, decoded);
+
+            assert!(matches!(
+                encode(&text, 0),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+    }
+}
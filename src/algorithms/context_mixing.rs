@@ -0,0 +1,617 @@
+#[allow(non_snake_case)]
+pub mod ContextMixing {
+    use crate::{
+        error::{CompressorError, Result},
+        range_coder::{RangeDecoder, RangeEncoder},
+        utils::check_decoded_size,
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://mattmahoney.net/dc/dce.html#Section_43 (context mixing, as in PAQ and lpaq)
+    // https://en.wikipedia.org/wiki/Context_mixing
+
+    /// smallest memory level, the tables of the models take 4 MiB
+    pub const MIN_LEVEL: u8 = 1;
+
+    /// largest memory level, the tables of the models take 1 GiB
+    pub const MAX_LEVEL: u8 = 9;
+
+    /// memory level used when none is given, the tables of the models take 64 MiB
+    pub const DEFAULT_LEVEL: u8 = 5;
+
+    /// the probabilities given to the arithmetic coder add up to 2^PROB_BITS
+    const PROB_BITS: u32 = 12;
+    const PROB_TOTAL: u64 = 1 << PROB_BITS;
+
+    /// orders of the contexts hashed in the tables, the last table is the word model
+    const ORDERS: [u32; 6] = [1, 2, 3, 4, 5, 6];
+    const HASHED_MODELS: usize = ORDERS.len() + 1;
+
+    /// order 0, hashed models, match model and the bias
+    const INPUTS: usize = HASHED_MODELS + 3;
+
+    /// number of bytes hashed to find the previous occurrence of the last bytes
+    const MIN_MATCH: usize = 4;
+
+    /// longest match length told apart by the match model
+    const MAX_MATCH_STATE: usize = 15;
+
+    /// update count past which the counters adapt at the same rate, the recent bits weighing more
+    const HASHED_LIMIT: u32 = 30;
+    const DIRECT_LIMIT: u32 = 1023;
+
+    /// learning rate of the mixer weights
+    const LEARNING_RATE: i32 = 10;
+
+    /// largest weight of an input, 2^16 being 1
+    const MAX_WEIGHT: i32 = 1 << 24;
+
+    /// the interpolated probabilities of the SSE stage move by 1/2^APM_RATE of their error
+    const APM_RATE: i32 = 7;
+
+    /// number of bits of the index of the tables at a memory level, the 8 tables of counters and
+    /// positions of 4 bytes taking 2^(21 + level) bytes
+    fn table_bits(level: u8) -> u32 {
+        16 + level as u32
+    }
+
+    /// probability (out of 4096) of a stretched probability `x`, the logistic function scaled by
+    /// 256, interpolated from 33 points
+    const fn squash(x: i32) -> i32 {
+        const POINTS: [i32; 33] = [
+            1, 2, 3, 6, 10, 16, 27, 45, 73, 120, 194, 310, 488, 747, 1101, 1546, 2047, 2549, 2994,
+            3348, 3607, 3785, 3901, 3975, 4022, 4050, 4068, 4079, 4085, 4089, 4092, 4093, 4094,
+        ];
+
+        if x > 2047 {
+            return 4095;
+        }
+        if x < -2047 {
+            return 1;
+        }
+
+        let weight = x & 127;
+        let i = ((x >> 7) + 16) as usize;
+        (POINTS[i] * (128 - weight) + POINTS[i + 1] * weight + 64) >> 7
+    }
+
+    /// inverse of [`squash`], ln(p / (1 - p)) scaled by 256
+    const STRETCH: [i16; 4096] = {
+        let mut table = [0; 4096];
+
+        let mut next = 0;
+        let mut x = -2047;
+        while x <= 2047 {
+            let p = squash(x) as usize;
+            while next <= p {
+                table[next] = x as i16;
+                next += 1;
+            }
+            x += 1;
+        }
+        while next < 4096 {
+            table[next] = 2047;
+            next += 1;
+        }
+
+        table
+    };
+
+    fn stretch(p: i32) -> i32 {
+        STRETCH[p as usize] as i32
+    }
+
+    /// 2^16 / (n + 1.5), how much a counter moves after n updates
+    const RECIPROCALS: [i32; 1024] = {
+        let mut table = [0; 1024];
+
+        let mut n = 0;
+        while n < 1024 {
+            table[n] = (2 << 16) / (2 * n as i32 + 3);
+            n += 1;
+        }
+
+        table
+    };
+
+    /// Probability of the next bit being a 1 in a context, adapting fast after the first bits then
+    /// more and more slowly.
+    ///
+    /// ```text
+    /// [probability, 22 bits][number of updates, 10 bits]
+    /// ```
+    ///
+    /// The probability is stored relative to 1/2, so a table of zeros starts with every
+    /// probability at 1/2 and does not have to be written before it is used.
+    #[derive(Clone, Copy, Default)]
+    struct Counter(u32);
+
+    impl Counter {
+        const HALF: u32 = 1 << 21;
+
+        fn probability(self) -> i32 {
+            (((self.0 >> 10) ^ Self::HALF) >> 10) as i32
+        }
+
+        fn count(self) -> u32 {
+            self.0 & 1023
+        }
+
+        fn update(&mut self, bit: u32, limit: u32) {
+            let count = self.0 & 1023;
+            let p = ((self.0 >> 10) ^ Self::HALF) as i32;
+            let target = if bit == 1 { (1 << 22) - 1 } else { 0 };
+
+            let p = p + (((target - p) as i64 * RECIPROCALS[count as usize] as i64) >> 16) as i32;
+            self.0 = ((p as u32) ^ Self::HALF) << 10 | (count + u32::from(count < limit));
+        }
+    }
+
+    /// Adaptive probability map (SSE): refine a probability given a context, by interpolating
+    /// between 33 probabilities learned for the stretched probabilities around it.
+    struct Apm {
+        table: Vec<u16>,
+        /// entry to update, the nearest one to the last refined probability
+        index: usize,
+    }
+
+    impl Apm {
+        fn new(contexts: usize) -> Self {
+            let points: Vec<u16> = (0..33)
+                .map(|i| (squash((i - 16) * 128) * 16) as u16)
+                .collect();
+
+            Apm {
+                table: points.repeat(contexts),
+                index: 0,
+            }
+        }
+
+        fn refine(&mut self, p: i32, context: usize) -> i32 {
+            let s = stretch(p) + 2048;
+            let weight = s & 127;
+            let base = context * 33 + (s >> 7) as usize;
+            self.index = base + (weight >> 6) as usize;
+
+            (self.table[base] as i32 * (128 - weight) + self.table[base + 1] as i32 * weight) >> 11
+        }
+
+        fn update(&mut self, bit: u32) {
+            let target = if bit == 1 { 65535 } else { 0 };
+            let entry = &mut self.table[self.index];
+            *entry = (*entry as i32 + ((target - *entry as i32) >> APM_RATE)) as u16;
+        }
+    }
+
+    /// Combine the stretched probabilities of the models with weights, chosen by a context and
+    /// trained to reduce the coding cost (a single layer neural network).
+    struct Mixer {
+        /// [`INPUTS`] weights for each context, 1 being 2^16
+        weights: Vec<i32>,
+        inputs: [i32; INPUTS],
+        /// first weight of the context of the last mix
+        offset: usize,
+        p: i32,
+    }
+
+    impl Mixer {
+        fn new(contexts: usize) -> Self {
+            let mut weights = vec![1 << 14; contexts * INPUTS];
+            // the bias only moves away from 0 when it helps
+            for context in 0..contexts {
+                weights[context * INPUTS + INPUTS - 1] = 0;
+            }
+
+            Mixer {
+                weights,
+                inputs: [0; INPUTS],
+                offset: 0,
+                p: 2048,
+            }
+        }
+
+        fn mix(&mut self, context: usize) -> i32 {
+            self.offset = context * INPUTS;
+            let weights = &self.weights[self.offset..self.offset + INPUTS];
+
+            let dot: i64 = self
+                .inputs
+                .iter()
+                .zip(weights)
+                .map(|(&input, &weight)| input as i64 * weight as i64)
+                .sum();
+
+            self.p = squash((dot >> 16).clamp(-2047, 2047) as i32);
+            self.p
+        }
+
+        fn update(&mut self, bit: u32) {
+            let error = (((bit as i32) << PROB_BITS) - self.p) * LEARNING_RATE;
+
+            let weights = &mut self.weights[self.offset..self.offset + INPUTS];
+            for (weight, &input) in weights.iter_mut().zip(&self.inputs) {
+                *weight = (*weight + ((input * error) >> 14)).clamp(-MAX_WEIGHT, MAX_WEIGHT);
+            }
+        }
+    }
+
+    /// hash of a context and of the model it belongs to
+    fn hash(context: u64, model: u64) -> u32 {
+        let h = (context ^ model.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .wrapping_mul(0xD6E8_FEB8_6659_FD93);
+        (h >> 32) as u32
+    }
+
+    /// Models predicting each bit of the input from the bits before it.
+    ///
+    /// - order 0: the bits already coded of the current byte
+    /// - orders 1 to 6: the last bytes, hashed with the bits of the current byte
+    /// - word: the letters of the current word, for text
+    /// - match: the bit that followed the last occurrence of the last bytes
+    ///
+    /// The hashed contexts find 16 counters at each half byte, one for each of the bits coded in
+    /// that half byte, which keeps them in the same cache line.
+    struct Model {
+        table_bits: u32,
+        order_0: Vec<Counter>,
+        /// counters of the hashed models, one table after the other
+        hashed: Vec<Counter>,
+        /// hash of the context of each hashed model, for the current byte
+        contexts: [u32; HASHED_MODELS],
+        /// first counter of each hashed model for the current half byte
+        buckets: [usize; HASHED_MODELS],
+
+        /// bytes coded so far
+        history: Vec<u8>,
+        /// last 8 bytes
+        last_bytes: u64,
+        word: u64,
+
+        /// position of the byte after the last occurrence of the last [`MIN_MATCH`] bytes
+        match_positions: Vec<u32>,
+        /// position of the byte predicted by the match, and the number of bytes matched before it
+        match_ptr: usize,
+        match_length: usize,
+        /// bit predicted by the match, None when there is no match or it failed in this byte
+        expected_bit: Option<u32>,
+        match_counters: Vec<Counter>,
+        match_counter: usize,
+
+        mixer: Mixer,
+        apm_order_0: Apm,
+        apm_order_1: Apm,
+
+        /// bits of the current byte after a leading 1
+        partial: u32,
+        /// bits of the current half byte after a leading 1
+        nibble: u32,
+        bit_count: u32,
+    }
+
+    impl Model {
+        fn new(level: u8) -> Self {
+            let table_bits = table_bits(level);
+
+            let mut model = Model {
+                table_bits,
+                order_0: vec![Counter::default(); 256],
+                hashed: vec![Counter::default(); HASHED_MODELS << table_bits],
+                contexts: [0; HASHED_MODELS],
+                buckets: [0; HASHED_MODELS],
+                history: Vec::new(),
+                last_bytes: 0,
+                word: 0,
+                match_positions: vec![0; 1 << table_bits],
+                match_ptr: 0,
+                match_length: 0,
+                expected_bit: None,
+                match_counters: vec![Counter::default(); 2 * (MAX_MATCH_STATE + 1)],
+                match_counter: 0,
+                mixer: Mixer::new((ORDERS.len() + 1) << 8),
+                apm_order_0: Apm::new(256),
+                apm_order_1: Apm::new(1 << 16),
+                partial: 1,
+                nibble: 1,
+                bit_count: 0,
+            };
+            model.update_contexts();
+
+            model
+        }
+
+        /// find the counters of the hashed contexts for the next half byte
+        fn update_buckets(&mut self) {
+            let mask = (1 << self.table_bits) - 16;
+            for (i, bucket) in self.buckets.iter_mut().enumerate() {
+                let h = self.contexts[i] ^ self.partial.wrapping_mul(0x2F0B_4C8D);
+                let h = h.wrapping_mul(0x9E37_79B1) as usize;
+                *bucket = (i << self.table_bits) | (h >> (32 - self.table_bits) & mask);
+            }
+        }
+
+        /// hash the contexts ending with the last byte
+        fn update_contexts(&mut self) {
+            for (i, &order) in ORDERS.iter().enumerate() {
+                let bytes = self.last_bytes & (u64::MAX >> (64 - 8 * order));
+                self.contexts[i] = hash(bytes, order as u64);
+            }
+            self.contexts[HASHED_MODELS - 1] = hash(self.word, HASHED_MODELS as u64);
+
+            self.update_buckets();
+        }
+
+        /// follow or look for a match with the last bytes, once the byte is in the history
+        fn update_match(&mut self, byte: u8) {
+            if self.match_length > 0 && self.history[self.match_ptr] == byte {
+                self.match_length += 1;
+                self.match_ptr += 1;
+            } else {
+                self.match_length = 0;
+            }
+
+            let size = self.history.len();
+            if size < MIN_MATCH {
+                return;
+            }
+
+            let last = &self.history[size - MIN_MATCH..];
+            let h = hash(
+                last.iter().fold(0, |h, &byte| h << 8 | byte as u64),
+                HASHED_MODELS as u64 + 1,
+            );
+            let slot = (h >> (32 - self.table_bits)) as usize;
+
+            if self.match_length == 0 {
+                let candidate = self.match_positions[slot] as usize;
+                if candidate > 0 {
+                    let length = (0..candidate.min(MAX_MATCH_STATE))
+                        .take_while(|&i| {
+                            self.history[candidate - 1 - i] == self.history[size - 1 - i]
+                        })
+                        .count();
+                    if length > 0 {
+                        self.match_length = length;
+                        self.match_ptr = candidate;
+                    }
+                }
+            }
+
+            self.match_positions[slot] = size as u32;
+        }
+
+        /// probability of the next bit being a 1, out of [`PROB_TOTAL`]
+        fn predict(&mut self) -> i32 {
+            let inputs = &mut self.mixer.inputs;
+
+            inputs[0] = stretch(self.order_0[self.partial as usize].probability());
+            let mut known_orders = 0;
+            for (i, &bucket) in self.buckets.iter().enumerate() {
+                let counter = self.hashed[bucket + self.nibble as usize];
+                inputs[1 + i] = stretch(counter.probability());
+                if i < ORDERS.len() && counter.count() > 0 {
+                    known_orders += 1;
+                }
+            }
+
+            inputs[INPUTS - 2] = match self.expected_bit {
+                Some(bit) => {
+                    let state = self.match_length.min(MAX_MATCH_STATE);
+                    self.match_counter = 2 * state + bit as usize;
+                    stretch(self.match_counters[self.match_counter].probability())
+                }
+                None => 0,
+            };
+            inputs[INPUTS - 1] = 256;
+
+            // weights for the number of orders that already saw their context, the longer ones
+            // being trusted once they have seen some bits
+            let p = self.mixer.mix(known_orders << 8 | self.partial as usize);
+
+            let c1 = (self.last_bytes & 0xFF) as usize;
+            let p_0 = self.apm_order_0.refine(p, self.partial as usize);
+            let p_1 = self.apm_order_1.refine(p, c1 << 8 | self.partial as usize);
+
+            ((p + p_0 + 2 * p_1 + 2) >> 2).clamp(1, PROB_TOTAL as i32 - 1)
+        }
+
+        fn update(&mut self, bit: u32) {
+            self.order_0[self.partial as usize].update(bit, DIRECT_LIMIT);
+            for &bucket in &self.buckets {
+                self.hashed[bucket + self.nibble as usize].update(bit, HASHED_LIMIT);
+            }
+            if let Some(expected_bit) = self.expected_bit {
+                self.match_counters[self.match_counter].update(bit, DIRECT_LIMIT);
+                if expected_bit != bit {
+                    self.expected_bit = None;
+                    self.match_length = 0;
+                }
+            }
+
+            self.mixer.update(bit);
+            self.apm_order_0.update(bit);
+            self.apm_order_1.update(bit);
+
+            self.partial = self.partial << 1 | bit;
+            self.nibble = self.nibble << 1 | bit;
+            self.bit_count += 1;
+
+            if self.bit_count == 8 {
+                let byte = self.partial as u8;
+                self.end_byte(byte);
+            } else {
+                if self.bit_count == 4 {
+                    self.nibble = 1;
+                    self.update_buckets();
+                }
+                self.predict_match_bit();
+            }
+        }
+
+        fn end_byte(&mut self, byte: u8) {
+            self.history.push(byte);
+            self.update_match(byte);
+
+            self.last_bytes = self.last_bytes << 8 | byte as u64;
+            self.word = if byte.is_ascii_alphabetic() {
+                (self.word ^ byte.to_ascii_lowercase() as u64).wrapping_mul(0x100_0000_01B3)
+            } else {
+                0
+            };
+
+            self.partial = 1;
+            self.nibble = 1;
+            self.bit_count = 0;
+            self.update_contexts();
+            self.predict_match_bit();
+        }
+
+        /// bit of the byte following the match at the current bit
+        fn predict_match_bit(&mut self) {
+            if self.match_length == 0 {
+                self.expected_bit = None;
+                return;
+            }
+
+            let expected = self.history[self.match_ptr] as u32 | 0x100;
+            self.expected_bit = (expected >> (8 - self.bit_count) == self.partial)
+                .then_some(expected >> (7 - self.bit_count) & 1);
+        }
+    }
+
+    /// encode an array of bytes with context mixing driving a binary arithmetic coder
+    ///
+    /// @**returns** Vec\<u8\> => varsize memory level, varsize number of bytes and the encoded bits
+    pub fn encode(input: &[u8], level: u8) -> Result<Vec<u8>> {
+        if !(MIN_LEVEL..=MAX_LEVEL).contains(&level) {
+            return Err(CompressorError::InvalidArgument(format!(
+                "memory level {level} is not between {MIN_LEVEL} and {MAX_LEVEL}"
+            )));
+        }
+
+        let mut model = Model::new(level);
+        let mut encoder = RangeEncoder::new();
+
+        for &byte in input {
+            for i in (0..8).rev() {
+                let bit = byte >> i & 1;
+                encoder.encode_bit(bit, model.predict() as u64, PROB_TOTAL);
+                model.update(bit as u32);
+            }
+        }
+
+        let mut encoded = encode_varsize(level as usize);
+        encoded.extend_from_slice(&encode_varsize(input.len()));
+        encoded.extend_from_slice(&encoder.finish());
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode`], with the memory level they were encoded with
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let (level, mut i) = get_first_decoded(input)?;
+        let (size, last_byte_found) = get_first_decoded(&input[i..])?;
+        i += last_byte_found;
+
+        let level = u8::try_from(level)
+            .ok()
+            .filter(|level| (MIN_LEVEL..=MAX_LEVEL).contains(level))
+            .ok_or_else(|| {
+                CompressorError::InvalidData(format!(
+                    "memory level {level} is not between {MIN_LEVEL} and {MAX_LEVEL}"
+                ))
+            })?;
+        check_decoded_size(size)?;
+
+        let mut model = Model::new(level);
+        let mut decoder = RangeDecoder::new(&input[i..]);
+
+        let mut decoded = Vec::new();
+        while decoded.len() < size {
+            let mut byte = 0;
+            for _ in 0..8 {
+                let bit = decoder.decode_bit(model.predict() as u64, PROB_TOTAL)?;
+                model.update(bit as u32);
+                byte = (byte << 1) | bit;
+            }
+
+            decoded.push(byte);
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::algorithms::ppm::Ppm;
+        use crate::test_utils::{assert_truncated, random_bytes, sample_texts};
+        use crate::utils::MAX_BLOCK_SIZE;
+
+        #[test]
+        fn cm_squash_stretch() {
+            assert_eq!(2047, squash(0));
+            assert_eq!(0, stretch(2047));
+            assert!(squash(-2047) >= 1 && squash(2047) <= 4095);
+            for x in (-2047..=2047).step_by(17) {
+                assert!((squash(stretch(squash(x))) - squash(x)).abs() <= 1);
+            }
+        }
+
+        #[test]
+        fn cm_round_trip() {
+            for text in sample_texts(random_bytes(10_000)) {
+                let encoded = encode(&text, MIN_LEVEL).unwrap();
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+        }
+
+        #[test]
+        fn cm_better_than_ppm() {
+            let text: Vec<u8> = (0..800)
+                .map(|i| {
+                    format!(
+                        "let value_{} = compute({}, \"{}\");\n",
+                        i % 37,
+                        i * 7 % 101,
+                        i % 13
+                    )
+                })
+                .collect::<String>()
+                .into_bytes();
+
+            let encoded = encode(&text, MIN_LEVEL).unwrap();
+            assert_eq!(text, decode(&encoded).unwrap());
+
+            let ppm = Ppm::encode(&text, Ppm::DEFAULT_ORDER, Ppm::DEFAULT_MEMORY).unwrap();
+            assert!(encoded.len() < ppm.len(), "{} {}", encoded.len(), ppm.len());
+        }
+
+        #[test]
+        fn cm_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".repeat(10).bytes().collect();
+            let encoded = encode(&text, MIN_LEVEL).unwrap();
+
+            assert_truncated(decode, &encoded);
+            for level in [0, MAX_LEVEL + 1] {
+                assert!(matches!(
+                    decode(&[level, 1, 0]),
+                    Err(CompressorError::InvalidData(_))
+                ));
+                assert!(matches!(
+                    encode(&text, level),
+                    Err(CompressorError::InvalidArgument(_))
+                ));
+            }
+
+            let mut oversized = encode_varsize(MIN_LEVEL as usize);
+            oversized.extend_from_slice(&encode_varsize(MAX_BLOCK_SIZE + 1));
+            oversized.push(0);
+            assert!(matches!(
+                decode(&oversized),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,780 @@
+#[allow(non_snake_case)]
+pub mod Deflate {
+    use std::io::{Read, Write};
+
+    use crate::{
+        algorithms::lzss::LZSS::{MIN_MATCH, MatchFinder, Token},
+        bit_io::{LsbBitWriter, LsbStreamReader},
+        canonical_huffman::{CanonicalDecoder, canonical_codes, code_lengths},
+        error::{CompressorError, Result},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://www.rfc-editor.org/rfc/rfc1951
+    // https://github.com/madler/zlib/blob/develop/contrib/puff/puff.c
+
+    /// level used when none is given, from 0 (stored) to 9 (smallest)
+    pub const DEFAULT_LEVEL: u8 = 6;
+
+    /// largest distance of a repeat
+    const WINDOW_SIZE: usize = 1 << 15;
+
+    /// longest repeat
+    const MAX_MATCH: usize = 258;
+
+    /// a repeat of MIN_MATCH bytes further than this costs more than its literals
+    const TOO_FAR: usize = 4096;
+
+    /// tokens of a block sharing the same huffman codes
+    const BLOCK_TOKENS: usize = 1 << 14;
+
+    /// largest number of bytes of a stored block
+    const MAX_STORED: usize = 0xFFFF;
+
+    const END_OF_BLOCK: usize = 256;
+
+    /// literals, end of block and repeat lengths (286 and 287 are never used)
+    const LITERAL_LENGTH_CODES: usize = 288;
+    const DISTANCE_CODES: usize = 30;
+
+    /// longest code of the literals and lengths, and of the distances
+    const MAX_CODE_LENGTH: u8 = 15;
+    /// longest code of the code lengths
+    const MAX_CODE_LENGTH_LENGTH: u8 = 7;
+
+    const LENGTH_BASE: [usize; 29] = [
+        3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115,
+        131, 163, 195, 227, 258,
+    ];
+    const LENGTH_EXTRA: [u32; 29] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+    ];
+    const DISTANCE_BASE: [usize; 30] = [
+        1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+        2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+    ];
+    const DISTANCE_EXTRA: [u32; 30] = [
+        0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12,
+        13, 13,
+    ];
+
+    /// order in which the lengths of the code length codes are stored
+    const CODE_LENGTH_ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    /// @**returns** (usize, u32, u32) => (code, extra bits, value of the extra bits) of a value
+    /// within the ranges starting at `bases`
+    fn split(bases: &[usize], extras: &[u32], value: usize) -> (usize, u32, u32) {
+        let code = bases.partition_point(|&base| base <= value) - 1;
+        (code, extras[code], (value - bases[code]) as u32)
+    }
+
+    /// lengths of the codes of the fixed blocks
+    fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+        let literal_lengths = (0..LITERAL_LENGTH_CODES)
+            .map(|symbol| match symbol {
+                0..=143 => 8,
+                144..=255 => 9,
+                256..=279 => 7,
+                _ => 8,
+            })
+            .collect();
+
+        (literal_lengths, vec![5; DISTANCE_CODES])
+    }
+
+    /// canonical codes with their bits reversed, the first bit of a code being written first
+    struct HuffmanCodes {
+        codes: Vec<u32>,
+        lengths: Vec<u8>,
+    }
+
+    impl HuffmanCodes {
+        fn new(lengths: Vec<u8>) -> Self {
+            let codes = canonical_codes(&lengths)
+                .iter()
+                .zip(&lengths)
+                .map(|(&code, &length)| {
+                    code.reverse_bits()
+                        .checked_shr(32 - length as u32)
+                        .unwrap_or(0)
+                })
+                .collect();
+
+            HuffmanCodes { codes, lengths }
+        }
+
+        fn write(&self, writer: &mut LsbBitWriter, symbol: usize) {
+            writer.write_bits(self.codes[symbol], self.lengths[symbol] as u32);
+        }
+
+        /// number of bits taken by symbols with the given frequencies
+        fn cost(&self, frequencies: &[usize]) -> usize {
+            frequencies
+                .iter()
+                .zip(&self.lengths)
+                .map(|(&frequency, &length)| frequency * length as usize)
+                .sum()
+        }
+    }
+
+    /// code length symbol (0-18) with the value of its extra bits
+    ///
+    /// 0-15: a length, 16: repeat the previous length 3-6 times, 17: 3-10 zeros, 18: 11-138 zeros
+    fn run_length_code_lengths(lengths: &[u8]) -> Vec<(usize, u32)> {
+        let mut symbols = Vec::new();
+
+        let mut i = 0;
+        while i < lengths.len() {
+            let length = lengths[i];
+            let run = lengths[i..].iter().take_while(|&&l| l == length).count();
+
+            if length == 0 && run >= 11 {
+                let run = run.min(138);
+                symbols.push((18, (run - 11) as u32));
+                i += run;
+            } else if length == 0 && run >= 3 {
+                symbols.push((17, (run - 3) as u32));
+                i += run;
+            } else if length > 0 && run >= 4 {
+                // the first length is written, the next ones are repeats of it
+                symbols.push((length as usize, 0));
+                let run = (run - 1).min(6);
+                symbols.push((16, (run - 3) as u32));
+                i += 1 + run;
+            } else {
+                symbols.push((length as usize, 0));
+                i += 1;
+            }
+        }
+
+        symbols
+    }
+
+    fn extra_bits(code_length_symbol: usize) -> u32 {
+        match code_length_symbol {
+            16 => 2,
+            17 => 3,
+            18 => 7,
+            _ => 0,
+        }
+    }
+
+    /// codes of a dynamic block with the header describing them
+    struct DynamicHeader {
+        literal_codes: HuffmanCodes,
+        distance_codes: HuffmanCodes,
+        literal_count: usize,
+        distance_count: usize,
+        code_length_codes: HuffmanCodes,
+        code_length_count: usize,
+        code_length_symbols: Vec<(usize, u32)>,
+    }
+
+    impl DynamicHeader {
+        fn new(literal_frequencies: &[usize], distance_frequencies: &[usize]) -> Self {
+            let literal_lengths = code_lengths(literal_frequencies, MAX_CODE_LENGTH);
+
+            // some decoders need at least two distance codes
+            let mut distance_frequencies = distance_frequencies.to_vec();
+            for code in 0..2 {
+                if distance_frequencies.iter().filter(|&&f| f > 0).count() < 2 {
+                    distance_frequencies[code] = distance_frequencies[code].max(1);
+                }
+            }
+            let distance_lengths = code_lengths(&distance_frequencies, MAX_CODE_LENGTH);
+
+            // the codes after the last one used are not stored
+            let literal_count =
+                257.max(literal_lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+            let distance_count =
+                1.max(distance_lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+
+            let all_lengths = [
+                &literal_lengths[..literal_count],
+                &distance_lengths[..distance_count],
+            ]
+            .concat();
+            let code_length_symbols = run_length_code_lengths(&all_lengths);
+
+            let mut code_length_frequencies = [0; 19];
+            for &(symbol, _) in &code_length_symbols {
+                code_length_frequencies[symbol] += 1;
+            }
+            let code_length_lengths =
+                code_lengths(&code_length_frequencies, MAX_CODE_LENGTH_LENGTH);
+            let code_length_count = 4.max(
+                CODE_LENGTH_ORDER
+                    .iter()
+                    .rposition(|&symbol| code_length_lengths[symbol] > 0)
+                    .unwrap_or(0)
+                    + 1,
+            );
+
+            DynamicHeader {
+                literal_codes: HuffmanCodes::new(literal_lengths),
+                distance_codes: HuffmanCodes::new(distance_lengths),
+                literal_count,
+                distance_count,
+                code_length_codes: HuffmanCodes::new(code_length_lengths),
+                code_length_count,
+                code_length_symbols,
+            }
+        }
+
+        /// number of bits of the header
+        fn cost(&self) -> usize {
+            let symbols: usize = self
+                .code_length_symbols
+                .iter()
+                .map(|&(symbol, _)| {
+                    self.code_length_codes.lengths[symbol] as usize + extra_bits(symbol) as usize
+                })
+                .sum();
+
+            5 + 5 + 4 + 3 * self.code_length_count + symbols
+        }
+
+        fn write(&self, writer: &mut LsbBitWriter) {
+            writer.write_bits((self.literal_count - 257) as u32, 5);
+            writer.write_bits((self.distance_count - 1) as u32, 5);
+            writer.write_bits((self.code_length_count - 4) as u32, 4);
+
+            for &symbol in &CODE_LENGTH_ORDER[..self.code_length_count] {
+                writer.write_bits(self.code_length_codes.lengths[symbol] as u32, 3);
+            }
+
+            for &(symbol, extra) in &self.code_length_symbols {
+                self.code_length_codes.write(writer, symbol);
+                writer.write_bits(extra, extra_bits(symbol));
+            }
+        }
+    }
+
+    /// frequencies of the literals and lengths, and of the distances, the bits of their extra values
+    fn frequencies(tokens: &[Token]) -> (Vec<usize>, Vec<usize>, usize) {
+        let mut literal_frequencies = vec![0; LITERAL_LENGTH_CODES];
+        let mut distance_frequencies = vec![0; DISTANCE_CODES];
+        let mut extra_bits = 0;
+
+        for token in tokens {
+            match *token {
+                Token::Literal(c) => literal_frequencies[c as usize] += 1,
+                Token::Match { offset, length } => {
+                    let (code, length_extra, _) = split(&LENGTH_BASE, &LENGTH_EXTRA, length);
+                    literal_frequencies[257 + code] += 1;
+
+                    let (code, distance_extra, _) = split(&DISTANCE_BASE, &DISTANCE_EXTRA, offset);
+                    distance_frequencies[code] += 1;
+
+                    extra_bits += (length_extra + distance_extra) as usize;
+                }
+            }
+        }
+        literal_frequencies[END_OF_BLOCK] = 1;
+
+        (literal_frequencies, distance_frequencies, extra_bits)
+    }
+
+    fn write_tokens(
+        writer: &mut LsbBitWriter,
+        tokens: &[Token],
+        literal_codes: &HuffmanCodes,
+        distance_codes: &HuffmanCodes,
+    ) {
+        for token in tokens {
+            match *token {
+                Token::Literal(c) => literal_codes.write(writer, c as usize),
+                Token::Match { offset, length } => {
+                    let (code, extra, value) = split(&LENGTH_BASE, &LENGTH_EXTRA, length);
+                    literal_codes.write(writer, 257 + code);
+                    writer.write_bits(value, extra);
+
+                    let (code, extra, value) = split(&DISTANCE_BASE, &DISTANCE_EXTRA, offset);
+                    distance_codes.write(writer, code);
+                    writer.write_bits(value, extra);
+                }
+            }
+        }
+
+        literal_codes.write(writer, END_OF_BLOCK);
+    }
+
+    /// the bytes split in stored blocks of at most MAX_STORED bytes
+    fn write_stored(writer: &mut LsbBitWriter, bytes: &[u8], last: bool) {
+        let chunks: Vec<&[u8]> = if bytes.is_empty() {
+            vec![&[]]
+        } else {
+            bytes.chunks(MAX_STORED).collect()
+        };
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            writer.write_bits((last && i == chunks.len() - 1) as u32, 1);
+            writer.write_bits(0b00, 2);
+            writer.align_to_byte();
+
+            let length = chunk.len() as u16;
+            writer.write_bytes(&length.to_le_bytes());
+            writer.write_bytes(&(!length).to_le_bytes());
+            writer.write_bytes(chunk);
+        }
+    }
+
+    /// write the tokens of `bytes` in the smallest of a stored, fixed or dynamic block
+    fn write_block(writer: &mut LsbBitWriter, tokens: &[Token], bytes: &[u8], last: bool) {
+        let (literal_frequencies, distance_frequencies, extra_bits) = frequencies(tokens);
+
+        let (fixed_literal_lengths, fixed_distance_lengths) = fixed_lengths();
+        let fixed_literal_codes = HuffmanCodes::new(fixed_literal_lengths);
+        let fixed_distance_codes = HuffmanCodes::new(fixed_distance_lengths);
+        let fixed_cost = fixed_literal_codes.cost(&literal_frequencies)
+            + fixed_distance_codes.cost(&distance_frequencies)
+            + extra_bits;
+
+        let header = DynamicHeader::new(&literal_frequencies, &distance_frequencies);
+        let dynamic_cost = header.cost()
+            + header.literal_codes.cost(&literal_frequencies)
+            + header.distance_codes.cost(&distance_frequencies)
+            + extra_bits;
+
+        // block type, up to 7 bits to align, length and its complement
+        let stored_cost = bytes.len().div_ceil(MAX_STORED).max(1) * (3 + 7 + 32) + 8 * bytes.len();
+
+        if stored_cost <= fixed_cost.min(dynamic_cost) {
+            write_stored(writer, bytes, last);
+        } else if fixed_cost <= dynamic_cost {
+            writer.write_bits(last as u32, 1);
+            writer.write_bits(0b01, 2);
+            write_tokens(writer, tokens, &fixed_literal_codes, &fixed_distance_codes);
+        } else {
+            writer.write_bits(last as u32, 1);
+            writer.write_bits(0b10, 2);
+            header.write(writer);
+            write_tokens(
+                writer,
+                tokens,
+                &header.literal_codes,
+                &header.distance_codes,
+            );
+        }
+    }
+
+    /// previous positions compared at each position, for each level
+    fn max_chain(level: u8) -> usize {
+        [0, 4, 8, 16, 32, 64, 128, 256, 1024, 4096][level as usize]
+    }
+
+    /// return the level if it is from 0 to 9
+    pub fn check_level(level: u8) -> Result<u8> {
+        if level > 9 {
+            return Err(CompressorError::InvalidArgument(format!(
+                "level {level} is not between 0 and 9"
+            )));
+        }
+
+        Ok(level)
+    }
+
+    /// encode an array of bytes as a raw DEFLATE stream (RFC 1951)
+    ///
+    /// Repeats are found within the last 32 KiB, the level (0 to 9) sets how hard they are looked for,
+    /// 0 storing the bytes as they are. Each block is written as stored, with the fixed codes or with
+    /// its own codes, whichever is the smallest.
+    ///
+    /// @**returns** Vec\<u8\> => the DEFLATE blocks, the last one being marked as final
+    pub fn encode(input: &[u8], level: u8) -> Result<Vec<u8>> {
+        encode_part(input, level, true)
+    }
+
+    /// encode a part of a DEFLATE stream written in several parts, as [`encode`] does
+    ///
+    /// Repeats are only looked for within the part. Unless it is the last one, the part ends with an
+    /// empty stored block so the next part starts on a whole byte (a sync flush for zlib).
+    pub fn encode_part(input: &[u8], level: u8, last: bool) -> Result<Vec<u8>> {
+        let level = check_level(level)?;
+
+        let mut writer = LsbBitWriter::new();
+        if level == 0 {
+            write_stored(&mut writer, input, last);
+            return Ok(writer.finish());
+        }
+
+        let tokens = MatchFinder::new(WINDOW_SIZE)
+            .with_max_match(MAX_MATCH)
+            .with_max_chain(max_chain(level))
+            .tokens(input, |offset, length| {
+                length > MIN_MATCH || offset <= TOO_FAR
+            });
+
+        if tokens.is_empty() {
+            write_block(&mut writer, &[], &[], last);
+        }
+
+        let blocks: Vec<&[Token]> = tokens.chunks(BLOCK_TOKENS).collect();
+        let mut start = 0;
+        for (i, block) in blocks.iter().enumerate() {
+            let size: usize = block
+                .iter()
+                .map(|token| match *token {
+                    Token::Literal(_) => 1,
+                    Token::Match { length, .. } => length,
+                })
+                .sum();
+
+            write_block(
+                &mut writer,
+                block,
+                &input[start..start + size],
+                last && i == blocks.len() - 1,
+            );
+            start += size;
+        }
+
+        if !last {
+            write_stored(&mut writer, &[], false);
+        }
+
+        Ok(writer.finish())
+    }
+
+    /// decode a raw DEFLATE stream
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        decode_first(input).map(|(decoded, _)| decoded)
+    }
+
+    /// decode the DEFLATE stream at the start of the input, up to its final block
+    ///
+    /// @**returns** (Vec\<u8\>, usize) => (decoded bytes, number of bytes of the stream)
+    pub fn decode_first(input: &[u8]) -> Result<(Vec<u8>, usize)> {
+        let mut reader = LsbStreamReader::new(input);
+        let mut decoded = Vec::new();
+        decode_to(&mut reader, &mut decoded)?;
+
+        Ok((decoded, reader.bytes_read()))
+    }
+
+    /// decode a DEFLATE stream up to its final block, the decoded bytes being written to `output`
+    /// as they come, only keeping the last 32 KiB the repeats can refer to
+    ///
+    /// @**returns** usize => number of decoded bytes
+    pub fn decode_to(
+        reader: &mut LsbStreamReader<impl Read>,
+        output: &mut impl Write,
+    ) -> Result<usize> {
+        let mut window = Window::new(output);
+
+        loop {
+            let last = reader.read_bit()? == 1;
+
+            match reader.read_bits(2)? {
+                0b00 => {
+                    reader.align_to_byte();
+                    let length = reader.read_bits(16)?;
+                    if reader.read_bits(16)? != !length & 0xFFFF {
+                        return Err(CompressorError::InvalidData(String::from(
+                            "stored block length not matching its complement",
+                        )));
+                    }
+                    window.read_stored(reader, length as usize)?;
+                }
+                0b01 => {
+                    let (literal_lengths, distance_lengths) = fixed_lengths();
+                    let literal_decoder = CanonicalDecoder::new(&literal_lengths)?;
+                    let distance_decoder = CanonicalDecoder::new(&distance_lengths)?;
+                    inflate_block(reader, &literal_decoder, &distance_decoder, &mut window)?;
+                }
+                0b10 => {
+                    let (literal_decoder, distance_decoder) = read_dynamic_header(reader)?;
+                    inflate_block(reader, &literal_decoder, &distance_decoder, &mut window)?;
+                }
+                _ => {
+                    return Err(CompressorError::InvalidData(String::from(
+                        "reserved block type",
+                    )));
+                }
+            }
+
+            if last {
+                break;
+            }
+        }
+
+        window.finish()
+    }
+
+    /// Decoded bytes waiting to be written, the last [`WINDOW_SIZE`] of them being kept for the repeats
+    struct Window<'a, W: Write> {
+        output: &'a mut W,
+        bytes: Vec<u8>,
+        /// number of bytes written to the output
+        written: usize,
+    }
+
+    impl<'a, W: Write> Window<'a, W> {
+        /// bytes kept before writing the oldest ones
+        const CAPACITY: usize = 4 * WINDOW_SIZE;
+
+        fn new(output: &'a mut W) -> Self {
+            Window {
+                output,
+                bytes: Vec::with_capacity(Self::CAPACITY),
+                written: 0,
+            }
+        }
+
+        /// write the bytes older than the window once there are too many of them
+        fn make_room(&mut self) -> Result<()> {
+            if self.bytes.len() >= Self::CAPACITY - MAX_STORED.max(MAX_MATCH) {
+                let old = self.bytes.len() - WINDOW_SIZE;
+                self.output.write_all(&self.bytes[..old])?;
+                self.bytes.drain(..old);
+                self.written += old;
+            }
+
+            Ok(())
+        }
+
+        fn push(&mut self, byte: u8) -> Result<()> {
+            self.make_room()?;
+            self.bytes.push(byte);
+            Ok(())
+        }
+
+        fn read_stored(
+            &mut self,
+            reader: &mut LsbStreamReader<impl Read>,
+            length: usize,
+        ) -> Result<()> {
+            self.make_room()?;
+
+            let start = self.bytes.len();
+            self.bytes.resize(start + length, 0);
+            reader.read_bytes(&mut self.bytes[start..])
+        }
+
+        fn repeat(&mut self, distance: usize, length: usize) -> Result<()> {
+            self.make_room()?;
+
+            // the window is never shorter than the largest distance once bytes were written
+            if distance > self.bytes.len() {
+                return Err(CompressorError::InvalidData(format!(
+                    "repeat at distance {distance} with {} bytes decoded",
+                    self.written + self.bytes.len()
+                )));
+            }
+
+            // the repeat can overlap the bytes it creates
+            let start = self.bytes.len() - distance;
+            for j in 0..length {
+                self.bytes.push(self.bytes[start + j]);
+            }
+
+            Ok(())
+        }
+
+        /// write the remaining bytes
+        ///
+        /// @**returns** usize => number of decoded bytes
+        fn finish(self) -> Result<usize> {
+            self.output.write_all(&self.bytes)?;
+            Ok(self.written + self.bytes.len())
+        }
+    }
+
+    fn read_dynamic_header(
+        reader: &mut LsbStreamReader<impl Read>,
+    ) -> Result<(CanonicalDecoder, CanonicalDecoder)> {
+        let literal_count = reader.read_bits(5)? as usize + 257;
+        let distance_count = reader.read_bits(5)? as usize + 1;
+        let code_length_count = reader.read_bits(4)? as usize + 4;
+
+        if literal_count > 286 || distance_count > DISTANCE_CODES {
+            return Err(CompressorError::InvalidData(format!(
+                "{literal_count} literal and {distance_count} distance codes"
+            )));
+        }
+
+        let mut code_length_lengths = [0; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_length_lengths[symbol] = reader.read_bits(3)? as u8;
+        }
+        let code_length_decoder = CanonicalDecoder::new(&code_length_lengths)?;
+
+        let count = literal_count + distance_count;
+        let mut lengths: Vec<u8> = Vec::with_capacity(count);
+        while lengths.len() < count {
+            let symbol = code_length_decoder.decode(|| reader.read_bit())?;
+
+            let (length, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *lengths.last().ok_or_else(|| {
+                        CompressorError::InvalidData(String::from(
+                            "repeat of the previous code length before the first one",
+                        ))
+                    })?;
+                    (previous, 3 + reader.read_bits(2)? as usize)
+                }
+                17 => (0, 3 + reader.read_bits(3)? as usize),
+                _ => (0, 11 + reader.read_bits(7)? as usize),
+            };
+
+            if lengths.len() + repeat > count {
+                return Err(CompressorError::InvalidData(String::from(
+                    "more code lengths than codes",
+                )));
+            }
+            lengths.extend(std::iter::repeat_n(length, repeat));
+        }
+
+        if lengths[END_OF_BLOCK] == 0 {
+            return Err(CompressorError::InvalidData(String::from(
+                "no code for the end of block",
+            )));
+        }
+
+        let (literal_lengths, distance_lengths) = lengths.split_at(literal_count);
+        Ok((
+            CanonicalDecoder::new(literal_lengths)?,
+            CanonicalDecoder::new(distance_lengths)?,
+        ))
+    }
+
+    fn inflate_block(
+        reader: &mut LsbStreamReader<impl Read>,
+        literal_decoder: &CanonicalDecoder,
+        distance_decoder: &CanonicalDecoder,
+        window: &mut Window<impl Write>,
+    ) -> Result<()> {
+        loop {
+            let symbol = literal_decoder.decode(|| reader.read_bit())?;
+
+            if symbol < END_OF_BLOCK {
+                window.push(symbol as u8)?;
+                continue;
+            }
+            if symbol == END_OF_BLOCK {
+                return Ok(());
+            }
+
+            let code = symbol - 257;
+            if code >= LENGTH_BASE.len() {
+                return Err(CompressorError::InvalidData(format!(
+                    "invalid length code {symbol}"
+                )));
+            }
+            let length = LENGTH_BASE[code] + reader.read_bits(LENGTH_EXTRA[code])? as usize;
+
+            let code = distance_decoder.decode(|| reader.read_bit())?;
+            if code >= DISTANCE_CODES {
+                return Err(CompressorError::InvalidData(format!(
+                    "invalid distance code {code}"
+                )));
+            }
+            let distance = DISTANCE_BASE[code] + reader.read_bits(DISTANCE_EXTRA[code])? as usize;
+
+            window.repeat(distance, length)?;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::test_utils::{assert_truncated, random_bytes, sample_texts};
+
+        /// a few words in a pattern, repeated far more than the random bytes
+        fn words() -> Vec<u8> {
+            (0..20_000)
+                .flat_map(|i: usize| {
+                    ["the ", "quick ", "brown ", "fox ", "jumps "][i * i % 5].bytes()
+                })
+                .collect()
+        }
+
+        fn texts() -> Vec<Vec<u8>> {
+            let mut texts = sample_texts(random_bytes(100_000));
+            texts.extend([vec![0xFF; 100_000], words()]);
+            texts
+        }
+
+        #[test]
+        fn deflate_round_trip() {
+            for text in texts() {
+                for level in [0, 1, DEFAULT_LEVEL, 9] {
+                    let encoded = encode(&text, level).unwrap();
+                    let (decoded, size) = decode_first(&encoded).unwrap();
+
+                    assert_eq!(text, decoded, "level {level}");
+                    assert_eq!(encoded.len(), size);
+                }
+            }
+
+            assert!(matches!(
+                encode(b"abc", 10),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+
+        #[test]
+        fn deflate_block_types() {
+            // only an end of block code in a fixed block
+            assert_eq!(vec![0x03, 0x00], encode(&[], DEFAULT_LEVEL).unwrap());
+
+            // random bytes are stored
+            let random = random_bytes(100_000);
+            let encoded = encode(&random, DEFAULT_LEVEL).unwrap();
+            assert_eq!(0b00, encoded[0] >> 1 & 0b11);
+            assert!(encoded.len() < random.len() * 1001 / 1000);
+
+            // repetitive text uses its own codes
+            let words = words();
+            let encoded = encode(&words, DEFAULT_LEVEL).unwrap();
+            assert_eq!(0b10, encoded[0] >> 1 & 0b11);
+            assert!(encoded.len() < words.len() / 20);
+        }
+
+        #[test]
+        fn deflate_known_streams() {
+            // "a" in a fixed block, as written by zlib
+            assert_eq!(b"a".to_vec(), decode(&[0x4B, 0x04, 0x00]).unwrap());
+
+            // "abcabcabc" stored
+            let stored = [
+                0x01, 0x09, 0x00, 0xF6, 0xFF, b'a', b'b', b'c', b'a', b'b', b'c', b'a', b'b', b'c',
+            ];
+            assert_eq!(b"abcabcabc".to_vec(), decode(&stored).unwrap());
+
+            // the bytes after the final block are not part of the stream
+            let mut encoded = encode(b"hello hello hello", DEFAULT_LEVEL).unwrap();
+            let size = encoded.len();
+            encoded.extend_from_slice(&[1, 2, 3, 4]);
+            assert_eq!(size, decode_first(&encoded).unwrap().1);
+        }
+
+        #[test]
+        fn deflate_invalid_input() {
+            let encoded = encode(&words(), DEFAULT_LEVEL).unwrap();
+            assert_truncated(decode, &encoded);
+
+            // reserved block type
+            assert!(matches!(
+                decode(&[0b111]),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            // wrong complement of a stored length
+            assert!(matches!(
+                decode(&[0x01, 0x01, 0x00, 0x00, 0x00, b'a']),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            // repeat before the start: fixed block, length code 257, distance code 0
+            assert!(matches!(
+                decode(&[0x03, 0x02, 0x00]),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,276 @@
+#[allow(non_snake_case)]
+pub mod DynamicMarkov {
+    use crate::{
+        error::{CompressorError, Result},
+        range_coder::{RangeDecoder, RangeEncoder},
+        utils::check_decoded_size,
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://webhome.cs.uvic.ca/~nigelh/Publications/DMC.pdf
+    // https://en.wikipedia.org/wiki/Dynamic_Markov_compression
+
+    /// the probabilities given to the range coder add up to 2^PROB_BITS
+    const PROB_BITS: u32 = 12;
+    const PROB_TOTAL: u64 = 1 << PROB_BITS;
+
+    /// counts are fixed point numbers, COUNT_ONE being a count of 1
+    const COUNT_ONE: u32 = 16;
+
+    /// starting count of every transition (0.2)
+    const INITIAL_COUNT: u32 = COUNT_ONE / 5;
+
+    /// a state is cloned when the transition taken to it, and the other transitions to it, have been
+    /// taken more than this
+    const CLONE_THRESHOLD: u32 = 2 * COUNT_ONE;
+
+    /// the counts of a state are halved past this total, so the recent bits weigh more
+    const MAX_COUNT: u32 = 0xFFFF * COUNT_ONE;
+
+    /// number of states before cloning, one for each bit of a byte after the bits before it
+    const INITIAL_STATES: usize = 255;
+
+    /// memory used by the model when none is given
+    pub const DEFAULT_MEMORY: usize = 32 << 20;
+
+    #[derive(Clone, Copy)]
+    struct State {
+        /// state reached after a 0 and after a 1
+        next: [u32; 2],
+        /// number of times each transition was taken
+        counts: [u32; 2],
+    }
+
+    /// Number of states fitting in the given memory
+    ///
+    /// @**returns** usize => states available, at least enough to clone some of the initial ones
+    pub fn max_states(memory: usize) -> usize {
+        (memory / size_of::<State>()).max(4 * INITIAL_STATES)
+    }
+
+    /// States of the Markov chain, grown by cloning the states reached from different contexts
+    struct Model {
+        states: Vec<State>,
+        max_states: usize,
+        current: usize,
+    }
+
+    impl Model {
+        fn new(max_states: usize) -> Self {
+            let mut model = Model {
+                states: Vec::new(),
+                max_states,
+                current: 0,
+            };
+            model.reset();
+
+            model
+        }
+
+        /// go back to a binary tree over the bits of a byte
+        ///
+        /// ```text
+        ///         1
+        ///      /     \
+        ///     2       3
+        ///    / \     / \
+        ///   4   5   6   7  ...  the leaves go back to the root
+        /// ```
+        fn reset(&mut self) {
+            self.states.clear();
+            self.states.extend((1..=INITIAL_STATES).map(|node| {
+                let next = [0, 1].map(|bit| {
+                    let child = 2 * node + bit;
+                    if child > INITIAL_STATES {
+                        0
+                    } else {
+                        child as u32 - 1
+                    }
+                });
+
+                State {
+                    next,
+                    counts: [INITIAL_COUNT; 2],
+                }
+            }));
+            self.current = 0;
+        }
+
+        /// reset the model if it may not have room for the states cloned during the next byte
+        fn start_byte(&mut self) {
+            if self.states.len() + 8 > self.max_states {
+                self.reset();
+            }
+        }
+
+        /// probability of the next bit being a 0, out of [`PROB_TOTAL`]
+        fn probability_of_zero(&self) -> u64 {
+            let [zeros, ones] = self.states[self.current].counts.map(|count| count as u64);
+            let probability = ((zeros + 1) * PROB_TOTAL) / (zeros + ones + 2);
+
+            probability.clamp(1, PROB_TOTAL - 1)
+        }
+
+        fn update(&mut self, bit: usize) {
+            let current = self.current;
+            let next = self.states[current].next[bit] as usize;
+            let count = self.states[current].counts[bit];
+            let next_total: u32 = self.states[next].counts.iter().sum();
+
+            // the next state is also reached from other contexts, it gets a copy of its own
+            if count > CLONE_THRESHOLD
+                && next_total.saturating_sub(count) > CLONE_THRESHOLD
+                && self.states.len() < self.max_states
+            {
+                let next_state = &mut self.states[next];
+                let mut clone = *next_state;
+                for i in 0..2 {
+                    clone.counts[i] =
+                        (next_state.counts[i] as u64 * count as u64 / next_total as u64) as u32;
+                    next_state.counts[i] -= clone.counts[i];
+                }
+
+                self.states[current].next[bit] = self.states.len() as u32;
+                self.states.push(clone);
+            }
+
+            let counts = &mut self.states[current].counts;
+            counts[bit] += COUNT_ONE;
+            if counts[bit] > MAX_COUNT {
+                counts[0] /= 2;
+                counts[1] /= 2;
+            }
+
+            self.current = self.states[current].next[bit] as usize;
+        }
+    }
+
+    /// encode an array of bytes with a dynamic Markov model driving a binary arithmetic coder
+    ///
+    /// @**returns** Vec\<u8\> => varsize maximum number of states, varsize number of bytes and the encoded bits
+    pub fn encode(input: &[u8], max_states: usize) -> Result<Vec<u8>> {
+        let mut model = Model::new(max_states);
+        let mut encoder = RangeEncoder::new();
+
+        for &byte in input {
+            model.start_byte();
+
+            for i in (0..8).rev() {
+                let bit = (byte >> i & 1) as usize;
+                let probability = model.probability_of_zero();
+
+                if bit == 0 {
+                    encoder.encode(0, probability, PROB_TOTAL);
+                } else {
+                    encoder.encode(probability, PROB_TOTAL, PROB_TOTAL);
+                }
+
+                model.update(bit);
+            }
+        }
+
+        let mut encoded = encode_varsize(max_states);
+        encoded.extend_from_slice(&encode_varsize(input.len()));
+        encoded.extend_from_slice(&encoder.finish());
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode`], with the number of states they were encoded with
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let (max_states, mut i) = get_first_decoded(input)?;
+        let (size, last_byte_found) = get_first_decoded(&input[i..])?;
+        i += last_byte_found;
+
+        if max_states < INITIAL_STATES + 8 {
+            return Err(CompressorError::InvalidData(format!(
+                "{max_states} states are not enough for the initial model"
+            )));
+        }
+        check_decoded_size(size)?;
+
+        let mut model = Model::new(max_states);
+        let mut decoder = RangeDecoder::new(&input[i..]);
+
+        let mut decoded = Vec::new();
+        while decoded.len() < size {
+            model.start_byte();
+
+            let mut byte = 0;
+            for _ in 0..8 {
+                let probability = model.probability_of_zero();
+
+                let bit = if decoder.target(PROB_TOTAL) < probability {
+                    decoder.consume(0, probability, PROB_TOTAL)?;
+                    0
+                } else {
+                    decoder.consume(probability, PROB_TOTAL, PROB_TOTAL)?;
+                    1
+                };
+
+                model.update(bit);
+                byte = (byte << 1) | bit as u8;
+            }
+
+            decoded.push(byte);
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::test_utils::{assert_truncated, random_bytes, sample_texts};
+        use crate::utils::MAX_BLOCK_SIZE;
+
+        #[test]
+        fn dmc_round_trip() {
+            for text in sample_texts(random_bytes(50_000)) {
+                let encoded = encode(&text, max_states(DEFAULT_MEMORY)).unwrap();
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+        }
+
+        #[test]
+        fn dmc_model_reset() {
+            let text: Vec<u8> = "AAAAACCCCadkjahsdkjashdkjashdjkashdkjashdCCCBBB"
+                .repeat(500)
+                .bytes()
+                .collect();
+
+            // the smallest model is reset many times
+            let small = encode(&text, max_states(0)).unwrap();
+            assert_eq!(text, decode(&small).unwrap());
+
+            let large = encode(&text, max_states(DEFAULT_MEMORY)).unwrap();
+            assert_eq!(text, decode(&large).unwrap());
+            assert!(large.len() < small.len());
+
+            // repetitive data compresses far better than order 0
+            assert!(large.len() < text.len() / 20, "{}", large.len());
+        }
+
+        #[test]
+        fn dmc_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".repeat(10).bytes().collect();
+            let encoded = encode(&text, max_states(DEFAULT_MEMORY)).unwrap();
+
+            assert_truncated(decode, &encoded);
+            assert!(matches!(
+                decode(&[1, 0]),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            let mut oversized = encode_varsize(max_states(DEFAULT_MEMORY));
+            oversized.extend_from_slice(&encode_varsize(MAX_BLOCK_SIZE + 1));
+            oversized.push(0);
+            assert!(matches!(
+                decode(&oversized),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
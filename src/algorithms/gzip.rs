@@ -0,0 +1,360 @@
+#[allow(non_snake_case)]
+pub mod Gzip {
+    use std::io::{self, BufRead, Read, Write};
+
+    use crate::{
+        algorithms::deflate::Deflate,
+        bit_io::LsbStreamReader,
+        checksum::crc32_continue,
+        error::{CompressorError, Result, warn_or_fail},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://www.rfc-editor.org/rfc/rfc1952
+
+    /// first bytes of a gzip member: the magic number and the DEFLATE compression method
+    pub const MAGIC: [u8; 3] = [0x1F, 0x8B, DEFLATE_METHOD];
+
+    /// bytes deflated at once by a [`GzipWriter`]
+    pub const PART_SIZE: usize = 1 << 20;
+
+    const DEFLATE_METHOD: u8 = 8;
+
+    const FLAG_HEADER_CRC: u8 = 1 << 1;
+    const FLAG_EXTRA: u8 = 1 << 2;
+    const FLAG_NAME: u8 = 1 << 3;
+    const FLAG_COMMENT: u8 = 1 << 4;
+
+    /// operating system of the header, unknown
+    const UNKNOWN_OS: u8 = 255;
+
+    /// encode an array of bytes as a single gzip member, readable by `gunzip`
+    ///
+    /// ```text
+    /// [1F 8B 08] [flags] [modification time: 0] [extra flags] [os] [DEFLATE blocks] [CRC-32] [size]
+    /// ```
+    ///
+    /// @**returns** Vec\<u8\> => the header, the DEFLATE stream, then the CRC-32 and size (mod 2^32) of the input
+    pub fn encode(input: &[u8], level: u8) -> Result<Vec<u8>> {
+        let mut writer = GzipWriter::new(Vec::new(), level)?;
+        writer.write_all(input)?;
+        writer.finish()
+    }
+
+    /// Compress everything written to it as a single gzip member, as [`encode`] does.
+    ///
+    /// The data is deflated in parts of [`PART_SIZE`] bytes, so the memory used does not depend on
+    /// its size. [`GzipWriter::finish`] must be called to write the end of the member, dropping the
+    /// writer also does it but ignores the errors.
+    pub struct GzipWriter<W: Write> {
+        /// None once finished
+        inner: Option<W>,
+        level: u8,
+        part: Vec<u8>,
+        checksum: u32,
+        /// size of the data mod 2^32
+        size: u32,
+    }
+
+    impl<W: Write> GzipWriter<W> {
+        /// write the header of the member, the level going from 0 (stored) to 9 (smallest)
+        pub fn new(mut inner: W, level: u8) -> Result<Self> {
+            let level = Deflate::check_level(level)?;
+
+            // extra flags: 2 for the slowest compression, 4 for the fastest
+            let extra_flags = match level {
+                9 => 2,
+                1 => 4,
+                _ => 0,
+            };
+
+            let mut header = Vec::with_capacity(10);
+            header.extend_from_slice(&MAGIC);
+            header.push(0);
+            header.extend_from_slice(&[0; 4]);
+            header.push(extra_flags);
+            header.push(UNKNOWN_OS);
+            inner.write_all(&header)?;
+
+            Ok(GzipWriter {
+                inner: Some(inner),
+                level,
+                part: Vec::new(),
+                checksum: 0,
+                size: 0,
+            })
+        }
+
+        /// deflate the remaining data, write the CRC-32 and size of the data and return the inner writer
+        pub fn finish(mut self) -> Result<W> {
+            self.write_end()?;
+
+            Ok(self.inner.take().expect("the writer is only finished once"))
+        }
+
+        fn inner(&mut self) -> &mut W {
+            self.inner
+                .as_mut()
+                .expect("the writer is not used after being finished")
+        }
+
+        fn write_part(&mut self, last: bool) -> Result<()> {
+            let deflated = Deflate::encode_part(&self.part, self.level, last)?;
+            self.inner().write_all(&deflated)?;
+            self.part.clear();
+
+            Ok(())
+        }
+
+        fn write_end(&mut self) -> Result<()> {
+            self.write_part(true)?;
+
+            let mut trailer = self.checksum.to_le_bytes().to_vec();
+            trailer.extend_from_slice(&self.size.to_le_bytes());
+            self.inner().write_all(&trailer)?;
+            self.inner().flush()?;
+
+            Ok(())
+        }
+    }
+
+    impl<W: Write> Write for GzipWriter<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut rest = buf;
+
+            while !rest.is_empty() {
+                let taken = rest.len().min(PART_SIZE - self.part.len());
+                self.part.extend_from_slice(&rest[..taken]);
+                self.checksum = crc32_continue(self.checksum, &rest[..taken]);
+                self.size = self.size.wrapping_add(taken as u32);
+                rest = &rest[taken..];
+
+                if self.part.len() == PART_SIZE {
+                    self.write_part(false)?;
+                }
+            }
+
+            Ok(buf.len())
+        }
+
+        /// deflate the buffered data (ending on a whole byte) and flush the inner writer
+        fn flush(&mut self) -> io::Result<()> {
+            if !self.part.is_empty() {
+                self.write_part(false)?;
+            }
+            self.inner().flush()
+        }
+    }
+
+    impl<W: Write> Drop for GzipWriter<W> {
+        fn drop(&mut self) {
+            if self.inner.is_some() {
+                let _ = self.write_end();
+            }
+        }
+    }
+
+    /// decode every gzip member of the input, one after the other
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        decode_to(input, &mut decoded, false)?;
+
+        Ok(decoded)
+    }
+
+    /// decode every gzip member read from `input`, the decoded bytes being written to `output` as they come
+    ///
+    /// A member whose CRC-32 or size does not match its decoded data is only a warning when forced.
+    pub fn decode_to(mut input: impl BufRead, output: &mut impl Write, force: bool) -> Result<()> {
+        let mut member = 1;
+        while member == 1 || !input.fill_buf()?.is_empty() {
+            read_header(&mut input)?;
+
+            let mut checked = ChecksumWriter {
+                inner: &mut *output,
+                checksum: 0,
+            };
+            let size = Deflate::decode_to(&mut LsbStreamReader::new(&mut input), &mut checked)?;
+            let found = checked.checksum;
+
+            let mut trailer = [0; 8];
+            input.read_exact(&mut trailer)?;
+
+            let expected = u32::from_le_bytes(trailer[..4].try_into().expect("4 bytes"));
+            if expected != found {
+                warn_or_fail(
+                    CompressorError::ChecksumMismatch {
+                        what: format!("uncompressed data of gzip member {member}"),
+                        expected,
+                        found,
+                    },
+                    force,
+                )?;
+            }
+
+            let expected_size = u32::from_le_bytes(trailer[4..].try_into().expect("4 bytes"));
+            if expected_size != size as u32 {
+                warn_or_fail(
+                    CompressorError::InvalidData(format!(
+                        "gzip member {member} of {expected_size} bytes decoded to {size} bytes"
+                    )),
+                    force,
+                )?;
+            }
+
+            member += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Compute the CRC-32 of the bytes written through it
+    struct ChecksumWriter<W: Write> {
+        inner: W,
+        checksum: u32,
+    }
+
+    impl<W: Write> Write for ChecksumWriter<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let written = self.inner.write(buf)?;
+            self.checksum = crc32_continue(self.checksum, &buf[..written]);
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    /// read the header of the member starting the input, leaving it at the DEFLATE stream
+    fn read_header(input: &mut impl Read) -> Result<()> {
+        let mut header = [0; 10];
+        input.read_exact(&mut header)?;
+
+        if !header.starts_with(&MAGIC) {
+            return Err(CompressorError::InvalidData(String::from(
+                "not a gzip member compressed with DEFLATE",
+            )));
+        }
+
+        let flags = header[3];
+        let mut byte = || -> Result<u8> {
+            let mut byte = [0];
+            input.read_exact(&mut byte)?;
+            Ok(byte[0])
+        };
+
+        if flags & FLAG_EXTRA != 0 {
+            let extra_size = u16::from_le_bytes([byte()?, byte()?]);
+            for _ in 0..extra_size {
+                byte()?;
+            }
+        }
+
+        // zero terminated strings
+        for flag in [FLAG_NAME, FLAG_COMMENT] {
+            if flags & flag != 0 {
+                while byte()? != 0 {}
+            }
+        }
+
+        if flags & FLAG_HEADER_CRC != 0 {
+            byte()?;
+            byte()?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::algorithms::deflate::Deflate::DEFAULT_LEVEL;
+
+        #[test]
+        fn gzip_round_trip() {
+            let text: Vec<u8> = "AAABBCCDACCAA".repeat(1000).bytes().collect();
+
+            for level in [0, 1, DEFAULT_LEVEL, 9] {
+                let encoded = encode(&text, level).unwrap();
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+
+            let encoded = encode(&[], DEFAULT_LEVEL).unwrap();
+            assert!(decode(&encoded).unwrap().is_empty());
+        }
+
+        #[test]
+        fn gzip_from_gzip() {
+            // `gzip hello.txt` of "hello", with a modification time of 0
+            let mut encoded = vec![0x1F, 0x8B, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03];
+            encoded.extend_from_slice(b"hello.txt\0");
+            encoded.extend_from_slice(&[
+                0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00, 0x86, 0xA6, 0x10, 0x36, 0x05, 0x00, 0x00,
+                0x00,
+            ]);
+            assert_eq!(b"hello".to_vec(), decode(&encoded).unwrap());
+
+            // members are decoded one after the other
+            let mut members = encode(b"hello ", DEFAULT_LEVEL).unwrap();
+            members.extend_from_slice(&encode(b"world", 0).unwrap());
+            assert_eq!(b"hello world".to_vec(), decode(&members).unwrap());
+        }
+
+        #[test]
+        fn gzip_in_parts() {
+            let text: Vec<u8> = (0..PART_SIZE * 2 + 1000)
+                .map(|i| (i % 251) as u8 ^ (i / 4093) as u8)
+                .collect();
+
+            let mut writer = GzipWriter::new(Vec::new(), DEFAULT_LEVEL).unwrap();
+            for chunk in text.chunks(300_000) {
+                writer.write_all(chunk).unwrap();
+            }
+            // the flushed data ends on a whole byte, the member going on after it
+            writer.flush().unwrap();
+            writer.write_all(b"end").unwrap();
+            let encoded = writer.finish().unwrap();
+
+            let mut expected = text.clone();
+            expected.extend_from_slice(b"end");
+            assert_eq!(expected, decode(&encoded).unwrap());
+
+            assert!(matches!(
+                GzipWriter::new(Vec::new(), 10),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+
+        #[test]
+        fn gzip_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".repeat(10).bytes().collect();
+            let encoded = encode(&text, DEFAULT_LEVEL).unwrap();
+
+            for end in 0..encoded.len() {
+                assert!(decode(&encoded[..end]).is_err());
+            }
+
+            let mut corrupted = encoded.clone();
+            let crc = corrupted.len() - 8;
+            corrupted[crc] ^= 1;
+            assert!(matches!(
+                decode(&corrupted),
+                Err(CompressorError::ChecksumMismatch { .. })
+            ));
+
+            // only a warning when forced
+            let mut decoded = Vec::new();
+            decode_to(&corrupted[..], &mut decoded, true).unwrap();
+            assert_eq!(text, decoded);
+
+            let mut corrupted = encoded.clone();
+            corrupted[0] = b'S';
+            assert!(matches!(
+                decode(&corrupted),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -8,7 +8,13 @@ use std::{
 };
 
 use crate::{
+    bit_io::MsbBitReader,
+    canonical_huffman::{
+        MAX_TABLE_CODE_LENGTH, MAX_TABLE_SYMBOLS, TableDecoder, canonical_codes, code_lengths,
+    },
     compressed_buffer::{Bit, CompressedBuffer},
+    error::{CompressorError, Result},
+    symbol::Symbol,
     varsize::{encode_varsize, get_first_decoded},
 };
 
@@ -20,45 +26,128 @@ const ENCODED_NULL_CHAR: char = 255 as char;
 // const ENCODED_NULL_CHAR: char = 1 as char;
 const INTERNAL_NODE_VALUE: char = '\0';
 
+/// A tree over 256 chars has at most 511 nodes, plus one for the doubled null char
+const MAX_TREE_SIZE: usize = 512;
+
+/// first byte of the canonical codes, the size of a tree never starts with it
+const CANONICAL: u8 = 0xFF;
+
+/// longest code, a code length is stored in 5 bits
+pub const MAX_CODE_LENGTH: u8 = 31;
+
+/// shortest limit of the code lengths, for the 256 bytes to have a code
+pub const MIN_CODE_LENGTH: u8 = 8;
+
+/// widest symbols whose code lengths are all stored, the code lengths of the wider ones follow the
+/// list of the symbols found in the input
+const DENSE_SYMBOL_BITS: u32 = 8;
+
+/// number of bits of a code length in the run-length coded lengths, the other bits are the run
+const LENGTH_BITS: u32 = 5;
+const MAX_LENGTH_RUN: usize = 1 << (8 - LENGTH_BITS);
+
+/// Code lengths of the symbols, each byte holding a length (5 bits) and how many symbols in a row
+/// have it, minus one (3 bits).
+///
+/// ## Example:
+///
+/// ```text
+/// [0 x 65][2, 1, 2][0 x 189] => [0 | 7, 0 | 7, ..., 0 | 0, 2 | 0, 1 | 0, 2 | 0, 0 | 7, ...]
+/// ```
+fn encode_code_lengths(lengths: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+
+    let mut i = 0;
+    while i < lengths.len() {
+        let length = lengths[i];
+        let run = lengths[i..]
+            .iter()
+            .take(MAX_LENGTH_RUN)
+            .take_while(|&&other| other == length)
+            .count();
+
+        encoded.push(length | ((run - 1) as u8) << LENGTH_BITS);
+        i += run;
+    }
+
+    encoded
+}
+
+/// @**returns** (Vec\<u8\>, usize) => (code length of the `count` symbols, number of bytes read)
+fn decode_code_lengths(input: &[u8], count: usize) -> Result<(Vec<u8>, usize)> {
+    let mut lengths = Vec::new();
+
+    let mut i = 0;
+    while lengths.len() < count {
+        let byte = *input.get(i).ok_or(CompressorError::TruncatedInput)?;
+        let length = byte & ((1 << LENGTH_BITS) - 1);
+        let run = (byte >> LENGTH_BITS) as usize + 1;
+
+        if lengths.len() + run > count {
+            return Err(CompressorError::InvalidData(format!(
+                "huffman code lengths of more than {count} symbols"
+            )));
+        }
+        lengths.resize(lengths.len() + run, length);
+        i += 1;
+    }
+
+    Ok((lengths, i))
+}
+
+/// decode the first `bit_length` bits of the input, which must all be read, `symbol` giving the
+/// symbol of each index of the decoder
+fn decode_with_table<T>(
+    decoder: &TableDecoder,
+    compressed_data: &[u8],
+    bit_length: usize,
+    symbol: impl Fn(usize) -> T,
+) -> Result<Vec<T>> {
+    let mut reader = MsbBitReader::new(compressed_data);
+    let mut decoded = Vec::new();
+    while reader.bits_read() < bit_length {
+        decoded.push(symbol(decoder.decode(&mut reader)?));
+    }
+
+    if reader.bits_read() > bit_length {
+        return Err(CompressorError::InvalidData(format!(
+            "huffman code crossing the end of the {bit_length} bits"
+        )));
+    }
+
+    Ok(decoded)
+}
+
+/// a symbol (a char of the input by default) and how many times it is found
 #[derive(Debug, Eq, PartialEq, PartialOrd)]
-pub struct FrequencyChar(pub char, pub usize);
+pub struct FrequencyChar<S = char>(pub S, pub usize);
 
 #[derive(Debug, Eq, PartialEq)]
-struct HeapNode {
+struct HeapNode<S> {
     pub frequency: usize,
-    pub c: Option<char>,
-    pub left: Option<Box<HeapNode>>,
-    pub right: Option<Box<HeapNode>>,
+    pub c: Option<S>,
+    pub left: Option<Box<HeapNode<S>>>,
+    pub right: Option<Box<HeapNode<S>>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct Node {
-    pub c: Option<char>,
-    pub left: Option<Box<Node>>,
-    pub right: Option<Box<Node>>,
+struct Node<S> {
+    pub c: Option<S>,
+    pub left: Option<Box<Node<S>>>,
+    pub right: Option<Box<Node<S>>>,
 }
 
-impl PartialOrd for HeapNode {
+impl<S: Symbol> PartialOrd for HeapNode<S> {
     // fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
     //     other.frequency.partial_cmp(&self.frequency)
     // }
 
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match other.frequency.partial_cmp(&self.frequency) {
-            Some(Ordering::Equal) => {
-                // We prioritize the node with nothing inside
-                match (self.c.is_none(), other.c.is_none()) {
-                    (true, false) => Some(Ordering::Greater),
-                    (false, true) => Some(Ordering::Less),
-                    _ => Some(Ordering::Equal),
-                }
-            }
-            other => other,
-        }
+        Some(self.cmp(other))
     }
 }
 
-impl Ord for HeapNode {
+impl<S: Symbol> Ord for HeapNode<S> {
     // fn cmp(&self, other: &Self) -> Ordering {
     //     other.frequency.cmp(&self.frequency)
     // }
@@ -78,8 +167,8 @@ impl Ord for HeapNode {
     }
 }
 
-impl HeapNode {
-    pub fn new(c: Option<char>, frequency: usize) -> Self {
+impl<S: Symbol> HeapNode<S> {
+    pub fn new(c: Option<S>, frequency: usize) -> Self {
         HeapNode {
             c,
             frequency,
@@ -88,7 +177,7 @@ impl HeapNode {
         }
     }
 
-    pub fn convert_to_node(&self) -> Node {
+    pub fn convert_to_node(&self) -> Node<S> {
         let mut node = Node::new(self.c);
 
         if let Some(l) = &self.left {
@@ -111,8 +200,8 @@ impl HeapNode {
     }
 }
 
-impl Node {
-    pub fn new(c: Option<char>) -> Self {
+impl<S: Symbol> Node<S> {
+    pub fn new(c: Option<S>) -> Self {
         Node {
             c,
             left: None,
@@ -134,11 +223,11 @@ impl Node {
 
         count
     }
-    pub fn get_encoding(&self, encoding: Vec<Bit>) -> Vec<(char, Vec<Bit>)> {
+    pub fn get_encoding(&self, encoding: Vec<Bit>) -> Vec<(S, Vec<Bit>)> {
         if let Some(c) = self.c {
-            return vec![(c, encoding)];
+            vec![(c, encoding)]
         } else {
-            let mut sub_encodings: Vec<(char, Vec<Bit>)> = Vec::new();
+            let mut sub_encodings: Vec<(S, Vec<Bit>)> = Vec::new();
 
             // get left sub encodings
             if let Some(l) = &self.left {
@@ -156,65 +245,12 @@ impl Node {
                 sub_encodings.append(&mut right_sub_encodings);
             }
 
-            return sub_encodings;
-        }
-    }
-
-    pub fn print_encoding(&self, encoding: Vec<u8>) {
-        if let Some(c) = self.c {
-            print!("'{}':    \t", c.escape_default());
-            // print!("{}: ", self.c);
-
-            for bit in encoding {
-                print!("{}", bit);
-            }
-
-            println!();
-        } else {
-            if let Some(l) = &self.left {
-                let mut new_encoding = encoding.clone();
-                new_encoding.push(0);
-                l.print_encoding(new_encoding);
-            }
-
-            if let Some(r) = &self.right {
-                let mut new_encoding = encoding.clone();
-                new_encoding.push(1);
-                r.print_encoding(new_encoding);
-            }
-        }
-    }
-
-    pub fn print_as_tree(&self, prefix: &str, is_left: bool) {
-        print!("{}", prefix);
-
-        if is_left {
-            print!("├──");
-        } else {
-            print!("└──")
-        }
-
-        match self.c {
-            Some(c) => println!("('{}')", c.escape_default()),
-            // Some(c) => println!("({})", c as u8),
-            None => println!("()"),
-        }
-
-        let new_prefix = if is_left {
-            format!("{}|   ", prefix)
-        } else {
-            format!("{}    ", prefix)
-        };
-
-        if let Some(l) = &self.left {
-            l.print_as_tree(&new_prefix, true);
-        }
-
-        if let Some(r) = &self.right {
-            r.print_as_tree(&new_prefix, false);
+            sub_encodings
         }
     }
+}
 
+impl Node<char> {
     pub fn convert_to_vec(&self, values: &mut Vec<char>) {
         // values.push(self.c);
         match self.c {
@@ -239,17 +275,18 @@ impl Node {
     }
 }
 
-pub struct HuffmanTree {
-    root: Node,
-    encoding: HashMap<char, Vec<Bit>>,
+/// Huffman codes of the symbols of an input, the chars of the bytes by default
+pub struct HuffmanTree<S = char> {
+    root: Node<S>,
+    encoding: HashMap<S, Vec<Bit>>,
 }
 
 // DEBUG use to see bad encoding
-impl PartialEq for HuffmanTree {
+impl<S: Symbol> PartialEq for HuffmanTree<S> {
     fn eq(&self, other: &Self) -> bool {
-        let print_error = |c: &char, bits: &Vec<Bit>, other_bits: &Vec<Bit>| {
-            println!("left: '{}': {bits:?}", c.escape_default());
-            println!("right: '{}': {other_bits:?}", c.escape_default());
+        let print_error = |c: &S, bits: &Vec<Bit>, other_bits: &Vec<Bit>| {
+            println!("left: {c:?}: {bits:?}");
+            println!("right: {c:?}: {other_bits:?}");
         };
 
         let mut valid = true;
@@ -258,10 +295,7 @@ impl PartialEq for HuffmanTree {
             let other_bits = match other.encoding.get(c) {
                 Some(other_bits) => other_bits,
                 None => {
-                    println!(
-                        "right does not have an encoding for the char {}",
-                        c.escape_default()
-                    );
+                    println!("right does not have an encoding for the symbol {c:?}");
                     // return false;
                     valid = false;
                     continue;
@@ -291,8 +325,15 @@ impl PartialEq for HuffmanTree {
     }
 }
 
-impl HuffmanTree {
-    pub fn new(frequencies: &mut Vec<FrequencyChar>) -> Self {
+impl<S: Symbol> HuffmanTree<S> {
+    /// tree of the given frequencies, there must be at least one symbol
+    pub fn new(frequencies: &mut Vec<FrequencyChar<S>>) -> Result<Self> {
+        if frequencies.is_empty() {
+            return Err(CompressorError::InvalidArgument(String::from(
+                "a huffman tree needs at least one symbol",
+            )));
+        }
+
         let mut min_heap = BinaryHeap::new();
 
         for f in frequencies {
@@ -321,24 +362,19 @@ impl HuffmanTree {
             let mut top = HeapNode::new(None, new_frequency);
 
             // update left node
-            top.left = if let Some(l) = left {
-                Some(Box::new(l))
-            } else {
-                None
-            };
+            top.left = left.map(Box::new);
 
             // update right node
-            top.right = if let Some(r) = right {
-                Some(Box::new(r))
-            } else {
-                None
-            };
+            top.right = right.map(Box::new);
 
             // push a new internal node into the heap
             min_heap.push(top);
         }
 
-        let root = min_heap.pop().unwrap().convert_to_node();
+        let root = min_heap
+            .pop()
+            .expect("at least one symbol was pushed")
+            .convert_to_node();
 
         // DEBUG
         // println!("value: {}, frequency: {}", root.c, root.frequency);
@@ -350,26 +386,27 @@ impl HuffmanTree {
 
         tree.set_encoding();
 
-        tree
+        Ok(tree)
     }
 
-    pub fn load_tree_from_bytes(bytes: &[u8]) -> HuffmanTree {
-        let mut map: HashMap<char, usize> = HashMap::new();
+    /// tree of the frequencies of the symbols, there must be at least one
+    pub fn from_symbols(symbols: &[S]) -> Result<Self> {
+        let mut map: HashMap<S, usize> = HashMap::new();
 
-        for &c in bytes {
-            let entry = map.get_mut(&(c as char));
+        for &c in symbols {
+            let entry = map.get_mut(&c);
 
             match entry {
                 Some(value) => {
                     *value += 1;
                 }
                 None => {
-                    map.insert(c as char, 1);
+                    map.insert(c, 1);
                 }
             }
         }
 
-        let mut frequencies: Vec<FrequencyChar> = map
+        let mut frequencies: Vec<FrequencyChar<S>> = map
             .iter()
             .map(|(c, freq)| FrequencyChar(*c, *freq))
             .collect();
@@ -381,44 +418,311 @@ impl HuffmanTree {
         self.root.count()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_encoding(&self) -> Vec<(S, Vec<Bit>)> {
+        self.root.get_encoding(Vec::new())
+    }
+
+    fn set_encoding(&mut self) {
+        let encoding = self.get_encoding();
+
+        for (c, bits) in encoding {
+            self.encoding.insert(c, bits);
+        }
+    }
+
+    /// symbols given a code length: the 256 bytes for the narrow symbols, otherwise the symbols of
+    /// the input in increasing order
+    fn alphabet(symbols: &[S]) -> Vec<S> {
+        if S::BITS <= DENSE_SYMBOL_BITS {
+            return (0..1 << S::BITS).filter_map(S::from_usize).collect();
+        }
+
+        let mut alphabet = symbols.to_vec();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+
+        alphabet
+    }
+
+    /// The symbols in increasing order, as runs of consecutive symbols.
+    ///
+    /// ```text
+    /// [number of runs][values skipped before the run][number of symbols of the run - 1]...
+    /// [3, 5, 1, 70, 9] => [2][3][2][0][0]
+    /// ```
+    fn encode_alphabet(alphabet: &[S]) -> Vec<u8> {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for symbol in alphabet.iter().map(|symbol| symbol.to_usize()) {
+            match runs.last_mut() {
+                Some((start, length)) if *start + *length == symbol => *length += 1,
+                _ => runs.push((symbol, 1)),
+            }
+        }
+
+        let mut encoded = encode_varsize(runs.len());
+        let mut next = 0;
+        for (start, length) in runs {
+            encoded.extend_from_slice(&encode_varsize(start - next));
+            encoded.extend_from_slice(&encode_varsize(length - 1));
+            next = start + length;
+        }
+
+        encoded
+    }
+
+    /// @**returns** (Vec\<S\>, usize) => (the symbols of [`HuffmanTree::encode_alphabet`], number of bytes read)
+    fn decode_alphabet(input: &[u8]) -> Result<(Vec<S>, usize)> {
+        let (num_runs, mut i) = get_first_decoded(input)?;
+
+        let mut alphabet = Vec::new();
+        let mut next: usize = 0;
+        for _ in 0..num_runs {
+            let (skipped, size) = get_first_decoded(&input[i..])?;
+            i += size;
+            let (length, size) = get_first_decoded(&input[i..])?;
+            i += size;
+
+            let start = next.checked_add(skipped);
+            let last = start.and_then(|start| start.checked_add(length));
+            let (Some(start), Some(_)) = (start, last.and_then(S::from_usize)) else {
+                return Err(CompressorError::InvalidData(format!(
+                    "huffman symbol of more than {} bits",
+                    S::BITS
+                )));
+            };
+            // every symbol has a code length, coded with at least a bit in the rest of the input
+            let max_symbols = (input.len() - i)
+                .saturating_mul(8)
+                .min(1 << MAX_CODE_LENGTH)
+                .min(1usize.checked_shl(S::BITS).unwrap_or(usize::MAX));
+            if alphabet.len().saturating_add(length) >= max_symbols {
+                return Err(CompressorError::InvalidData(format!(
+                    "more huffman symbols than the {max_symbols} codes the input can hold"
+                )));
+            }
+
+            alphabet.extend(
+                (start..=start + length)
+                    .map(|symbol| S::from_usize(symbol).expect("smaller than the last symbol")),
+            );
+            next = start + length + 1;
+        }
+
+        Ok((alphabet, i))
+    }
+
+    /// encode the symbols with canonical codes, preceded by the code lengths from which the decoder
+    /// builds the same codes, no code being longer than `max_code_length` bits
+    ///
+    /// ## Example:
+    ///
+    /// It would be represented as follow, for the symbols of at most 8 bits:
+    /// [FF][run-length coded code lengths][number of bits][encoded data]
+    ///
+    /// and for the wider symbols, only the ones found in the input having a code length:
+    /// [FF][runs of symbols][size of the code lengths][code lengths][number of bits][encoded data]
+    ///
+    /// the code lengths of the wider symbols are themselves encoded as bytes, a dictionary coder
+    /// giving each of its thousands of codes a length of about the same number of bits
+    pub fn encode_symbols(symbols: &[S], max_code_length: u8) -> Result<Vec<u8>> {
+        if !(MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&max_code_length) {
+            return Err(CompressorError::InvalidArgument(format!(
+                "huffman codes of at most {max_code_length} bits are not between {MIN_CODE_LENGTH} and {MAX_CODE_LENGTH} bits"
+            )));
+        }
+
+        let alphabet = HuffmanTree::alphabet(symbols);
+        if alphabet.len() > 1 << max_code_length {
+            return Err(CompressorError::InvalidArgument(format!(
+                "{} symbols do not fit in huffman codes of at most {max_code_length} bits",
+                alphabet.len()
+            )));
+        }
+        if alphabet.len() > MAX_TABLE_SYMBOLS {
+            return Err(CompressorError::InvalidArgument(format!(
+                "{} huffman symbols, the decoder reads at most {MAX_TABLE_SYMBOLS} of them",
+                alphabet.len()
+            )));
+        }
+
+        let indexes: Vec<usize> = if S::BITS <= DENSE_SYMBOL_BITS {
+            symbols.iter().map(|symbol| symbol.to_usize()).collect()
+        } else {
+            symbols
+                .iter()
+                .map(|symbol| alphabet.binary_search(symbol).expect("symbol of the input"))
+                .collect()
+        };
+
+        let mut frequencies = vec![0usize; alphabet.len()];
+        for &index in &indexes {
+            frequencies[index] += 1;
+        }
+
+        let lengths = code_lengths(&frequencies, max_code_length);
+        let codes = canonical_codes(&lengths);
+
+        let mut compressed_buffer = CompressedBuffer::new();
+        let mut num_bits: usize = 0;
+        for &index in &indexes {
+            let length = lengths[index] as u32;
+            compressed_buffer.push_bits(codes[index] as usize, length);
+            num_bits += length as usize;
+        }
+
+        let mut encoded = vec![CANONICAL];
+        if S::BITS <= DENSE_SYMBOL_BITS {
+            encoded.extend_from_slice(&encode_code_lengths(&lengths));
+        } else {
+            encoded.append(&mut HuffmanTree::encode_alphabet(&alphabet));
+
+            let coded_lengths = HuffmanTree::<u8>::encode_symbols(&lengths, MAX_CODE_LENGTH)?;
+            encoded.extend_from_slice(&encode_varsize(coded_lengths.len()));
+            encoded.extend_from_slice(&coded_lengths);
+        }
+        encoded.extend_from_slice(&encode_varsize(num_bits));
+        encoded.append(&mut compressed_buffer.buffer);
+
+        Ok(encoded)
+    }
+
+    /// decode the symbols of [`HuffmanTree::encode_symbols`]
+    pub fn decode_symbols(input: &[u8]) -> Result<Vec<S>> {
+        if input.first() != Some(&CANONICAL) {
+            return Err(CompressorError::InvalidData(String::from(
+                "huffman symbols without their code lengths",
+            )));
+        }
+
+        let mut i = 1;
+        let (alphabet, lengths) = if S::BITS <= DENSE_SYMBOL_BITS {
+            let (lengths, lengths_size) = decode_code_lengths(&input[i..], 256)?;
+            i += lengths_size;
+
+            (HuffmanTree::alphabet(&[]), lengths)
+        } else {
+            let (alphabet, alphabet_size) = HuffmanTree::decode_alphabet(&input[i..])?;
+            i += alphabet_size;
+
+            let (lengths_size, size) = get_first_decoded(&input[i..])?;
+            i += size;
+            let lengths_end = i
+                .checked_add(lengths_size)
+                .filter(|&end| end <= input.len())
+                .ok_or(CompressorError::TruncatedInput)?;
+            let lengths = HuffmanTree::<u8>::decode_symbols(&input[i..lengths_end])?;
+            i = lengths_end;
+
+            if lengths.len() != alphabet.len()
+                || lengths.iter().any(|&length| length > MAX_CODE_LENGTH)
+            {
+                return Err(CompressorError::InvalidData(format!(
+                    "invalid huffman code lengths of {} symbols",
+                    alphabet.len()
+                )));
+            }
+
+            (alphabet, lengths)
+        };
+        let decoder = TableDecoder::new(&lengths)?;
+
+        let (bit_length, size_last_byte_index) = get_first_decoded(&input[i..])?;
+        let compressed_data = &input[i + size_last_byte_index..];
+        if bit_length.div_ceil(8) > compressed_data.len() {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        decode_with_table(&decoder, compressed_data, bit_length, |index| {
+            alphabet[index]
+        })
+    }
+}
+
+impl HuffmanTree {
+    pub fn load_tree_from_bytes(bytes: &[u8]) -> Result<HuffmanTree> {
+        let chars: Vec<char> = bytes.iter().map(|&c| c as char).collect();
+
+        HuffmanTree::from_symbols(&chars)
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         let bytes: Vec<u8> = self.convert_to_vec().iter().map(|&c| c as u8).collect();
 
         bytes
     }
 
-    pub fn get_encoding(&self) -> Vec<(char, Vec<Bit>)> {
-        self.root.get_encoding(Vec::new())
-    }
-
     /// encode given bytes
     ///
     /// @**returns** (usize, Vec\<u8\>) => (number of bits encoded, the encoded bytes)
-    pub fn encode(&self, bytes: &[u8]) -> (usize, Vec<u8>) {
+    pub fn encode(&self, bytes: &[u8]) -> Result<(usize, Vec<u8>)> {
         let mut compressed_buffer = CompressedBuffer::new();
         let mut num_bits: usize = 0;
 
         for &byte in bytes {
-            let new_bits = self[byte as char].clone();
+            let new_bits = self.encoding.get(&(byte as char)).ok_or_else(|| {
+                CompressorError::InvalidData(format!("no huffman code for the byte {byte}"))
+            })?;
 
-            for bit in new_bits {
+            for &bit in new_bits {
                 compressed_buffer.push_bit(bit);
                 num_bits += 1;
             }
         }
 
-        (num_bits, compressed_buffer.get_buffer())
+        Ok((num_bits, compressed_buffer.get_buffer()))
+    }
+
+    /// return the encoding preceded by the code length of every byte, from which the decoder
+    /// builds the same canonical codes.
+    ///
+    /// ## Example:
+    ///
+    /// It would be represented as follow:
+    /// [FF][run-length coded code lengths][number of bits][encoded data]
+    ///
+    /// An empty input is represented by an empty tree: [0]
+    pub fn encode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        HuffmanTree::encode_with_max_length(input, MAX_CODE_LENGTH)
+    }
+
+    /// same as [`HuffmanTree::encode_with_metadatas`], no code being longer than `max_code_length`
+    /// bits (from [`MIN_CODE_LENGTH`] to [`MAX_CODE_LENGTH`])
+    pub fn encode_with_max_length(input: &[u8], max_code_length: u8) -> Result<Vec<u8>> {
+        if !(MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&max_code_length) {
+            return Err(CompressorError::InvalidArgument(format!(
+                "huffman codes of at most {max_code_length} bits are not between {MIN_CODE_LENGTH} and {MAX_CODE_LENGTH} bits"
+            )));
+        }
+
+        if input.is_empty() {
+            return HuffmanTree::encode_with_tree(input);
+        }
+
+        HuffmanTree::<u8>::encode_symbols(input, max_code_length)
     }
 
-    /// return the encoding preceded by the tree content and the size of said tree.
+    /// return the encoding preceded by the tree content and the size of said tree, the format
+    /// written before [`HuffmanTree::encode_with_metadatas`] stored canonical codes.
     ///
     /// ## Example:
     ///
     /// It would be represented as follow:
     /// [tree_size][tree_content][encoded data]
-    pub fn encode_with_metadatas(input: &[u8]) -> Vec<u8> {
+    ///
+    /// An empty input is represented by an empty tree: [0]
+    pub fn encode_with_tree(input: &[u8]) -> Result<Vec<u8>> {
         let mut encoded: Vec<u8> = Vec::new();
-        let tree = HuffmanTree::load_tree_from_bytes(&input);
+
+        if input.is_empty() {
+            encoded.extend_from_slice(&encode_varsize(0));
+            return Ok(encoded);
+        }
+
+        let tree = HuffmanTree::load_tree_from_bytes(input)?;
 
         let tree_to_byte = tree.as_bytes();
 
@@ -433,31 +737,78 @@ impl HuffmanTree {
             encoded.push(byte);
         }
 
-        let (num_bits, encoded_data) = tree.encode(input);
+        let (num_bits, encoded_data) = tree.encode(input)?;
         let num_bits = encode_varsize(num_bits);
 
         // Add converted bits and their number
         encoded.extend_from_slice(&num_bits);
         encoded.extend_from_slice(&encoded_data);
 
-        encoded
+        Ok(encoded)
     }
 
-    pub fn decode_with_metadatas(input: &[u8]) -> Vec<u8> {
+    /// decode the output of [`HuffmanTree::encode_with_metadatas`] or [`HuffmanTree::encode_with_tree`]
+    pub fn decode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        if input.first() == Some(&CANONICAL) {
+            return HuffmanTree::<u8>::decode_symbols(input);
+        }
+
         // extracting tree
-        let (tree_size, tree_content_start) = get_first_decoded(&input);
-        let tree_content = &input[tree_content_start..(tree_content_start + tree_size)];
-        let tree = HuffmanTree::from(tree_content);
+        let (tree_size, tree_content_start) = get_first_decoded(input)?;
+        if tree_size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let tree_content_end = tree_content_start
+            .checked_add(tree_size)
+            .filter(|&end| end <= input.len())
+            .ok_or(CompressorError::TruncatedInput)?;
+        let tree_content = &input[tree_content_start..tree_content_end];
+        let tree = HuffmanTree::try_from(tree_content)?;
 
-        let compressed_data = &input[(tree_content_start + tree_size)..];
-        let (size, size_last_byte_index) = get_first_decoded(compressed_data);
+        let compressed_data = &input[tree_content_end..];
+        let (size, size_last_byte_index) = get_first_decoded(compressed_data)?;
 
         let compressed_data = &compressed_data[size_last_byte_index..];
 
-        tree.decode(&compressed_data, size)
+        tree.decode(compressed_data, size)
+    }
+
+    /// decode the first `bit_length` bits of the bytes with a lookup table of the codes of the tree
+    pub fn decode(&self, bytes: &[u8], bit_length: usize) -> Result<Vec<u8>> {
+        if bit_length.div_ceil(8) > bytes.len() {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        let encoding = self.get_encoding();
+        if encoding
+            .iter()
+            .any(|(_, bits)| bits.len() > MAX_TABLE_CODE_LENGTH as usize)
+        {
+            return self.decode_bit_by_bit(bytes, bit_length);
+        }
+
+        let codes: Vec<(usize, u32, u8)> = encoding
+            .iter()
+            .map(|(c, bits)| {
+                let code = bits.iter().fold(0, |code, &bit| code << 1 | bit as u32);
+                (*c as usize, code, bits.len() as u8)
+            })
+            .collect();
+
+        // the bits missing a child of the tree match no code
+        let tree_error = |error| match error {
+            CompressorError::InvalidData(_) => CompressorError::InvalidTree,
+            error => error,
+        };
+        let decoder = TableDecoder::from_codes(&codes).map_err(tree_error)?;
+
+        decode_with_table(&decoder, bytes, bit_length, |c| c as u8).map_err(tree_error)
     }
 
-    pub fn decode(&self, bytes: &[u8], bit_length: usize) -> Vec<u8> {
+    /// same as [`HuffmanTree::decode`], walking down the tree for each bit, for the trees too
+    /// deep for a lookup table
+    fn decode_bit_by_bit(&self, bytes: &[u8], bit_length: usize) -> Result<Vec<u8>> {
         let mut node = &self.root;
         let mut decoded = Vec::new();
         let mut visited_bits = 0;
@@ -477,13 +828,11 @@ impl HuffmanTree {
                 // println!("bit: {}", bit);
                 // println!("bit: {:#010b}\n", bit);
 
-                match bit {
-                    0 => node = node.left.as_ref().unwrap(),
-                    1 => node = node.right.as_ref().unwrap(),
-                    _ => {
-                        panic!("Cannot index encoding with a non binary value")
-                    }
-                }
+                let child = match bit {
+                    0 => node.left.as_ref(),
+                    _ => node.right.as_ref(),
+                };
+                node = child.ok_or(CompressorError::InvalidTree)?;
 
                 if let Some(c) = node.c {
                     // DEBUG
@@ -496,23 +845,7 @@ impl HuffmanTree {
             }
         }
 
-        decoded
-    }
-
-    fn set_encoding(&mut self) {
-        let encoding = self.get_encoding();
-
-        for (c, bits) in encoding {
-            self.encoding.insert(c, bits);
-        }
-    }
-
-    pub fn print_encoding(&self) {
-        self.root.print_encoding(Vec::new());
-    }
-
-    pub fn print_tree(&self) {
-        self.root.print_as_tree("", false);
+        Ok(decoded)
     }
 
     pub fn convert_to_vec(&self) -> Vec<char> {
@@ -523,30 +856,24 @@ impl HuffmanTree {
         values
     }
 
-    pub fn save_as_file(&self, file_path: &str) {
+    pub fn save_as_file(&self, file_path: &str) -> Result<()> {
         let tree = self.convert_to_vec();
         let bytes: Vec<u8> = tree.iter().map(|c| *c as u8).collect();
 
-        let mut output_f = File::create(&file_path)
-            .expect("Failed to create file in src/huffman_tree.rs => fn save_as_file");
-
-        output_f
-            .write_all(&bytes)
-            .expect("Failed to write to file in src/huffman_tree.rs => fn save_as_file");
+        let mut output_f = File::create(file_path)?;
+        output_f.write_all(&bytes)?;
+        output_f.flush()?;
 
-        output_f
-            .flush()
-            .expect("Failed to flush in src/huffman_tree.rs => fn save_as_file");
+        Ok(())
     }
 
-    pub fn load_from_file(file_path: &str) -> Self {
-        let decoded_tree: Vec<char> = fs::read(file_path)
-            .expect("Failed to read file in src/huffman_tree.rs => fn load_from_file")
+    pub fn load_from_file(file_path: &str) -> Result<Self> {
+        let decoded_tree: Vec<char> = fs::read(file_path)?
             .iter()
             .map(|byte| *byte as char)
             .collect();
 
-        HuffmanTree::from(decoded_tree)
+        HuffmanTree::try_from(decoded_tree)
     }
 }
 
@@ -577,7 +904,7 @@ impl Index<Vec<u8>> for HuffmanTree {
         }
 
         if let Some(c) = &node.c {
-            return c;
+            c
         } else {
             panic!("Attempting to return internal node which should be impossible.");
         }
@@ -601,42 +928,41 @@ fn slice_contains_nullchar(values: &[char]) -> bool {
 //             ^ ^ the 2 last digit are the encoded value
 //             | the function mistake is as: ['\u{0}', '\u{1}']
 //                               instead of: ['\u{1}', '\u{0}']
-fn node_from_vec(values: &Vec<char>, index: usize) -> Node {
-    let c = values[index];
+fn node_from_vec(values: &Vec<char>, index: usize) -> Result<Node<char>> {
+    let c = *values.get(index).ok_or(CompressorError::InvalidTree)?;
 
     let mut left = None;
     let mut right = None;
 
     // Last Value
     if index + 1 == values.len() {
-        return Node::new(Some(c));
+        return Ok(Node::new(Some(c)));
     }
 
     // Special scenario: null char are encoded as 0x01 0x01
     if c == ENCODED_NULL_CHAR && values[index + 1] == ENCODED_NULL_CHAR {
         // if c == (1 as char) && values[index + 1] == (1 as char) {
-        return Node::new(Some(LEAF_NULL_CHAR));
+        return Ok(Node::new(Some(LEAF_NULL_CHAR)));
     }
 
     if values[index] == INTERNAL_NODE_VALUE {
-        left = Some(Box::new(node_from_vec(values, index + 1)));
+        let left_node = node_from_vec(values, index + 1)?;
         // We know that the next right value is after all the values of the left
         // node so we just need to skip them
-        let mut count = left.as_ref().unwrap().count();
+        let mut count = left_node.count();
+        left = Some(Box::new(left_node));
 
         // in case the left node was a null char else if null char in right
-        if count == 1
-            && values[index + 1] == ENCODED_NULL_CHAR
-            && values[index + 2] == ENCODED_NULL_CHAR
-        {
-            count += 1;
-        } else if index + 1 + count < values.len()
-            && slice_contains_nullchar(&values[index..index + count + 2])
+        if (count == 1
+            && values.get(index + 1) == Some(&ENCODED_NULL_CHAR)
+            && values.get(index + 2) == Some(&ENCODED_NULL_CHAR))
+            || (index + 1 + count < values.len()
+                && slice_contains_nullchar(&values[index..index + count + 2]))
         {
             count += 1;
         }
 
-        right = Some(Box::new(node_from_vec(values, index + 1 + count)));
+        right = Some(Box::new(node_from_vec(values, index + 1 + count)?));
     }
 
     let mut node = if c == INTERNAL_NODE_VALUE {
@@ -648,53 +974,51 @@ fn node_from_vec(values: &Vec<char>, index: usize) -> Node {
     node.left = left;
     node.right = right;
 
-    node
+    Ok(node)
 }
 
-impl From<Vec<char>> for HuffmanTree {
-    fn from(value: Vec<char>) -> Self {
+impl TryFrom<Vec<char>> for HuffmanTree {
+    type Error = CompressorError;
+
+    fn try_from(value: Vec<char>) -> Result<Self> {
+        // also bounds the depth of the recursion in node_from_vec
+        if value.len() > MAX_TREE_SIZE {
+            return Err(CompressorError::InvalidTree);
+        }
+
         let mut tree = HuffmanTree {
-            root: node_from_vec(&value, 0),
+            root: node_from_vec(&value, 0)?,
             encoding: HashMap::new(),
         };
 
         tree.set_encoding();
 
-        tree
+        Ok(tree)
     }
 }
 
-impl From<&[u8]> for HuffmanTree {
-    fn from(value: &[u8]) -> Self {
-        let value: Vec<char> = value.iter().map(|&byte| byte as char).collect();
-        let mut tree = HuffmanTree {
-            root: node_from_vec(&value, 0),
-            encoding: HashMap::new(),
-        };
+impl TryFrom<&[u8]> for HuffmanTree {
+    type Error = CompressorError;
 
-        tree.set_encoding();
+    fn try_from(value: &[u8]) -> Result<Self> {
+        let value: Vec<char> = value.iter().map(|&byte| byte as char).collect();
 
-        tree
+        HuffmanTree::try_from(value)
     }
 }
 
-impl From<&Vec<u8>> for HuffmanTree {
-    fn from(value: &Vec<u8>) -> Self {
-        let value: Vec<char> = value.iter().map(|&byte| byte as char).collect();
-        let mut tree = HuffmanTree {
-            root: node_from_vec(&value, 0),
-            encoding: HashMap::new(),
-        };
-
-        tree.set_encoding();
+impl TryFrom<&Vec<u8>> for HuffmanTree {
+    type Error = CompressorError;
 
-        tree
+    fn try_from(value: &Vec<u8>) -> Result<Self> {
+        HuffmanTree::try_from(value.as_slice())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::bitvec;
+    use crate::test_utils::pseudo_random;
 
     use super::*;
 
@@ -711,7 +1035,7 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let tree = HuffmanTree::new(&mut array);
+        let tree = HuffmanTree::new(&mut array).unwrap();
 
         let encoding = tree.get_encoding();
 
@@ -742,7 +1066,7 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let tree = HuffmanTree::new(&mut array);
+        let tree = HuffmanTree::new(&mut array).unwrap();
 
         // Should be:
         // f: 0
@@ -770,7 +1094,7 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let tree = HuffmanTree::new(&mut array);
+        let tree = HuffmanTree::new(&mut array).unwrap();
 
         // let encoding = tree.get_encoding();
 
@@ -800,7 +1124,7 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let tree = HuffmanTree::new(&mut array);
+        let tree = HuffmanTree::new(&mut array).unwrap();
 
         // We wish to encode 'faced'
         #[cfg_attr(any(), rustfmt::skip)]
@@ -814,7 +1138,7 @@ mod tests {
         // Should be: 0b0110_0100 0b1111_01--
         //                8 char +  6 char = 14 char
         let encoded: [u8; 2] = [0b0110_0100, 0b1111_0100];
-        let decoded = tree.decode(&encoded, 14);
+        let decoded = tree.decode(&encoded, 14).unwrap();
 
         let text: Vec<u8> = "faced".bytes().collect();
         assert_eq!(text, decoded);
@@ -831,12 +1155,12 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let original_tree = HuffmanTree::new(&mut array);
+        let original_tree = HuffmanTree::new(&mut array).unwrap();
 
         let filename = "tests/test_saved_huffman_tree";
-        original_tree.save_as_file(filename);
+        original_tree.save_as_file(filename).unwrap();
 
-        let new_tree = HuffmanTree::load_from_file(filename);
+        let new_tree = HuffmanTree::load_from_file(filename).unwrap();
         // let encoding = tree.get_encoding();
 
         // Should be:
@@ -865,7 +1189,7 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let tree = HuffmanTree::new(&mut array);
+        let tree = HuffmanTree::new(&mut array).unwrap();
 
         let len = tree.len();
 
@@ -897,17 +1221,11 @@ mod tests {
             FrequencyChar('f', 45),
         ];
 
-        let tree = HuffmanTree::new(&mut array);
-
-        // DEBUG
-        // tree.print_tree();
+        let tree = HuffmanTree::new(&mut array).unwrap();
 
         let values = tree.convert_to_vec();
 
-        let new_tree: HuffmanTree = HuffmanTree::from(values);
-
-        // DEBUG
-        // new_tree.print_tree();
+        let new_tree: HuffmanTree = HuffmanTree::try_from(values).unwrap();
 
         // Should be:
         // f: 0
@@ -931,10 +1249,10 @@ mod tests {
         let text: Vec<u8> = text.bytes().collect();
         let text: &[u8] = &text;
 
-        let encoded = HuffmanTree::encode_with_metadatas(text);
+        let encoded = HuffmanTree::encode_with_metadatas(text).unwrap();
         println!("huff: {encoded:?}");
 
-        let decoded = HuffmanTree::decode_with_metadatas(&encoded);
+        let decoded = HuffmanTree::decode_with_metadatas(&encoded).unwrap();
         for c in &decoded {
             print!("{}", *c as char);
         }
@@ -947,39 +1265,272 @@ mod tests {
     #[test]
     pub fn tree_with_null_char() {
         let text: Vec<u8> = "ABBBCCCCCDDDDDD\0\0".bytes().collect();
-        let tree = HuffmanTree::load_tree_from_bytes(&text);
-        let (nb_bits, encoded) = tree.encode(&text);
+        let tree = HuffmanTree::load_tree_from_bytes(&text).unwrap();
+        let (nb_bits, encoded) = tree.encode(&text).unwrap();
 
         let formatted_tree = tree.as_bytes();
-        let new_tree = HuffmanTree::from(&formatted_tree);
-        let decoded = new_tree.decode(&encoded, nb_bits);
+        let new_tree = HuffmanTree::try_from(&formatted_tree).unwrap();
+        let decoded = new_tree.decode(&encoded, nb_bits).unwrap();
         assert_eq!(text, decoded);
 
         let text: Vec<u8> = "ABBBCCCCCDDDDDDD\0\0\0\0".bytes().collect();
-        let tree = HuffmanTree::load_tree_from_bytes(&text);
-        let (nb_bits, encoded) = tree.encode(&text);
+        let tree = HuffmanTree::load_tree_from_bytes(&text).unwrap();
+        let (nb_bits, encoded) = tree.encode(&text).unwrap();
 
         let formatted_tree = tree.as_bytes();
-        let new_tree = HuffmanTree::from(&formatted_tree);
-        let decoded = new_tree.decode(&encoded, nb_bits);
+        let new_tree = HuffmanTree::try_from(&formatted_tree).unwrap();
+        let decoded = new_tree.decode(&encoded, nb_bits).unwrap();
         assert_eq!(text, decoded);
 
         let text: Vec<u8> = "ABBBCCCCCDDDDDD\0\0\0\0\0\0".bytes().collect();
-        let tree = HuffmanTree::load_tree_from_bytes(&text);
-        let (nb_bits, encoded) = tree.encode(&text);
+        let tree = HuffmanTree::load_tree_from_bytes(&text).unwrap();
+        let (nb_bits, encoded) = tree.encode(&text).unwrap();
 
         let formatted_tree = tree.as_bytes();
-        let new_tree = HuffmanTree::from(&formatted_tree);
-        let decoded = new_tree.decode(&encoded, nb_bits);
+        let new_tree = HuffmanTree::try_from(&formatted_tree).unwrap();
+        let decoded = new_tree.decode(&encoded, nb_bits).unwrap();
         assert_eq!(text, decoded);
 
         let text: Vec<u8> = "ABBBCCCCCDDDDDD\0\0\0\0\0\0\0\0\0\0".bytes().collect();
-        let tree = HuffmanTree::load_tree_from_bytes(&text);
-        let (nb_bits, encoded) = tree.encode(&text);
+        let tree = HuffmanTree::load_tree_from_bytes(&text).unwrap();
+        let (nb_bits, encoded) = tree.encode(&text).unwrap();
 
         let formatted_tree = tree.as_bytes();
-        let new_tree = HuffmanTree::from(&formatted_tree);
-        let decoded = new_tree.decode(&encoded, nb_bits);
+        let new_tree = HuffmanTree::try_from(&formatted_tree).unwrap();
+        let decoded = new_tree.decode(&encoded, nb_bits).unwrap();
         assert_eq!(text, decoded);
     }
+
+    #[test]
+    fn canonical_code_lengths() {
+        let text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
+        let encoded = HuffmanTree::encode_with_metadatas(&text).unwrap();
+
+        // A: 1, C: 2, B: 3, D: 3 => A: 0, C: 10, B: 110, D: 111
+        #[cfg_attr(any(), rustfmt::skip)]
+        let lengths = [
+            0xFF, /*0 x 65*/ 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0x00,
+            /*A*/ 0x01, /*B*/ 0x03, /*C*/ 0x02, /*D*/ 0x03,
+        ];
+        assert_eq!(lengths, encoded[..14]);
+        // 23 bits: 0 0 0 110 110 10 10 111 0 10 10 0 0
+        let data = &encoded[encoded.len() - 4..];
+        assert_eq!([23, 0b0001_1011, 0b0101_0111, 0b0101_0000], data);
+
+        assert_eq!(text, HuffmanTree::decode_with_metadatas(&encoded).unwrap());
+    }
+
+    #[test]
+    fn canonical_round_trip() {
+        let mut text: Vec<u8> = (0..=255).collect();
+        text.extend("\0\0\0ABBBCCCCCDDDDDD".bytes());
+        text.extend((0..10_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 7));
+
+        let encoded = HuffmanTree::encode_with_metadatas(&text).unwrap();
+        assert_eq!(text, HuffmanTree::decode_with_metadatas(&encoded).unwrap());
+
+        // smaller than the tree
+        let text: Vec<u8> = "the quick brown fox jumps over the lazy dog"
+            .bytes()
+            .collect();
+        let canonical = HuffmanTree::encode_with_metadatas(&text).unwrap();
+        let with_tree = HuffmanTree::encode_with_tree(&text).unwrap();
+        assert!(canonical.len() < with_tree.len());
+
+        for text in [vec![0], vec![b'A'; 100]] {
+            let encoded = HuffmanTree::encode_with_metadatas(&text).unwrap();
+            assert_eq!(text, HuffmanTree::decode_with_metadatas(&encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn canonical_max_length() {
+        // fibonacci counts give codes of up to 19 bits
+        let mut counts = vec![1usize, 1];
+        for i in 2..20 {
+            counts.push(counts[i - 1] + counts[i - 2]);
+        }
+        let text: Vec<u8> = counts
+            .iter()
+            .enumerate()
+            .flat_map(|(byte, &count)| std::iter::repeat_n(byte as u8, count))
+            .collect();
+
+        let longest = |encoded: &[u8]| {
+            *decode_code_lengths(&encoded[1..], 256)
+                .unwrap()
+                .0
+                .iter()
+                .max()
+                .unwrap()
+        };
+
+        let encoded = HuffmanTree::encode_with_metadatas(&text).unwrap();
+        assert_eq!(19, longest(&encoded));
+
+        for max_code_length in [MIN_CODE_LENGTH, 12] {
+            let limited = HuffmanTree::encode_with_max_length(&text, max_code_length).unwrap();
+            assert_eq!(max_code_length, longest(&limited));
+            assert_eq!(text, HuffmanTree::decode_with_metadatas(&limited).unwrap());
+        }
+
+        for max_code_length in [MIN_CODE_LENGTH - 1, MAX_CODE_LENGTH + 1] {
+            assert!(matches!(
+                HuffmanTree::encode_with_max_length(&text, max_code_length),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn tree_of_wide_symbols() {
+        let mut array = vec![
+            FrequencyChar(1000u16, 5),
+            FrequencyChar(2, 9),
+            FrequencyChar(65535, 12),
+            FrequencyChar(300, 13),
+            FrequencyChar(4, 16),
+            FrequencyChar(0, 45),
+        ];
+
+        let tree = HuffmanTree::new(&mut array).unwrap();
+
+        assert_eq!(11, tree.len());
+        assert_eq!(tree.get_encoding()[0], (0, bitvec![0]));
+        assert_eq!(tree.get_encoding()[3], (1000, bitvec![1, 1, 0, 0]));
+
+        let tree = HuffmanTree::from_symbols(&[70_000u32, 70_000, 3]).unwrap();
+        assert_eq!(3, tree.len());
+    }
+
+    #[test]
+    fn huffman_tree_without_symbols() {
+        assert!(matches!(
+            HuffmanTree::<u8>::new(&mut Vec::new()),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            HuffmanTree::<u32>::from_symbols(&[]),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            HuffmanTree::load_tree_from_bytes(&[]),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn canonical_wide_symbols() {
+        let symbols = [1000u16, 1000, 1000, 1001, 1001, 1002, 1002, 3000, 1000];
+        let encoded = HuffmanTree::encode_symbols(&symbols, MAX_CODE_LENGTH).unwrap();
+
+        // runs of 3 symbols from 1000 and 1 symbol 1997 values later
+        let runs = [0xFF, 2, 0x87, 0x68, 2, 0x8F, 0x4D, 0];
+        assert_eq!(runs, encoded[..runs.len()]);
+        assert_eq!(
+            symbols.to_vec(),
+            HuffmanTree::<u16>::decode_symbols(&encoded).unwrap()
+        );
+
+        let codes: Vec<u32> = pseudo_random(42)
+            .take(50_000)
+            .map(|seed| (seed >> 16) % 1000 * (seed >> 28) * 1000)
+            .collect();
+        for symbols in [Vec::new(), vec![u32::MAX], vec![7; 100], codes] {
+            let encoded = HuffmanTree::encode_symbols(&symbols, 15).unwrap();
+            assert_eq!(
+                symbols,
+                HuffmanTree::<u32>::decode_symbols(&encoded).unwrap()
+            );
+        }
+
+        // the bytes keep the code lengths of all of them
+        let text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
+        assert_eq!(
+            HuffmanTree::encode_with_metadatas(&text).unwrap(),
+            HuffmanTree::encode_symbols(&text, MAX_CODE_LENGTH).unwrap()
+        );
+
+        // 512 symbols do not fit in codes of 8 bits
+        let symbols: Vec<u16> = (0..512).collect();
+        assert!(matches!(
+            HuffmanTree::encode_symbols(&symbols, MIN_CODE_LENGTH),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+        let encoded = HuffmanTree::encode_symbols(&symbols, 9).unwrap();
+        assert_eq!(
+            symbols,
+            HuffmanTree::<u16>::decode_symbols(&encoded).unwrap()
+        );
+
+        // a symbol wider than the type decoded
+        let encoded = HuffmanTree::encode_symbols(&[70_000u32], MAX_CODE_LENGTH).unwrap();
+        assert!(matches!(
+            HuffmanTree::<u16>::decode_symbols(&encoded),
+            Err(CompressorError::InvalidData(_))
+        ));
+        for end in 0..encoded.len() {
+            assert!(HuffmanTree::<u32>::decode_symbols(&encoded[..end]).is_err());
+        }
+
+        // a run of about 2^31 symbols, without the code lengths of any of them
+        let huge_run = [0xFF, 1, 0, 0x87, 0xFF, 0xFF, 0xFF, 0x7E];
+        assert!(matches!(
+            HuffmanTree::<u32>::decode_symbols(&huge_run),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn decode_tree_format() {
+        // written before the canonical codes
+        let text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
+        let encoded = [7, 0, 65, 0, 0, 68, 66, 67, 23, 22, 252, 120];
+        assert_eq!(text, HuffmanTree::decode_with_metadatas(&encoded).unwrap());
+
+        let text: Vec<u8> = "ABBBCCCCCDDDDDD\0\0".bytes().collect();
+        let encoded = HuffmanTree::encode_with_tree(&text).unwrap();
+        assert_eq!(text, HuffmanTree::decode_with_metadatas(&encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_n_decode_empty_input() {
+        let encoded = HuffmanTree::encode_with_metadatas(&[]).unwrap();
+        let decoded = HuffmanTree::decode_with_metadatas(&encoded).unwrap();
+
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn decode_malformed_input() {
+        let text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
+        let encoded = HuffmanTree::encode_with_metadatas(&text).unwrap();
+
+        // every truncation must be reported instead of panicking
+        for end in 0..encoded.len() {
+            assert!(HuffmanTree::decode_with_metadatas(&encoded[..end]).is_err());
+        }
+
+        // more code lengths than bytes
+        let invalid_lengths = [
+            0xFF, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE0, 0xE1,
+        ];
+        assert!(matches!(
+            HuffmanTree::decode_with_metadatas(&invalid_lengths[..10]),
+            Err(CompressorError::TruncatedInput)
+        ));
+        let mut invalid_lengths = vec![0xFF];
+        invalid_lengths.extend_from_slice(&[0xE1; 33]);
+        assert!(matches!(
+            HuffmanTree::decode_with_metadatas(&invalid_lengths),
+            Err(CompressorError::InvalidData(_))
+        ));
+
+        // tree made only of internal nodes
+        let invalid_tree = [5, 0, 0, 0, 0, 0, 1, 0];
+        assert!(matches!(
+            HuffmanTree::decode_with_metadatas(&invalid_tree),
+            Err(CompressorError::InvalidTree)
+        ));
+    }
 }
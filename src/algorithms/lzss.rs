@@ -0,0 +1,594 @@
+#[allow(non_snake_case)]
+pub mod LZSS {
+    use crate::{
+        bit_io::MsbBitReader,
+        compressed_buffer::CompressedBuffer,
+        error::{CompressorError, Result},
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://en.wikipedia.org/wiki/Lempel%E2%80%93Ziv%E2%80%93Storer%E2%80%93Szymanski
+    // https://www.rfc-editor.org/rfc/rfc1951#section-4
+
+    /// shortest repeat worth looking for
+    pub const MIN_MATCH: usize = 3;
+
+    /// longest repeat, a longer one is split in several repeats
+    ///
+    /// the decoder rejects the longer ones instead of trusting any length read from its input
+    pub const MAX_MATCH: usize = 1 << 16;
+
+    /// default maximum distance of a repeat
+    pub const DEFAULT_WINDOW: usize = 1 << 20;
+
+    /// default number of previous positions compared at each position
+    pub const DEFAULT_MAX_CHAIN: usize = 64;
+
+    /// number of bits of the hash of the next MIN_MATCH bytes
+    const HASH_BITS: u32 = 18;
+
+    /// end of a hash chain
+    const NONE: usize = usize::MAX;
+
+    /// a repeat of MIN_MATCH bytes further than this costs more than its literals, once entropy coded
+    const TOO_FAR: usize = 4096;
+
+    /// first symbol of the lengths of [`encode_symbols`], the bytes being the symbols before it
+    const LENGTH_SYMBOLS: u32 = 256;
+
+    /// first symbol of the offsets of [`encode_symbols`], after a bucket of lengths for each bit length
+    const OFFSET_SYMBOLS: u32 = LENGTH_SYMBOLS + 2 * usize::BITS;
+
+    /// Either a byte, or a repeat of `length` bytes found `offset` bytes before.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Token {
+        Literal(u8),
+        Match { offset: usize, length: usize },
+    }
+
+    /// Find the repeats of a sliding window with hash chains, the previous positions starting with the
+    /// same bytes are linked together.
+    ///
+    /// ## Example:
+    ///
+    /// ```
+    /// use simple_file_compressor::algorithms::lzss::LZSS::{MatchFinder, Token};
+    ///
+    /// let tokens = MatchFinder::new(1024).tokens(b"abcabcabc", |_, _| true);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         Token::Literal(b'a'),
+    ///         Token::Literal(b'b'),
+    ///         Token::Literal(b'c'),
+    ///         Token::Match { offset: 3, length: 6 },
+    ///     ],
+    ///     tokens
+    /// );
+    /// ```
+    pub struct MatchFinder {
+        window_size: usize,
+        max_match: usize,
+        max_chain: usize,
+    }
+
+    impl MatchFinder {
+        pub fn new(window_size: usize) -> Self {
+            MatchFinder {
+                window_size: window_size.max(1),
+                max_match: usize::MAX,
+                max_chain: DEFAULT_MAX_CHAIN,
+            }
+        }
+
+        /// longest repeat returned (at least MIN_MATCH)
+        pub fn with_max_match(mut self, max_match: usize) -> Self {
+            self.max_match = max_match.max(MIN_MATCH);
+            self
+        }
+
+        /// number of previous positions compared at each position (at least 1)
+        pub fn with_max_chain(mut self, max_chain: usize) -> Self {
+            self.max_chain = max_chain.max(1);
+            self
+        }
+
+        fn hash(bytes: &[u8]) -> usize {
+            let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+            (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+        }
+
+        /// split the input in literals and repeats, a repeat is only kept if `accept(offset, length)`
+        ///
+        /// at each position, the repeat is skipped for a literal if a longer one starts at the next position
+        pub fn tokens(&self, input: &[u8], accept: impl Fn(usize, usize) -> bool) -> Vec<Token> {
+            let mut chains = HashChains {
+                head: vec![NONE; 1 << HASH_BITS],
+                previous: vec![NONE; self.window_size.min(input.len())],
+                inserted: 0,
+            };
+
+            let mut tokens = Vec::new();
+            let mut pos = 0;
+            while pos < input.len() {
+                chains.insert_until(input, pos);
+                let found = self
+                    .longest_match(input, pos, &chains)
+                    .filter(|&(offset, length)| accept(offset, length));
+
+                let Some((offset, length)) = found else {
+                    tokens.push(Token::Literal(input[pos]));
+                    pos += 1;
+                    continue;
+                };
+
+                // lazy matching: a longer repeat starting at the next byte is worth a literal
+                chains.insert_until(input, pos + 1);
+                let next = self
+                    .longest_match(input, pos + 1, &chains)
+                    .filter(|&(offset, length)| accept(offset, length));
+                if next.is_some_and(|(_, next_length)| next_length > length) {
+                    tokens.push(Token::Literal(input[pos]));
+                    pos += 1;
+                    continue;
+                }
+
+                tokens.push(Token::Match { offset, length });
+                pos += length;
+            }
+
+            tokens
+        }
+
+        /// @**returns** Option\<(usize, usize)\> => (offset, length) of the longest repeat starting at `pos`
+        fn longest_match(
+            &self,
+            input: &[u8],
+            pos: usize,
+            chains: &HashChains,
+        ) -> Option<(usize, usize)> {
+            if pos + MIN_MATCH > input.len() {
+                return None;
+            }
+
+            let max_length = self.max_match.min(input.len() - pos);
+            let mut best: Option<(usize, usize)> = None;
+
+            let mut candidate = chains.head[Self::hash(&input[pos..])];
+            for _ in 0..self.max_chain {
+                // the previous positions of the chain are overwritten past the window
+                if candidate >= pos || pos - candidate > self.window_size {
+                    break;
+                }
+
+                // only a repeat going past the best one is worth comparing
+                let best_length = best.map_or(MIN_MATCH - 1, |(_, length)| length);
+                if input[candidate + best_length] == input[pos + best_length] {
+                    let length = input[candidate..]
+                        .iter()
+                        .zip(&input[pos..pos + max_length])
+                        .take_while(|(a, b)| a == b)
+                        .count();
+
+                    if length > best_length {
+                        best = Some((pos - candidate, length));
+                        if length == max_length {
+                            break;
+                        }
+                    }
+                }
+
+                candidate = chains.previous[candidate % chains.previous.len()];
+            }
+
+            best
+        }
+    }
+
+    struct HashChains {
+        /// last position of each hash
+        head: Vec<usize>,
+        /// position before each position of the window with the same hash
+        previous: Vec<usize>,
+        /// positions before this one are in the chains
+        inserted: usize,
+    }
+
+    impl HashChains {
+        fn insert_until(&mut self, input: &[u8], end: usize) {
+            while self.inserted < end && self.inserted + MIN_MATCH <= input.len() {
+                let pos = self.inserted;
+                let hash = MatchFinder::hash(&input[pos..]);
+
+                let slot = pos % self.previous.len();
+                self.previous[slot] = self.head[hash];
+                self.head[hash] = pos;
+                self.inserted += 1;
+            }
+        }
+    }
+
+    /// number of bytes a repeat takes in [`encode`]
+    fn match_size(offset: usize, length: usize) -> usize {
+        encode_varsize(offset).len() + encode_varsize(length - MIN_MATCH).len()
+    }
+
+    /// encode an array of bytes by replacing repeats with (offset, length) pairs
+    ///
+    /// Every 8 tokens are preceded by a byte of flags, 1 for a repeat and 0 for a literal (from the most
+    /// significant bit). The repeats are kept byte aligned so the next algorithms can compress them.
+    ///
+    /// ## Example:
+    ///
+    /// ```text
+    /// abcabcabc => [window] [0b0001_0000] a b c [3] [6 - MIN_MATCH]
+    /// ```
+    ///
+    /// @**returns** Vec\<u8\> => varsize window size followed by the flags and tokens
+    pub fn encode(input: &[u8], window_size: usize, max_chain: usize) -> Result<Vec<u8>> {
+        let finder = MatchFinder::new(window_size)
+            .with_max_match(MAX_MATCH)
+            .with_max_chain(max_chain);
+
+        // a repeat must be smaller than the literals it replaces
+        let tokens = finder.tokens(input, |offset, length| match_size(offset, length) < length);
+
+        let mut encoded = encode_varsize(window_size);
+        for group in tokens.chunks(8) {
+            let flags_index = encoded.len();
+            encoded.push(0);
+
+            for (i, token) in group.iter().enumerate() {
+                match *token {
+                    Token::Literal(c) => encoded.push(c),
+                    Token::Match { offset, length } => {
+                        encoded[flags_index] |= 0x80 >> i;
+                        encoded.extend_from_slice(&encode_varsize(offset));
+                        encoded.extend_from_slice(&encode_varsize(length - MIN_MATCH));
+                    }
+                }
+            }
+        }
+
+        Ok(encoded)
+    }
+
+    /// Bucket of a value, the values below 4 having their own bucket and the others sharing one with
+    /// the values of the same bit length and the same second bit.
+    ///
+    /// ```text
+    /// value:  0 1 2 3 | 4 5 | 6 7 | 8 9 10 11 | 12 13 14 15 | 16 ...
+    /// bucket: 0 1 2 3 | 4   | 5   | 6         | 7           | 8 ...
+    /// ```
+    ///
+    /// @**returns** (u32, u32, usize) => (bucket, number of extra bits, extra bits telling the value from the others of the bucket)
+    fn bucket(value: usize) -> (u32, u32, usize) {
+        if value < 4 {
+            return (value as u32, 0, 0);
+        }
+
+        let extra_bits = value.ilog2() - 1;
+        let bucket = 2 * value.ilog2() + (value >> extra_bits & 1) as u32;
+
+        (bucket, extra_bits, value & ((1 << extra_bits) - 1))
+    }
+
+    /// read the extra bits of the bucket, for the value they give
+    fn read_bucket(bucket: u32, extra_bits: &mut MsbBitReader) -> Result<usize> {
+        if bucket < 4 {
+            return Ok(bucket as usize);
+        }
+
+        // at most 62 extra bits, read in two halves
+        let count = bucket / 2 - 1;
+        let high = extra_bits.read_bits(count.saturating_sub(32))? as usize;
+        let low = extra_bits.read_bits(count.min(32))? as usize;
+
+        Ok(((2 | (bucket & 1) as usize) << count) | high << 32 | low)
+    }
+
+    /// Encode an array of bytes as symbols an entropy coder can code directly: the literals are the
+    /// bytes, a repeat is the bucket of its length followed by the bucket of its offset.
+    ///
+    /// ```text
+    /// [0-255] literal
+    /// [256-383] bucket of length - MIN_MATCH, followed by [384-511] bucket of offset - 1
+    /// ```
+    ///
+    /// @**returns** (Vec\<u8\>, Vec\<u32\>) => (varsize window size followed by the extra bits of the buckets, the symbols)
+    pub fn encode_symbols(
+        input: &[u8],
+        window_size: usize,
+        max_chain: usize,
+    ) -> Result<(Vec<u8>, Vec<u32>)> {
+        let finder = MatchFinder::new(window_size)
+            .with_max_match(MAX_MATCH)
+            .with_max_chain(max_chain);
+        let tokens = finder.tokens(input, |offset, length| {
+            length > MIN_MATCH || offset <= TOO_FAR
+        });
+
+        let mut symbols = Vec::with_capacity(tokens.len());
+        let mut extra_bits = CompressedBuffer::new();
+        for token in tokens {
+            match token {
+                Token::Literal(c) => symbols.push(c as u32),
+                Token::Match { offset, length } => {
+                    for (first, value) in [
+                        (LENGTH_SYMBOLS, length - MIN_MATCH),
+                        (OFFSET_SYMBOLS, offset - 1),
+                    ] {
+                        let (bucket, count, extra) = bucket(value);
+                        symbols.push(first + bucket);
+                        extra_bits.push_bits(extra, count);
+                    }
+                }
+            }
+        }
+
+        let mut side = encode_varsize(window_size);
+        side.append(&mut extra_bits.get_buffer());
+
+        Ok((side, symbols))
+    }
+
+    /// decode the symbols of [`encode_symbols`]
+    pub fn decode_symbols(side: &[u8], symbols: &[u32]) -> Result<Vec<u8>> {
+        let (window_size, window_size_len) = get_first_decoded(side)?;
+        let mut extra_bits = MsbBitReader::new(&side[window_size_len..]);
+
+        let mut decoded = Vec::new();
+        let mut symbols = symbols.iter();
+        while let Some(&symbol) = symbols.next() {
+            if symbol < LENGTH_SYMBOLS {
+                decoded.push(symbol as u8);
+                continue;
+            }
+
+            let offset_symbol = *symbols.next().ok_or(CompressorError::TruncatedInput)?;
+            if symbol >= OFFSET_SYMBOLS
+                || !(OFFSET_SYMBOLS..OFFSET_SYMBOLS + 2 * usize::BITS).contains(&offset_symbol)
+            {
+                return Err(CompressorError::InvalidData(format!(
+                    "lzss symbols {symbol}, {offset_symbol} are not a length and an offset"
+                )));
+            }
+
+            let length = read_bucket(symbol - LENGTH_SYMBOLS, &mut extra_bits)?;
+            let offset = read_bucket(offset_symbol - OFFSET_SYMBOLS, &mut extra_bits)?;
+            copy_repeat(&mut decoded, offset.saturating_add(1), length, window_size)?;
+        }
+
+        Ok(decoded)
+    }
+
+    /// append the `length + MIN_MATCH` bytes found `offset` bytes before the end of the decoded bytes
+    fn copy_repeat(
+        decoded: &mut Vec<u8>,
+        offset: usize,
+        length: usize,
+        window_size: usize,
+    ) -> Result<()> {
+        if offset == 0 || offset > decoded.len() || offset > window_size {
+            return Err(CompressorError::InvalidData(format!(
+                "repeat at offset {offset} with {} bytes decoded",
+                decoded.len()
+            )));
+        }
+        if length > MAX_MATCH - MIN_MATCH {
+            return Err(CompressorError::InvalidData(format!(
+                "repeat of {length} + {MIN_MATCH} bytes, longer than {MAX_MATCH} bytes"
+            )));
+        }
+
+        // the repeat can overlap the bytes it creates
+        let start = decoded.len() - offset;
+        for j in 0..length + MIN_MATCH {
+            decoded.push(decoded[start + j]);
+        }
+
+        Ok(())
+    }
+
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let (window_size, mut i) = get_first_decoded(input)?;
+
+        let mut decoded = Vec::new();
+        while i < input.len() {
+            let flags = input[i];
+            i += 1;
+
+            for bit in 0..8 {
+                if i == input.len() {
+                    break;
+                }
+
+                if flags & (0x80 >> bit) == 0 {
+                    decoded.push(input[i]);
+                    i += 1;
+                    continue;
+                }
+
+                let (offset, last_byte_found) = get_first_decoded(&input[i..])?;
+                i += last_byte_found;
+                let (length, last_byte_found) = get_first_decoded(&input[i..])?;
+                i += last_byte_found;
+
+                copy_repeat(&mut decoded, offset, length, window_size)?;
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::test_utils::{pseudo_random, sample_texts};
+
+        fn texts() -> Vec<Vec<u8>> {
+            let random = pseudo_random(42)
+                .take(50_000)
+                .map(|seed| (seed >> 29) as u8 + b'a')
+                .collect();
+
+            let mut texts = sample_texts(random);
+            texts.push(
+                "abcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabcab"
+                    .bytes()
+                    .collect(),
+            );
+            texts
+        }
+
+        #[test]
+        fn lzss_round_trip() {
+            for text in texts() {
+                for window_size in [1, 4, 100, DEFAULT_WINDOW] {
+                    let encoded = encode(&text, window_size, DEFAULT_MAX_CHAIN).unwrap();
+                    assert_eq!(text, decode(&encoded).unwrap(), "window {window_size}");
+                }
+            }
+        }
+
+        #[test]
+        fn lzss_tokens() {
+            let tokens = MatchFinder::new(1024).tokens(b"aaaaaaaa", |_, _| true);
+            assert_eq!(
+                vec![
+                    Token::Literal(b'a'),
+                    Token::Match {
+                        offset: 1,
+                        length: 7
+                    }
+                ],
+                tokens
+            );
+
+            // the repeat is too far
+            let tokens = MatchFinder::new(2).tokens(b"abcabc", |_, _| true);
+            assert!(
+                tokens
+                    .iter()
+                    .all(|token| matches!(token, Token::Literal(_)))
+            );
+
+            let tokens = MatchFinder::new(1024)
+                .with_max_match(4)
+                .tokens(b"aaaaaaaaa", |_, _| true);
+            assert_eq!(
+                vec![
+                    Token::Literal(b'a'),
+                    Token::Match {
+                        offset: 1,
+                        length: 4
+                    },
+                    Token::Match {
+                        offset: 1,
+                        length: 4
+                    },
+                ],
+                tokens
+            );
+        }
+
+        #[test]
+        fn lzss_long_distance_repeats() {
+            // pseudo random block repeated far apart, LZW cannot find it
+            let block: Vec<u8> = pseudo_random(7)
+                .take(100_000)
+                .map(|seed| (seed >> 24) as u8)
+                .collect();
+            let text = [&block[..], &block[..]].concat();
+
+            // the first block costs a flag bit for each literal, the second one a single repeat
+            let encoded = encode(&text, DEFAULT_WINDOW, DEFAULT_MAX_CHAIN).unwrap();
+            assert!(encoded.len() < block.len() * 9 / 8 + 16);
+            assert_eq!(text, decode(&encoded).unwrap());
+
+            // out of a small window
+            let encoded = encode(&text, 1 << 16, DEFAULT_MAX_CHAIN).unwrap();
+            assert!(encoded.len() > text.len());
+        }
+
+        #[test]
+        fn lzss_symbols() {
+            assert_eq!((3, 0, 0), bucket(3));
+            assert_eq!((5, 1, 1), bucket(7));
+            assert_eq!((7, 2, 0b01), bucket(13));
+            assert_eq!((127, 62, (1 << 62) - 1), bucket(usize::MAX));
+
+            let (side, symbols) = encode_symbols(b"abcabcabc", 1024, DEFAULT_MAX_CHAIN).unwrap();
+            // repeat of 6 bytes 3 bytes before: lengths 3 => bucket 3, offset 2 => bucket 2
+            assert_eq!(vec![97, 98, 99, 256 + 3, OFFSET_SYMBOLS + 2], symbols);
+            assert_eq!(vec![0x88, 0x00], side);
+
+            for text in texts() {
+                for window_size in [1, 100, DEFAULT_WINDOW] {
+                    let (side, symbols) =
+                        encode_symbols(&text, window_size, DEFAULT_MAX_CHAIN).unwrap();
+                    assert!(symbols.iter().all(|&symbol| symbol < OFFSET_SYMBOLS + 128));
+                    assert_eq!(text, decode_symbols(&side, &symbols).unwrap());
+                }
+            }
+
+            let text = vec![0xFF; 100_000];
+            let (side, symbols) = encode_symbols(&text, 1024, DEFAULT_MAX_CHAIN).unwrap();
+            assert_eq!(text, decode_symbols(&side, &symbols).unwrap());
+            assert!(matches!(
+                decode_symbols(&side[..2], &symbols),
+                Err(CompressorError::TruncatedInput)
+            ));
+            assert!(matches!(
+                decode_symbols(&side, &symbols[..2]),
+                Err(CompressorError::TruncatedInput)
+            ));
+            assert!(matches!(
+                decode_symbols(&side, &[0, OFFSET_SYMBOLS, 0]),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+
+        #[test]
+        fn lzss_invalid_input() {
+            let encoded = encode(b"abcabcabc", 1024, DEFAULT_MAX_CHAIN).unwrap();
+
+            assert!(matches!(
+                decode(&encoded[..encoded.len() - 1]),
+                Err(CompressorError::TruncatedInput)
+            ));
+
+            // repeat before the start
+            let mut corrupted = encoded.clone();
+            let last = corrupted.len() - 2;
+            corrupted[last] = 10;
+            assert!(matches!(
+                decode(&corrupted),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            // the long repeats are split, and a longer one is rejected before being copied
+            let text = vec![0; 3 * MAX_MATCH];
+            let encoded = encode(&text, DEFAULT_WINDOW, DEFAULT_MAX_CHAIN).unwrap();
+            assert_eq!(text, decode(&encoded).unwrap());
+            let (side, symbols) = encode_symbols(&text, DEFAULT_WINDOW, DEFAULT_MAX_CHAIN).unwrap();
+            assert_eq!(text, decode_symbols(&side, &symbols).unwrap());
+
+            let too_long = [0x84, 0x00, 0x40, 0x61, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+            assert!(matches!(
+                decode(&too_long),
+                Err(CompressorError::InvalidData(_))
+            ));
+            let (bucket, _, _) = bucket(MAX_MATCH);
+            assert!(matches!(
+                decode_symbols(
+                    &[0x80, 0x01, 0, 0, 0],
+                    &[97, LENGTH_SYMBOLS + bucket, OFFSET_SYMBOLS]
+                ),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
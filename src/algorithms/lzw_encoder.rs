@@ -1,146 +1,311 @@
+#[allow(non_snake_case)]
 pub mod LZWEncoder {
     use std::collections::HashMap;
 
-    use crate::varsize::{decode_varsize, encode_varsize, get_first_decoded};
-
-    fn insert(
-        // &self,
-        codeword: Vec<u8>,
-        codewords: &mut HashMap<Vec<u8>, usize>,
-        encoding: &mut Vec<Vec<u8>>,
-    ) {
-        let index = encoding.len();
-        encoding.push(codeword.clone());
-        codewords.insert(codeword, index);
+    use crate::{
+        compressed_buffer::CompressedBuffer,
+        error::{CompressorError, Result},
+        varsize::{decode_varsize, encode_varsize, get_first_decoded},
+    };
+
+    /// first byte of the bit-packed codes, a number of unique chars never starts with it
+    const PACKED: u8 = 0xFF;
+
+    /// smallest and largest number of codes of a bit-packed dictionary
+    pub const MIN_DICT_SIZE: usize = 1 << 9;
+    pub const MAX_DICT_SIZE: usize = 1 << 24;
+    pub const DEFAULT_DICT_SIZE: usize = 1 << 16;
+
+    /// number of bytes between two checks of the compression ratio, once the dictionary is full
+    const CHECK_GAP: usize = 10_000;
+
+    /// What happens to a bit-packed dictionary once it holds as many codes as it can
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum DictionaryFull {
+        /// start again from the unique chars
+        Reset,
+        /// keep using the codes, without adding new ones
+        Freeze,
+        /// keep using the codes until the compression ratio degrades, then reset
+        AdaptiveReset,
     }
 
-    fn usize_to_bytes(xs: &[usize]) -> Vec<u8> {
-        let mut bytes = Vec::new();
-
-        for x in xs {
-            let mut new_bytes = encode_varsize(*x);
-            bytes.append(&mut new_bytes);
+    impl DictionaryFull {
+        fn from_byte(byte: u8) -> Result<Self> {
+            match byte {
+                0 => Ok(DictionaryFull::Reset),
+                1 => Ok(DictionaryFull::Freeze),
+                2 => Ok(DictionaryFull::AdaptiveReset),
+                _ => Err(CompressorError::InvalidData(format!(
+                    "unknown lzw dictionary policy {byte}"
+                ))),
+            }
         }
+    }
 
-        bytes
+    /// Sizes of a bit-packed dictionary, the same for the encoder and the decoder so that both agree
+    /// on the width of every code.
+    struct PackedDictionary {
+        /// number of codewords it can hold, the last code is kept for resets when they are adaptive
+        capacity: usize,
+        when_full: DictionaryFull,
     }
 
-    /// Encode given bytes using the lzw encoding
-    ///
-    /// @**returns** (Vec\<u8\>, Vec\<u8\>) => (single chars in order, the encoded indexes)
-    pub fn encode(input: &[u8]) -> (Vec<u8>, Vec<u8>) {
-        let mut indexes: Vec<usize> = Vec::new();
-        let mut unique_char: Vec<u8> = Vec::new();
-        let mut codewords: HashMap<Vec<u8>, usize> = HashMap::new();
+    impl PackedDictionary {
+        fn new(max_dict_size: usize, when_full: DictionaryFull) -> Result<Self> {
+            if !(MIN_DICT_SIZE..=MAX_DICT_SIZE).contains(&max_dict_size) {
+                return Err(CompressorError::InvalidArgument(format!(
+                    "lzw dictionary of {max_dict_size} codes is not between {MIN_DICT_SIZE} and {MAX_DICT_SIZE} codes"
+                )));
+            }
 
-        let mut encoding: Vec<Vec<u8>> = Vec::new();
-        // add unique char to dict
-        for &c in input {
-            let codeword = vec![c];
+            let capacity = match when_full {
+                DictionaryFull::AdaptiveReset => max_dict_size - 1,
+                _ => max_dict_size,
+            };
 
-            if let None = codewords.get(&codeword) {
-                unique_char.push(c);
-                insert(codeword, &mut codewords, &mut encoding);
+            Ok(PackedDictionary {
+                capacity,
+                when_full,
+            })
+        }
+
+        /// code telling the decoder to reset its dictionary, only written once it is full
+        fn clear_code(&self) -> Option<usize> {
+            (self.when_full == DictionaryFull::AdaptiveReset).then_some(self.capacity)
+        }
+
+        /// @**returns** u32 => number of bits of the codes, when the dictionary holds `size` codewords
+        fn code_width(&self, size: usize) -> u32 {
+            let largest_code = match self.clear_code() {
+                Some(clear) if size == self.capacity => clear,
+                _ => size.saturating_sub(1),
             };
+
+            usize::BITS - largest_code.leading_zeros()
+        }
+    }
+
+    /// read `count` bits from the bit `position` of the input, the most significant one first
+    fn read_bits(input: &[u8], position: usize, count: u32) -> Result<usize> {
+        if position + count as usize > input.len() * 8 {
+            return Err(CompressorError::TruncatedInput);
         }
 
-        // Encoding the rest while creating new codewords
-        let mut i: usize = 0;
-        while i < input.len() {
-            let mut current = vec![input[i]];
-            let mut next = i + 1;
-            let mut index = 0;
+        let mut value = 0;
+        for i in position..position + count as usize {
+            value = value << 1 | (input[i / 8] >> (7 - i % 8) & 1) as usize;
+        }
 
-            let mut done = false;
+        Ok(value)
+    }
 
-            while let Some(codeword_index) = codewords.get(&current) {
-                index = *codeword_index;
+    /// Codewords of the encoder as a trie: a codeword is found from the code of the codeword
+    /// without its last byte, and that byte.
+    struct Trie {
+        /// code of each single char of the input
+        single_chars: [Option<usize>; 256],
+        /// code of each (prefix code, byte)
+        children: HashMap<(usize, u8), usize>,
+        /// number of codes
+        size: usize,
+        /// number of codes after a reset
+        initial_size: usize,
+    }
 
-                if next == input.len() {
-                    done = true;
-                    break;
-                }
-                current.push(input[next]);
-                next += 1;
+    impl Trie {
+        fn new(single_chars: &[u8]) -> Self {
+            let mut codes = [None; 256];
+            for (code, &c) in single_chars.iter().enumerate() {
+                codes[c as usize] = Some(code);
             }
 
-            // NOTE validation can be better
-            if !done {
-                let new_codeword = current.clone();
-                insert(new_codeword, &mut codewords, &mut encoding);
-                current.pop();
+            Trie {
+                single_chars: codes,
+                children: HashMap::new(),
+                size: single_chars.len(),
+                initial_size: single_chars.len(),
             }
+        }
 
-            indexes.push(index);
+        /// keep only the single chars
+        fn reset(&mut self) {
+            self.children.clear();
+            self.size = self.initial_size;
+        }
 
-            i = next - 1;
-            if done {
-                break;
+        /// add the codeword of `prefix` followed by `byte` as the next code
+        fn insert(&mut self, prefix: usize, byte: u8) {
+            self.children.insert((prefix, byte), self.size);
+            self.size += 1;
+        }
+
+        /// @**returns** (usize, usize) => (code, length) of the longest codeword starting the input
+        fn longest_match(&self, input: &[u8]) -> (usize, usize) {
+            let mut code = self.single_chars[input[0] as usize].expect("every char has a code");
+            let mut length = 1;
+
+            while let Some(&child) = input
+                .get(length)
+                .and_then(|&byte| self.children.get(&(code, byte)))
+            {
+                code = child;
+                length += 1;
             }
+
+            (code, length)
         }
+    }
 
-        // DEBUG
-        // println!("dict: {:?}", self.dict);
-        // println!("encoding: {:?}", self.encoding);
-        // println!("data: {:?}", self.data);
-        // LZWEncoder::usize_to_bytes(&self.data)
-        (unique_char, usize_to_bytes(&indexes))
+    /// Codewords of the decoder: every code is its prefix code followed by a byte.
+    #[derive(Clone, Copy)]
+    struct Entry {
+        prefix: usize,
+        byte: u8,
+        /// first byte of the codeword
+        first: u8,
+        length: usize,
     }
 
-    /// Decode previously encoded data
-    /// -
-    pub fn decode(single_chars: &[u8], input: &[u8]) -> Vec<u8> {
-        let mut codewords: HashMap<Vec<u8>, usize> = HashMap::new();
+    struct Table {
+        entries: Vec<Entry>,
+        /// number of codes after a reset
+        initial_size: usize,
+    }
 
-        let mut encoding: Vec<Vec<u8>> = Vec::new();
-        let input = decode_varsize(input);
+    impl Table {
+        fn new(single_chars: &[u8]) -> Self {
+            let entries = single_chars
+                .iter()
+                .enumerate()
+                .map(|(code, &c)| Entry {
+                    prefix: code,
+                    byte: c,
+                    first: c,
+                    length: 1,
+                })
+                .collect();
+
+            Table {
+                entries,
+                initial_size: single_chars.len(),
+            }
+        }
 
-        // add unique char to dict
-        for &c in single_chars {
-            let codeword = vec![c];
-            insert(codeword, &mut codewords, &mut encoding);
+        fn len(&self) -> usize {
+            self.entries.len()
         }
 
-        let mut decoded_chunks = Vec::new();
+        /// keep only the single chars
+        fn reset(&mut self) {
+            self.entries.truncate(self.initial_size);
+        }
 
-        let mut previous_string = Vec::new();
-        let mut first = true;
-        for index in input {
-            let word = if (index as usize) < encoding.len() {
-                encoding[index as usize].clone()
-            } else if (index as usize) == encoding.len() {
-                let mut new_word = previous_string.clone();
-                new_word.push(previous_string[0]);
-                new_word
-            } else {
-                panic!("Should no be possible");
-            };
+        /// add the codeword of `prefix` followed by `byte` as the next code
+        fn push(&mut self, prefix: usize, byte: u8) {
+            let Entry { first, length, .. } = self.entries[prefix];
+            self.entries.push(Entry {
+                prefix,
+                byte,
+                first,
+                length: length + 1,
+            });
+        }
 
-            // DEBUG
-            // println!("word: {}, index: {}", word.clone(), index);
+        fn first_byte(&self, code: usize) -> u8 {
+            self.entries[code].first
+        }
+
+        /// append the codeword of the code to the output, from its last byte to its first one
+        fn write(&self, code: usize, output: &mut Vec<u8>) {
+            let start = output.len();
+            let mut entry = self.entries[code];
+            output.resize(start + entry.length, 0);
 
-            decoded_chunks.push(word.clone());
+            for byte in output[start..].iter_mut().rev() {
+                *byte = entry.byte;
+                entry = self.entries[entry.prefix];
+            }
+        }
+    }
 
-            if !first {
-                let mut new_encoding = previous_string.clone();
-                new_encoding.push(word[0]);
-                encoding.push(new_encoding);
+    /// @**returns** Vec\<u8\> => the chars of the input, in the order they first appear
+    fn unique_chars(input: &[u8]) -> Vec<u8> {
+        let mut unique_chars: Vec<u8> = Vec::new();
+        let mut seen = [false; 256];
+        for &c in input {
+            if !seen[c as usize] {
+                seen[c as usize] = true;
+                unique_chars.push(c);
             }
+        }
+
+        unique_chars
+    }
+
+    fn usize_to_bytes(xs: &[usize]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        for x in xs {
+            let mut new_bytes = encode_varsize(*x);
+            bytes.append(&mut new_bytes);
+        }
+
+        bytes
+    }
+
+    /// Encode given bytes using the lzw encoding
+    ///
+    /// @**returns** (Vec\<u8\>, Vec\<u8\>) => (single chars in order, the encoded indexes)
+    pub fn encode(input: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut indexes: Vec<usize> = Vec::new();
+        let unique_char = unique_chars(input);
+        let mut codewords = Trie::new(&unique_char);
+
+        // Encoding the rest while creating new codewords
+        let mut i: usize = 0;
+        while i < input.len() {
+            let (index, length) = codewords.longest_match(&input[i..]);
+            indexes.push(index);
 
-            first = false;
-            previous_string = word;
+            i += length;
+            if i < input.len() {
+                codewords.insert(index, input[i]);
+            }
         }
 
-        // NOTE useless? Will let it there in case we need to return the encoding at some point.
-        encoding.pop();
+        Ok((unique_char, usize_to_bytes(&indexes)))
+    }
+
+    /// Decode previously encoded data
+    /// -
+    pub fn decode(single_chars: &[u8], input: &[u8]) -> Result<Vec<u8>> {
+        let mut encoding = Table::new(single_chars);
+        let input = decode_varsize(input)?;
 
         let mut decoded = Vec::new();
 
-        for decoded_chunk in decoded_chunks {
-            decoded.extend_from_slice(&decoded_chunk);
+        let mut previous: Option<usize> = None;
+        for index in input {
+            let first = match previous {
+                _ if index < encoding.len() => encoding.first_byte(index),
+                Some(previous) if index == encoding.len() => encoding.first_byte(previous),
+                _ => {
+                    return Err(CompressorError::InvalidData(format!(
+                        "lzw index {index} is not in the dictionary"
+                    )));
+                }
+            };
+
+            if let Some(previous) = previous {
+                encoding.push(previous, first);
+            }
+
+            encoding.write(index, &mut decoded);
+            previous = Some(index);
         }
 
-        decoded
+        Ok(decoded)
     }
 
     /// return the encoding preceded by the unique chars and the number of unique chars.
@@ -150,8 +315,8 @@ pub mod LZWEncoder {
     /// It would be represented as follow:
     /// [num_unique_chars][chars][encoded data]
     /// [3][A, B, C][0, 0, 1, 4, 2, 2, 6]
-    pub fn encode_with_metadatas(input: &[u8]) -> Vec<u8> {
-        let (unique_chars, encoded) = encode(input);
+    pub fn encode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        let (unique_chars, encoded) = encode(input)?;
         let num_chars = encode_varsize(unique_chars.len());
         let mut new_encoded =
             Vec::with_capacity(num_chars.len() + unique_chars.len() + encoded.len());
@@ -159,16 +324,287 @@ pub mod LZWEncoder {
         new_encoded.extend_from_slice(&unique_chars);
         new_encoded.extend_from_slice(&encoded);
 
-        new_encoded
+        Ok(new_encoded)
+    }
+
+    /// Encode given bytes with codes of exactly as many bits as the dictionary needs, in a
+    /// dictionary of at most `max_dict_size` codes.
+    ///
+    /// ```text
+    /// [FF][max dict size][policy][length][num_unique_chars][chars][codes, most significant bit first]
+    /// ```
+    ///
+    /// With `n` codewords in the dictionary, the codes take ceil(log2(n)) bits. When the dictionary
+    /// is full, it is reset, frozen or, for [`DictionaryFull::AdaptiveReset`], frozen until the
+    /// compression ratio degrades and reset with its last code.
+    pub fn encode_packed(
+        input: &[u8],
+        max_dict_size: usize,
+        when_full: DictionaryFull,
+    ) -> Result<Vec<u8>> {
+        let unique_chars = unique_chars(input);
+        let dictionary = PackedDictionary::new(max_dict_size, when_full)?;
+
+        let mut encoded = vec![PACKED];
+        encoded.append(&mut packed_header(
+            input,
+            max_dict_size,
+            when_full,
+            &unique_chars,
+        ));
+
+        let mut buffer = CompressedBuffer::new();
+        encode_codes(input, &unique_chars, &dictionary, |code, width| {
+            buffer.push_bits(code, width)
+        });
+        encoded.append(&mut buffer.buffer);
+
+        Ok(encoded)
+    }
+
+    /// Encode given bytes with the codes of [`encode_packed`], as symbols an entropy coder can code
+    /// directly instead of their bits.
+    ///
+    /// @**returns** (Vec\<u8\>, Vec\<u32\>) => (the header of [`encode_packed`] without its first byte, the codes)
+    pub fn encode_symbols(
+        input: &[u8],
+        max_dict_size: usize,
+        when_full: DictionaryFull,
+    ) -> Result<(Vec<u8>, Vec<u32>)> {
+        let unique_chars = unique_chars(input);
+        let dictionary = PackedDictionary::new(max_dict_size, when_full)?;
+
+        let header = packed_header(input, max_dict_size, when_full, &unique_chars);
+
+        let mut codes = Vec::new();
+        encode_codes(input, &unique_chars, &dictionary, |code, _| {
+            codes.push(code as u32)
+        });
+
+        Ok((header, codes))
+    }
+
+    /// [max dict size][policy][length][num_unique_chars][chars]
+    fn packed_header(
+        input: &[u8],
+        max_dict_size: usize,
+        when_full: DictionaryFull,
+        unique_chars: &[u8],
+    ) -> Vec<u8> {
+        let mut header = encode_varsize(max_dict_size);
+        header.push(when_full as u8);
+        header.extend_from_slice(&encode_varsize(input.len()));
+        header.extend_from_slice(&encode_varsize(unique_chars.len()));
+        header.extend_from_slice(unique_chars);
+
+        header
+    }
+
+    /// give each code of the input to `push_code`, with the number of bits it takes
+    fn encode_codes(
+        input: &[u8],
+        unique_chars: &[u8],
+        dictionary: &PackedDictionary,
+        mut push_code: impl FnMut(usize, u32),
+    ) {
+        let mut codewords = Trie::new(unique_chars);
+
+        let mut bits_written = 0;
+        let mut push_code = |code: usize, size: usize| {
+            let width = dictionary.code_width(size);
+            push_code(code, width);
+            bits_written += width as usize;
+            bits_written
+        };
+
+        // compression ratio since the last reset
+        let mut reset_at = (0, 0);
+        let mut checkpoint = CHECK_GAP;
+        let mut best_ratio = 0.0;
+
+        let mut i = 0;
+        while i < input.len() {
+            let (index, length) = codewords.longest_match(&input[i..]);
+
+            let size = codewords.size;
+            let bits_written = push_code(index, size);
+            i += length;
+
+            if size < dictionary.capacity {
+                if i < input.len() {
+                    codewords.insert(index, input[i]);
+                }
+                continue;
+            }
+
+            match dictionary.when_full {
+                DictionaryFull::Reset => codewords.reset(),
+                DictionaryFull::Freeze => {}
+                DictionaryFull::AdaptiveReset if i >= checkpoint => {
+                    checkpoint = i + CHECK_GAP;
+
+                    let ratio = (i - reset_at.0) as f64 / (bits_written - reset_at.1) as f64;
+                    if ratio > best_ratio {
+                        best_ratio = ratio;
+                    } else if i < input.len() {
+                        // the dictionary does not fit the data anymore
+                        let clear = dictionary.clear_code().expect("adaptive reset");
+                        let bits_written = push_code(clear, size);
+                        codewords.reset();
+                        reset_at = (i, bits_written);
+                        best_ratio = 0.0;
+                    }
+                }
+                DictionaryFull::AdaptiveReset => {}
+            }
+        }
+    }
+
+    /// Decode the codes of [`encode_packed`]
+    pub fn decode_packed(input: &[u8]) -> Result<Vec<u8>> {
+        if input.first() != Some(&PACKED) {
+            return Err(CompressorError::InvalidData(String::from(
+                "lzw codes are not bit-packed",
+            )));
+        }
+
+        let (dictionary, length, single_chars, header_size) = read_packed_header(&input[1..])?;
+        let codes = &input[1 + header_size..];
+
+        let mut position = 0;
+        decode_codes(&dictionary, length, single_chars, |width| {
+            let index = read_bits(codes, position, width)?;
+            position += width as usize;
+            Ok(index)
+        })
+    }
+
+    /// Decode the codes of [`encode_symbols`], following their header
+    pub fn decode_symbols(header: &[u8], codes: &[u32]) -> Result<Vec<u8>> {
+        let (dictionary, length, single_chars, header_size) = read_packed_header(header)?;
+        if header_size != header.len() {
+            return Err(CompressorError::InvalidData(String::from(
+                "bytes after the header of the lzw codes",
+            )));
+        }
+
+        let mut codes = codes.iter();
+        decode_codes(&dictionary, length, single_chars, |_| {
+            codes
+                .next()
+                .map(|&code| code as usize)
+                .ok_or(CompressorError::TruncatedInput)
+        })
+    }
+
+    /// @**returns** (PackedDictionary, usize, &\[u8\], usize) => (
+    ///   the dictionary,
+    ///   the number of decoded bytes,
+    ///   the single chars,
+    ///   the size of the header
+    /// )
+    fn read_packed_header(input: &[u8]) -> Result<(PackedDictionary, usize, &[u8], usize)> {
+        let (max_dict_size, mut i) = get_first_decoded(input)?;
+        let when_full =
+            DictionaryFull::from_byte(*input.get(i).ok_or(CompressorError::TruncatedInput)?)?;
+        i += 1;
+        let (length, size) = get_first_decoded(&input[i..])?;
+        i += size;
+        let (num_chars, size) = get_first_decoded(&input[i..])?;
+        i += size;
+
+        let single_chars = input
+            .get(i..i + num_chars)
+            .ok_or(CompressorError::TruncatedInput)?;
+
+        let dictionary = PackedDictionary::new(max_dict_size, when_full).map_err(|_| {
+            CompressorError::InvalidData(format!("invalid lzw dictionary size {max_dict_size}"))
+        })?;
+
+        Ok((dictionary, length, single_chars, i + num_chars))
+    }
+
+    /// decode `length` bytes from the codes given by `next_code`, which is told their width
+    fn decode_codes(
+        dictionary: &PackedDictionary,
+        length: usize,
+        single_chars: &[u8],
+        mut next_code: impl FnMut(u32) -> Result<usize>,
+    ) -> Result<Vec<u8>> {
+        let mut encoding = Table::new(single_chars);
+
+        let mut decoded = Vec::new();
+        // None for the first code after a reset
+        let mut previous: Option<usize> = None;
+        while decoded.len() < length {
+            // the codeword of the previous code is only added now, the encoder knew it one code earlier
+            let size = match previous {
+                Some(_) if encoding.len() < dictionary.capacity => encoding.len() + 1,
+                _ => encoding.len(),
+            };
+
+            let index = next_code(dictionary.code_width(size))?;
+
+            if size == dictionary.capacity && Some(index) == dictionary.clear_code() {
+                encoding.reset();
+                previous = None;
+                continue;
+            }
+
+            let first = match previous {
+                _ if index < encoding.len() => encoding.first_byte(index),
+                Some(previous) if index == encoding.len() && size > encoding.len() => {
+                    encoding.first_byte(previous)
+                }
+                _ => {
+                    return Err(CompressorError::InvalidData(format!(
+                        "lzw index {index} is not in the dictionary"
+                    )));
+                }
+            };
+
+            if let Some(previous) = previous
+                && size > encoding.len()
+            {
+                encoding.push(previous, first);
+            }
+
+            encoding.write(index, &mut decoded);
+
+            if dictionary.when_full == DictionaryFull::Reset && size == dictionary.capacity {
+                encoding.reset();
+                previous = None;
+            } else {
+                previous = Some(index);
+            }
+        }
+
+        if decoded.len() > length {
+            return Err(CompressorError::InvalidData(format!(
+                "lzw codes decoded to more than {length} bytes"
+            )));
+        }
+
+        Ok(decoded)
     }
 
     /// from an encoded input with metadatas return decoded bytes
-    pub fn decode_with_metadatas(input: &[u8]) -> Vec<u8> {
-        let (num_chars, new_first_index) = get_first_decoded(&input);
+    ///
+    /// the bit-packed codes of [`encode_packed`] are recognized as well
+    pub fn decode_with_metadatas(input: &[u8]) -> Result<Vec<u8>> {
+        if input.first() == Some(&PACKED) {
+            return decode_packed(input);
+        }
+
+        let (num_chars, new_first_index) = get_first_decoded(input)?;
 
-        let single_chars = &input[new_first_index..num_chars + 1];
+        let single_chars_end = new_first_index
+            .checked_add(num_chars)
+            .filter(|&end| end <= input.len())
+            .ok_or(CompressorError::TruncatedInput)?;
+        let single_chars = &input[new_first_index..single_chars_end];
 
-        let encoded = &input[num_chars + 1..];
+        let encoded = &input[single_chars_end..];
 
         decode(single_chars, encoded)
     }
@@ -176,7 +612,10 @@ pub mod LZWEncoder {
 
 #[cfg(test)]
 mod tests {
-    use crate::{algorithms::huffman_tree::HuffmanTree, utils::display_data_compression_ratio};
+    use crate::{
+        algorithms::huffman_tree::HuffmanTree, error::CompressorError,
+        utils::display_data_compression_ratio,
+    };
 
     use super::*;
 
@@ -185,7 +624,7 @@ mod tests {
         let text = "AABABCCABC";
         let to_encode: Vec<u8> = text.bytes().collect();
 
-        let (_, encoded) = LZWEncoder::encode(&to_encode);
+        let (_, encoded) = LZWEncoder::encode(&to_encode).unwrap();
 
         assert_eq!(vec![0, 0, 1, 4, 2, 2, 6], encoded);
     }
@@ -195,7 +634,7 @@ mod tests {
         let to_decode = [0, 0, 1, 4, 2, 2, 6];
 
         let single_chars = [65u8, 66u8, 67u8];
-        let decoded = LZWEncoder::decode(&single_chars, &to_decode);
+        let decoded = LZWEncoder::decode(&single_chars, &to_decode).unwrap();
 
         let text: Vec<u8> = "AABABCCABC".bytes().collect();
         assert_eq!(text, decoded);
@@ -207,7 +646,7 @@ mod tests {
         // let text = "aaaaabbbbbbbbbccccccccccccdddddddddddddeeeeeeeeeeeeeeeefffffffffffffffffffffffffffffffffffffffffffffsdashjdgasjhdgasjhdbvasjhvdjhasgdajhsgdkhasgdjgvbgwsyfghewirfuywgyubefkhicruygwesyurfhgb uyeg rbwnhs jbgvfzfgujwa jge He";
         let to_encode: Vec<u8> = text.bytes().collect();
 
-        let (single_chars, to_decode) = LZWEncoder::encode(&to_encode);
+        let (single_chars, to_decode) = LZWEncoder::encode(&to_encode).unwrap();
 
         // DEBUG
         // println!("Initial length: {}", text.len());
@@ -215,7 +654,7 @@ mod tests {
         // println!("encoded length: {}", to_decode.len() * 8);
         // println!("encoded: {:?}", to_decode);
 
-        let decoded = LZWEncoder::decode(&single_chars, &to_decode);
+        let decoded = LZWEncoder::decode(&single_chars, &to_decode).unwrap();
 
         assert_eq!(to_encode, decoded);
     }
@@ -227,9 +666,9 @@ mod tests {
         // let mut to_encode2: Vec<char> = (1..=255).map(|c: u8| c as char).collect();
         // to_encode.append(&mut to_encode2);
 
-        let (single_chars, to_decode) = LZWEncoder::encode(&to_encode);
+        let (single_chars, to_decode) = LZWEncoder::encode(&to_encode).unwrap();
 
-        let decoded = LZWEncoder::decode(&single_chars, &to_decode);
+        let decoded = LZWEncoder::decode(&single_chars, &to_decode).unwrap();
 
         // DEBUG
         // println!("decoded.len(): {}", decoded.len());
@@ -247,19 +686,19 @@ mod tests {
 
         // LZW ENCODING
         let to_encode: Vec<u8> = text.bytes().collect();
-        let (_, lzw_encoded) = LZWEncoder::encode(&to_encode);
+        let (_, lzw_encoded) = LZWEncoder::encode(&to_encode).unwrap();
 
         // HUFFMAN_ENCODING
         let to_encode_with_huffman: Vec<u8> = text.bytes().collect();
-        let tree = HuffmanTree::load_tree_from_bytes(&to_encode_with_huffman);
-        let (_, compressed_buffer_huffman) = tree.encode(&to_encode_with_huffman);
+        let tree = HuffmanTree::load_tree_from_bytes(&to_encode_with_huffman).unwrap();
+        let (_, compressed_buffer_huffman) = tree.encode(&to_encode_with_huffman).unwrap();
 
         // LZW + HUFFMAN encoding
         let to_encode: Vec<u8> = text.bytes().collect();
-        let (single_chars_huff_lzw, encoded_with_lzw) = LZWEncoder::encode(&to_encode);
+        let (single_chars_huff_lzw, encoded_with_lzw) = LZWEncoder::encode(&to_encode).unwrap();
 
-        let tree = HuffmanTree::load_tree_from_bytes(&encoded_with_lzw);
-        let (nb_bits, compressed_buffer) = tree.encode(&encoded_with_lzw);
+        let tree = HuffmanTree::load_tree_from_bytes(&encoded_with_lzw).unwrap();
+        let (nb_bits, compressed_buffer) = tree.encode(&encoded_with_lzw).unwrap();
 
         // DEBUG print compression rates
         println!("LZW ONLY:");
@@ -272,11 +711,11 @@ mod tests {
         display_data_compression_ratio(text.len(), compressed_buffer.len());
         // END DEBUG //
 
-        let decoded_huffman = tree.decode(&compressed_buffer, nb_bits);
+        let decoded_huffman = tree.decode(&compressed_buffer, nb_bits).unwrap();
 
         assert_eq!(encoded_with_lzw, decoded_huffman);
 
-        let decoded = LZWEncoder::decode(&single_chars_huff_lzw, &decoded_huffman);
+        let decoded = LZWEncoder::decode(&single_chars_huff_lzw, &decoded_huffman).unwrap();
 
         let text: Vec<u8> = text.bytes().collect();
         assert_eq!(text, decoded);
@@ -287,13 +726,170 @@ mod tests {
         let text = "AABABCCABC";
         let to_encode: Vec<u8> = text.bytes().collect();
 
-        let encoded = LZWEncoder::encode_with_metadatas(&to_encode);
+        let encoded = LZWEncoder::encode_with_metadatas(&to_encode).unwrap();
 
         assert_eq!(vec![3, 65, 66, 67, 0, 0, 1, 4, 2, 2, 6], encoded);
 
-        let decoded = LZWEncoder::decode_with_metadatas(&encoded);
+        let decoded = LZWEncoder::decode_with_metadatas(&encoded).unwrap();
 
         let text: Vec<u8> = text.bytes().collect();
         assert_eq!(text, decoded);
     }
+
+    #[test]
+    fn encode_n_decode_with_metadatas_many_unique_chars() {
+        // the number of unique chars takes more than one byte
+        let to_encode: Vec<u8> = (0..=255).rev().collect();
+
+        let encoded = LZWEncoder::encode_with_metadatas(&to_encode).unwrap();
+        let decoded = LZWEncoder::decode_with_metadatas(&encoded).unwrap();
+
+        assert_eq!(to_encode, decoded);
+    }
+
+    #[test]
+    fn encode_n_decode_long_codewords() {
+        // codewords up to about 1400 chars long, each one byte longer than the previous one
+        let text = vec![b'a'; 1_000_000];
+
+        let encoded = LZWEncoder::encode_with_metadatas(&text).unwrap();
+        assert_eq!(text, LZWEncoder::decode_with_metadatas(&encoded).unwrap());
+
+        let encoded =
+            LZWEncoder::encode_packed(&text, 1 << 9, LZWEncoder::DictionaryFull::Freeze).unwrap();
+        assert_eq!(text, LZWEncoder::decode_with_metadatas(&encoded).unwrap());
+    }
+
+    #[test]
+    fn packed_codes_width() {
+        let text: Vec<u8> = "AABABCCABC".bytes().collect();
+
+        let encoded =
+            LZWEncoder::encode_packed(&text, 1 << 9, LZWEncoder::DictionaryFull::Freeze).unwrap();
+
+        // codes 0, 0, 1, 4, 2, 2, 6 take 2 bits with up to 4 codewords, 3 bits with up to 8, then 4 bits
+        #[cfg_attr(any(), rustfmt::skip)]
+        let header = vec![0xFF, 0x84, 0x00, 1, 10, 3, 65, 66, 67];
+        assert_eq!(header, encoded[..9]);
+        assert_eq!(vec![0b0000_0011, 0b0001_0010, 0b0110_0000], encoded[9..]);
+
+        assert_eq!(text, LZWEncoder::decode_with_metadatas(&encoded).unwrap());
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        use LZWEncoder::DictionaryFull::*;
+
+        let mut text: Vec<u8> = "aaaaabbbbbbbbbccccccccccccdddddddddddddeeeeeeeeeeeeeeeefffff"
+            .repeat(50)
+            .bytes()
+            .collect();
+        // data unlike the first part, which the frozen dictionary does not fit
+        text.extend((0..60_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 16));
+        text.extend_from_slice(&text.clone());
+
+        for when_full in [Reset, Freeze, AdaptiveReset] {
+            for max_dict_size in [
+                LZWEncoder::MIN_DICT_SIZE,
+                1000,
+                LZWEncoder::DEFAULT_DICT_SIZE,
+            ] {
+                let encoded = LZWEncoder::encode_packed(&text, max_dict_size, when_full).unwrap();
+                assert_eq!(text, LZWEncoder::decode_packed(&encoded).unwrap());
+            }
+        }
+
+        for text in [vec![], vec![7], vec![7; 1000], (0..=255).collect()] {
+            let encoded = LZWEncoder::encode_packed(&text, 1 << 9, AdaptiveReset).unwrap();
+            assert_eq!(text, LZWEncoder::decode_packed(&encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn codes_as_symbols() {
+        use LZWEncoder::DictionaryFull::*;
+
+        let text: Vec<u8> = "AABABCCABC".bytes().collect();
+        let (header, codes) = LZWEncoder::encode_symbols(&text, 1 << 9, Freeze).unwrap();
+
+        // the same codes and header as the bit-packed codes
+        assert_eq!(vec![0x84, 0x00, 1, 10, 3, 65, 66, 67], header);
+        assert_eq!(vec![0, 0, 1, 4, 2, 2, 6], codes);
+        assert_eq!(text, LZWEncoder::decode_symbols(&header, &codes).unwrap());
+
+        let mut text: Vec<u8> = "abracadabra".repeat(2000).bytes().collect();
+        text.extend((0..60_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 16));
+        for when_full in [Reset, Freeze, AdaptiveReset] {
+            let (header, codes) = LZWEncoder::encode_symbols(&text, 1000, when_full).unwrap();
+            assert!(codes.iter().all(|&code| code < 1000));
+            assert_eq!(text, LZWEncoder::decode_symbols(&header, &codes).unwrap());
+        }
+
+        let (header, codes) = LZWEncoder::encode_symbols(&text, 1 << 9, Reset).unwrap();
+        assert!(matches!(
+            LZWEncoder::decode_symbols(&header, &codes[..codes.len() - 1]),
+            Err(CompressorError::TruncatedInput)
+        ));
+        assert!(matches!(
+            LZWEncoder::decode_symbols(&header, &[1 << 9]),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn packed_smaller_than_varsize() {
+        let text: Vec<u8> = "RGGRRRGGGB\nthe quick brown fox jumps over the lazy dog\n"
+            .repeat(2000)
+            .bytes()
+            .collect();
+
+        let varsize = LZWEncoder::encode_with_metadatas(&text).unwrap();
+        let packed = LZWEncoder::encode_packed(
+            &text,
+            LZWEncoder::DEFAULT_DICT_SIZE,
+            LZWEncoder::DictionaryFull::AdaptiveReset,
+        )
+        .unwrap();
+        assert!(packed.len() < varsize.len() * 3 / 4);
+    }
+
+    #[test]
+    fn packed_invalid_input() {
+        use LZWEncoder::DictionaryFull::*;
+
+        assert!(matches!(
+            LZWEncoder::encode_packed(b"abc", 256, Reset),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+
+        let text: Vec<u8> = "abracadabra".repeat(20).bytes().collect();
+        let encoded = LZWEncoder::encode_packed(&text, 1 << 9, Reset).unwrap();
+        for end in 0..encoded.len() {
+            assert!(LZWEncoder::decode_packed(&encoded[..end]).is_err());
+        }
+
+        let mut corrupted = encoded.clone();
+        corrupted[3] = 3;
+        assert!(matches!(
+            LZWEncoder::decode_packed(&corrupted),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn decode_invalid_index() {
+        // index 5 is not in the dictionary yet
+        let encoded = [2, 65, 66, 0, 5];
+        assert!(matches!(
+            LZWEncoder::decode_with_metadatas(&encoded),
+            Err(CompressorError::InvalidData(_))
+        ));
+
+        // more unique chars than bytes
+        let encoded = [3, 65, 66];
+        assert!(matches!(
+            LZWEncoder::decode_with_metadatas(&encoded),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
 }
@@ -1,6 +1,18 @@
+pub mod adaptive_huffman;
+pub mod ans_encoder;
 pub mod arithmetic_encoder;
 pub mod burrows_wheeler;
+pub mod bzip2;
+pub mod context_mixing;
+pub mod deflate;
+pub mod dynamic_markov;
+pub mod gzip;
 pub mod huffman_tree;
+pub mod lzss;
 pub mod lzw_encoder;
 pub mod move_to_front;
+pub mod ppm;
 pub mod run_length_encoding;
+pub mod unix_compress;
+pub mod zero_run_length;
+pub mod zlib;
@@ -1,11 +1,51 @@
 #[allow(non_snake_case)]
 pub mod MoveToFront {
+    use crate::error::Result;
+
+    /// Where a symbol goes once coded, the variants keeping the frequent symbols at the front
+    /// when another symbol is only seen once.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Variant {
+        /// to the front
+        MoveToFront,
+        /// MTF-1: to the front from position 1, to position 1 from further
+        Mtf1,
+        /// MTF-2: as MTF-1, but to the front from position 1 only when the previous symbol was not
+        /// at the front
+        Mtf2,
+        /// sticky MTF: halfway to the front, a symbol needs to be seen a few times to take the
+        /// place of the front one
+        Sticky,
+    }
+
+    impl Variant {
+        /// position a symbol moves to from `index`, `previous` being the index of the previous symbol
+        fn destination(self, index: usize, previous: usize) -> usize {
+            match self {
+                Variant::MoveToFront => 0,
+                Variant::Mtf1 => usize::from(index > 1),
+                Variant::Mtf2 => usize::from(index > 1 || (index == 1 && previous == 0)),
+                Variant::Sticky => index / 2,
+            }
+        }
+    }
+
+    pub fn encode(input: &[u8]) -> Result<Vec<u8>> {
+        encode_variant(input, Variant::MoveToFront)
+    }
 
-    pub fn encode(input: &[u8]) -> Vec<u8> {
-        let mut unique_symbols: Vec<u8> = (0..255).collect();
+    pub fn decode(encoded: &[u8]) -> Result<Vec<u8>> {
+        decode_variant(encoded, Variant::MoveToFront)
+    }
+
+    /// replace each byte by its position in the list of the bytes, then move it as the variant does
+    pub fn encode_variant(input: &[u8], variant: Variant) -> Result<Vec<u8>> {
+        let mut unique_symbols: Vec<u8> = (0..=255).collect();
         let mut encoded = Vec::with_capacity(input.len());
 
+        let mut previous = 0;
         for c in input {
+            // every byte is in the symbols
             let index = unique_symbols
                 .iter()
                 .position(|symbol| c == symbol)
@@ -15,40 +55,30 @@ pub mod MoveToFront {
             //       we can safely use u8 as an index
             encoded.push(index as u8);
 
-            if index == 0 {
-                continue;
-            }
-
-            for i in (1..index + 1).rev() {
-                unique_symbols[i] = unique_symbols[i - 1];
-            }
-
-            unique_symbols[0] = *c;
+            let destination = variant.destination(index, previous);
+            unique_symbols[destination..=index].rotate_right(1);
+            previous = index;
         }
 
-        encoded
+        Ok(encoded)
     }
 
-    pub fn decode(encoded: &[u8]) -> Vec<u8> {
-        let mut unique_symbols: Vec<u8> = (0..255).collect();
+    /// decode bytes encoded with [`encode_variant`] and the same variant
+    pub fn decode_variant(encoded: &[u8], variant: Variant) -> Result<Vec<u8>> {
+        let mut unique_symbols: Vec<u8> = (0..=255).collect();
         let mut decoded = Vec::with_capacity(encoded.len());
 
+        let mut previous = 0;
         for &index in encoded {
-            let symbol = unique_symbols[index as usize];
-            decoded.push(symbol);
+            let index = index as usize;
+            decoded.push(unique_symbols[index]);
 
-            if index == 0 {
-                continue;
-            }
-
-            for i in (1..index as usize + 1).rev() {
-                unique_symbols[i] = unique_symbols[i - 1];
-            }
-
-            unique_symbols[0] = symbol;
+            let destination = variant.destination(index, previous);
+            unique_symbols[destination..=index].rotate_right(1);
+            previous = index;
         }
 
-        decoded
+        Ok(decoded)
     }
 }
 
@@ -62,7 +92,7 @@ mod tests {
     #[test]
     fn move_to_front_encode() {
         let text: Vec<u8> = "NNBAAA".bytes().collect();
-        let encoded = MoveToFront::encode(&text);
+        let encoded = MoveToFront::encode(&text).unwrap();
 
         let initial_entropy = (get_entropy(&text) * 100.).round() / 100.;
         let encoded_entropy = (get_entropy(&encoded) * 100.).round() / 100.;
@@ -74,9 +104,49 @@ mod tests {
     #[test]
     fn move_to_front_decode() {
         let encoded = vec![78, 0, 67, 67, 0, 0];
-        let decoded = MoveToFront::decode(&encoded);
+        let decoded = MoveToFront::decode(&encoded).unwrap();
 
         let expected: Vec<u8> = "NNBAAA".bytes().collect();
         assert_eq!(expected, decoded);
     }
+
+    #[test]
+    fn move_to_front_variants() {
+        let text: Vec<u8> = "NNBAAANAAB".bytes().collect();
+
+        for (variant, expected) in [
+            (
+                MoveToFront::Variant::MoveToFront,
+                vec![78, 0, 67, 67, 0, 0, 2, 1, 0, 2],
+            ),
+            (
+                MoveToFront::Variant::Mtf1,
+                vec![78, 1, 67, 67, 1, 0, 1, 1, 0, 2],
+            ),
+            (
+                MoveToFront::Variant::Mtf2,
+                vec![78, 1, 67, 67, 1, 0, 1, 0, 0, 2],
+            ),
+            (
+                MoveToFront::Variant::Sticky,
+                vec![78, 39, 67, 67, 33, 16, 20, 8, 4, 34],
+            ),
+        ] {
+            let encoded = MoveToFront::encode_variant(&text, variant).unwrap();
+            assert_eq!(expected, encoded, "{variant:?}");
+            assert_eq!(
+                text,
+                MoveToFront::decode_variant(&encoded, variant).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn move_to_front_every_byte() {
+        let text: Vec<u8> = (0..=255).rev().collect();
+        let encoded = MoveToFront::encode(&text).unwrap();
+        let decoded = MoveToFront::decode(&encoded).unwrap();
+
+        assert_eq!(text, decoded);
+    }
 }
@@ -0,0 +1,499 @@
+#[allow(non_snake_case)]
+pub mod Ppm {
+    use crate::{
+        error::{CompressorError, Result},
+        range_coder::{RangeDecoder, RangeEncoder},
+        utils::{check_decoded_size, check_input_size},
+        varsize::{encode_varsize, get_first_decoded},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://en.wikipedia.org/wiki/Prediction_by_partial_matching
+    // A. Moffat, Implementing the PPM data compression scheme (1990), exclusions and method C
+    // P. G. Howard, The Design and Analysis of Efficient Lossless Data Compression Systems (1993), method D
+
+    /// longest context a byte can be predicted from
+    pub const MAX_ORDER: usize = 16;
+
+    /// context length used when none is given
+    pub const DEFAULT_ORDER: usize = 5;
+
+    /// memory used by the model when none is given
+    pub const DEFAULT_MEMORY: usize = 64 << 20;
+
+    /// the counts of a context are halved past this total, so the recent bytes weigh more
+    ///
+    /// with the escape count, the total stays far below [`crate::range_coder::MAX_TOTAL`]
+    const MAX_CONTEXT_TOTAL: u32 = 1 << 15;
+
+    /// no child context
+    const NO_CHILD: u32 = 0;
+
+    /// the entries of a context are allocated at least 4 at a time
+    const MIN_ENTRIES: usize = 4;
+
+    /// a byte seen after a context
+    struct Entry {
+        symbol: u8,
+        count: u16,
+        /// context made of this context followed by the byte, [`NO_CHILD`] until it is needed
+        child: u32,
+    }
+
+    /// Bytes seen after a context, with their counts.
+    ///
+    /// A context only sees a few different bytes, so they are kept in a list instead of a
+    /// [`FenwickTree`](crate::fenwick_tree::FenwickTree) of the 256 bytes, which also makes it
+    /// simple to leave out the bytes excluded by the longer contexts.
+    #[derive(Default)]
+    struct Context {
+        entries: Vec<Entry>,
+    }
+
+    /// Bytes excluded from the shorter contexts, as they were already predicted by a longer one
+    /// that escaped.
+    struct Exclusions {
+        excluded: [bool; 256],
+        count: usize,
+    }
+
+    impl Exclusions {
+        fn new() -> Self {
+            Exclusions {
+                excluded: [false; 256],
+                count: 0,
+            }
+        }
+
+        fn clear(&mut self) {
+            self.excluded = [false; 256];
+            self.count = 0;
+        }
+
+        fn contains(&self, symbol: u8) -> bool {
+            self.excluded[symbol as usize]
+        }
+
+        fn exclude(&mut self, context: &Context) {
+            for entry in &context.entries {
+                if !self.excluded[entry.symbol as usize] {
+                    self.excluded[entry.symbol as usize] = true;
+                    self.count += 1;
+                }
+            }
+        }
+    }
+
+    impl Context {
+        /// the entries that are not excluded
+        fn candidates<'a>(
+            &'a self,
+            exclusions: &'a Exclusions,
+        ) -> impl Iterator<Item = &'a Entry> + 'a {
+            self.entries
+                .iter()
+                .filter(|entry| !exclusions.contains(entry.symbol))
+        }
+
+        /// Counts of the bytes that are not excluded, the escape having the count of the number of
+        /// different bytes.
+        ///
+        /// A byte starts with a count of 1 and gets 2 more each time it is seen again, which gives
+        /// the escape half a count per different byte in the usual units (method D).
+        ///
+        /// ```text
+        /// [byte counts...][escape]
+        /// 0               sum     total
+        /// ```
+        ///
+        /// @**returns** (u64, u64) => (sum of the counts of the bytes, total with the escape)
+        fn totals(&self, exclusions: &Exclusions) -> (u64, u64) {
+            let (sum, distinct) = self
+                .candidates(exclusions)
+                .fold((0, 0), |(sum, distinct), entry| {
+                    (sum + entry.count as u64, distinct + 1)
+                });
+
+            (sum, sum + distinct)
+        }
+    }
+
+    /// Contexts of every order up to the maximum one, in a tree where the child of a context for a
+    /// byte is the context one byte longer ending with that byte.
+    struct Model {
+        contexts: Vec<Context>,
+        max_order: usize,
+        memory: usize,
+        /// memory used by the contexts and their entries
+        used: usize,
+        /// context of each order for the bytes just coded, None while fewer bytes were coded
+        current: Vec<Option<u32>>,
+    }
+
+    impl Model {
+        fn new(max_order: usize, memory: usize) -> Self {
+            let mut model = Model {
+                contexts: Vec::new(),
+                max_order,
+                memory,
+                used: 0,
+                current: Vec::new(),
+            };
+            model.reset();
+
+            model
+        }
+
+        /// only keep the empty context of order 0
+        fn reset(&mut self) {
+            self.contexts.clear();
+            self.contexts.push(Context::default());
+            self.used = Self::context_size();
+
+            self.current.clear();
+            self.current.resize(self.max_order + 1, None);
+            self.current[0] = Some(0);
+        }
+
+        /// memory taken by a new context
+        fn context_size() -> usize {
+            size_of::<Context>() + MIN_ENTRIES * size_of::<Entry>()
+        }
+
+        /// reset the model if it grew past its memory
+        fn start_symbol(&mut self) {
+            if self.used > self.memory {
+                self.reset();
+            }
+        }
+
+        /// contexts to code the next byte with, from the longest one
+        fn orders(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            self.current
+                .iter()
+                .enumerate()
+                .rev()
+                .filter_map(|(order, context)| context.map(|context| (order, context as usize)))
+        }
+
+        /// Count the byte in the contexts that could not predict it and in the one that did (update
+        /// exclusion), then move to the contexts ending with it.
+        ///
+        /// `coded_order` is the order of the context that predicted the byte, None if it was never
+        /// seen.
+        fn update(&mut self, symbol: u8, coded_order: Option<usize>) {
+            let mut next = vec![None; self.max_order + 1];
+            next[0] = Some(0);
+
+            for order in 0..=self.max_order {
+                let Some(context) = self.current[order] else {
+                    continue;
+                };
+                let context = context as usize;
+                let counted = coded_order.is_none_or(|coded_order| order >= coded_order);
+
+                let entries = &mut self.contexts[context].entries;
+                let index = match entries.iter().position(|entry| entry.symbol == symbol) {
+                    Some(index) => {
+                        if counted {
+                            entries[index].count += 2;
+                        }
+                        index
+                    }
+                    None => {
+                        entries.push(Entry {
+                            symbol,
+                            count: 1,
+                            child: NO_CHILD,
+                        });
+                        if entries.len() > MIN_ENTRIES {
+                            self.used += size_of::<Entry>();
+                        }
+                        entries.len() - 1
+                    }
+                };
+
+                let total: u32 = entries.iter().map(|entry| entry.count as u32).sum();
+                if total > MAX_CONTEXT_TOTAL {
+                    for entry in entries.iter_mut() {
+                        entry.count = entry.count.div_ceil(2);
+                    }
+                }
+
+                if order < self.max_order {
+                    let mut child = entries[index].child;
+                    if child == NO_CHILD {
+                        child = self.contexts.len() as u32;
+                        self.contexts[context].entries[index].child = child;
+                        self.contexts.push(Context::default());
+                        self.used += Self::context_size();
+                    }
+                    next[order + 1] = Some(child);
+                }
+            }
+
+            self.current = next;
+        }
+    }
+
+    /// encode an array of bytes with prediction by partial matching driving an arithmetic coder
+    ///
+    /// Each byte is coded in the longest context that has seen it, the contexts in between coding
+    /// an escape. The bytes never seen are coded with the same probability (order -1).
+    ///
+    /// @**returns** Vec\<u8\> => varsize maximum order, varsize memory, varsize number of bytes and the encoded bits
+    pub fn encode(input: &[u8], max_order: usize, memory: usize) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        if max_order > MAX_ORDER {
+            return Err(CompressorError::InvalidArgument(format!(
+                "order {max_order} is larger than {MAX_ORDER}"
+            )));
+        }
+        if memory == 0 {
+            return Err(CompressorError::InvalidArgument(String::from(
+                "the model needs some memory",
+            )));
+        }
+
+        let mut model = Model::new(max_order, memory);
+        let mut exclusions = Exclusions::new();
+        let mut encoder = RangeEncoder::new();
+
+        for &symbol in input {
+            model.start_symbol();
+            exclusions.clear();
+
+            let mut coded_order = None;
+            for (order, context) in model.orders() {
+                let context = &model.contexts[context];
+                let (sum, total) = context.totals(&exclusions);
+                if total == 0 {
+                    continue;
+                }
+
+                let mut low = 0;
+                let found = context.candidates(&exclusions).find(|entry| {
+                    if entry.symbol == symbol {
+                        return true;
+                    }
+                    low += entry.count as u64;
+                    false
+                });
+
+                match found {
+                    Some(entry) => {
+                        encoder.encode(low, low + entry.count as u64, total);
+                        coded_order = Some(order);
+                        break;
+                    }
+                    None => {
+                        encoder.encode(sum, total, total);
+                        exclusions.exclude(context);
+                    }
+                }
+            }
+
+            if coded_order.is_none() {
+                let low = (0..symbol).filter(|&c| !exclusions.contains(c)).count() as u64;
+                encoder.encode(low, low + 1, (256 - exclusions.count) as u64);
+            }
+
+            model.update(symbol, coded_order);
+        }
+
+        let mut encoded = encode_varsize(max_order);
+        encoded.extend_from_slice(&encode_varsize(memory));
+        encoded.extend_from_slice(&encode_varsize(input.len()));
+        encoded.extend_from_slice(&encoder.finish());
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode`], with the order and memory they were encoded with
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let (max_order, mut i) = get_first_decoded(input)?;
+        let (memory, last_byte_found) = get_first_decoded(&input[i..])?;
+        i += last_byte_found;
+        let (size, last_byte_found) = get_first_decoded(&input[i..])?;
+        i += last_byte_found;
+
+        if max_order > MAX_ORDER {
+            return Err(CompressorError::InvalidData(format!(
+                "order {max_order} is larger than {MAX_ORDER}"
+            )));
+        }
+        if memory == 0 {
+            return Err(CompressorError::InvalidData(String::from(
+                "the model has no memory",
+            )));
+        }
+        // the skewed contexts code many bytes per bit, so the size is not bounded by the input
+        check_decoded_size(size)?;
+
+        let mut model = Model::new(max_order, memory);
+        let mut exclusions = Exclusions::new();
+        let mut decoder = RangeDecoder::new(&input[i..]);
+
+        let mut decoded = Vec::new();
+        while decoded.len() < size {
+            model.start_symbol();
+            exclusions.clear();
+
+            let mut found = None;
+            for (order, context) in model.orders() {
+                let context = &model.contexts[context];
+                let (sum, total) = context.totals(&exclusions);
+                if total == 0 {
+                    continue;
+                }
+
+                let target = decoder.target(total);
+                if target >= sum {
+                    decoder.consume(sum, total, total)?;
+                    exclusions.exclude(context);
+                    continue;
+                }
+
+                let mut low = 0;
+                for entry in context.candidates(&exclusions) {
+                    let high = low + entry.count as u64;
+                    if target < high {
+                        decoder.consume(low, high, total)?;
+                        found = Some((entry.symbol, order));
+                        break;
+                    }
+                    low = high;
+                }
+                break;
+            }
+
+            let (symbol, coded_order) = match found {
+                Some((symbol, order)) => (symbol, Some(order)),
+                None => {
+                    // only corrupted data can escape every byte
+                    if exclusions.count == 256 {
+                        return Err(CompressorError::InvalidData(String::from(
+                            "every byte was excluded before order -1",
+                        )));
+                    }
+
+                    let total = (256 - exclusions.count) as u64;
+                    let target = decoder.target(total);
+                    let symbol = (0..=255)
+                        .filter(|&c| !exclusions.contains(c))
+                        .nth(target as usize)
+                        .ok_or_else(|| {
+                            CompressorError::InvalidData(String::from(
+                                "encoded value does not match any symbol",
+                            ))
+                        })?;
+                    decoder.consume(target, target + 1, total)?;
+                    (symbol, None)
+                }
+            };
+
+            model.update(symbol, coded_order);
+            decoded.push(symbol);
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::algorithms::arithmetic_encoder::ArithmeticEncoder;
+        use crate::test_utils::{assert_truncated, random_bytes, sample_texts};
+        use crate::utils::MAX_BLOCK_SIZE;
+
+        #[test]
+        fn ppm_round_trip() {
+            for text in sample_texts(random_bytes(10_000)) {
+                for max_order in [0, 2, MAX_ORDER] {
+                    let encoded = encode(&text, max_order, DEFAULT_MEMORY).unwrap();
+                    assert_eq!(text, decode(&encoded).unwrap());
+                }
+            }
+        }
+
+        #[test]
+        fn ppm_context_orders() {
+            let text: Vec<u8> =
+                "the quick brown fox jumps over the lazy dog, then the dog sleeps. "
+                    .repeat(200)
+                    .bytes()
+                    .collect();
+
+            let order_0 = encode(&text, 0, DEFAULT_MEMORY).unwrap();
+            let default_order = encode(&text, DEFAULT_ORDER, DEFAULT_MEMORY).unwrap();
+            assert!(
+                default_order.len() < order_0.len() / 10,
+                "{}",
+                default_order.len()
+            );
+
+            // order 0 is about as good as the adaptive arithmetic coding of bytes
+            let adaptive = ArithmeticEncoder::encode_adaptive(&text).unwrap();
+            assert!(order_0.len() < adaptive.len() * 11 / 10);
+        }
+
+        #[test]
+        fn ppm_model_reset() {
+            let text: Vec<u8> = "AAAAACCCCadkjahsdkjashdkjashdjkashdkjashdCCCBBB"
+                .repeat(500)
+                .bytes()
+                .collect();
+
+            // the smallest model is reset before every byte
+            let small = encode(&text, DEFAULT_ORDER, 1).unwrap();
+            assert_eq!(text, decode(&small).unwrap());
+
+            let large = encode(&text, DEFAULT_ORDER, DEFAULT_MEMORY).unwrap();
+            assert_eq!(text, decode(&large).unwrap());
+            assert!(large.len() < small.len() / 10);
+        }
+
+        #[test]
+        fn ppm_invalid_input() {
+            let text: Vec<u8> = "RGGRRRGGGB\n".repeat(10).bytes().collect();
+            let encoded = encode(&text, DEFAULT_ORDER, DEFAULT_MEMORY).unwrap();
+
+            assert_truncated(decode, &encoded);
+            assert!(matches!(
+                decode(&[MAX_ORDER as u8 + 1, 1, 0]),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            // every byte is escaped in order 0 until all of them are excluded
+            let mut escapes = encode_varsize(0);
+            escapes.extend_from_slice(&encode_varsize(DEFAULT_MEMORY));
+            escapes.extend_from_slice(&encode_varsize(1000));
+            escapes.extend_from_slice(&[0xFF; 300]);
+            assert!(matches!(
+                decode(&escapes),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            for (memory, size) in [(0, 1), (1, MAX_BLOCK_SIZE + 1), (1, usize::MAX)] {
+                let mut header = encode_varsize(DEFAULT_ORDER);
+                header.extend_from_slice(&encode_varsize(memory));
+                header.extend_from_slice(&encode_varsize(size));
+                header.push(0);
+                assert!(matches!(
+                    decode(&header),
+                    Err(CompressorError::InvalidData(_))
+                ));
+            }
+
+            assert!(matches!(
+                encode(&text, MAX_ORDER + 1, DEFAULT_MEMORY),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+            assert!(matches!(
+                encode(&text, DEFAULT_ORDER, 0),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+    }
+}
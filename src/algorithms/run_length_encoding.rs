@@ -1,5 +1,10 @@
+#[allow(non_snake_case)]
 pub mod RLE {
-    use crate::varsize::{encode_varsize, get_first_decoded};
+    use crate::{
+        error::Result,
+        utils::{check_decoded_size, check_input_size},
+        varsize::{encode_varsize, get_first_decoded},
+    };
 
     /// Adjust the number of bytes needed to represent the count of each run length encoded values
     ///
@@ -18,8 +23,12 @@ pub mod RLE {
         encoded_smaller
     }
 
-    pub fn encode(input: &[u8]) -> Vec<u8> {
-        assert!(input.len() > 0);
+    /// encode an array of bytes as (byte, varsize count) pairs, the input being at most a block
+    pub fn encode(input: &[u8]) -> Result<Vec<u8>> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        check_input_size(input)?;
 
         let mut encoded = vec![(input[0], 1usize)];
 
@@ -36,39 +45,67 @@ pub mod RLE {
             }
         }
 
-        reduce_sizes(encoded)
+        Ok(reduce_sizes(encoded))
     }
 
-    pub fn decode(input: &[u8]) -> Vec<u8> {
-        let mut decoded = Vec::new();
+    /// decode bytes encoded with [`encode`], the runs adding up to more than
+    /// [`MAX_BLOCK_SIZE`](crate::utils::MAX_BLOCK_SIZE) bytes are rejected before anything is
+    /// written
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut runs = Vec::new();
+        let mut size: usize = 0;
 
         let mut i = 0usize;
         while i < input.len() {
             let c = input[i];
-            let (num, ending_index) = get_first_decoded(&input[i + 1..]);
+            let (num, ending_index) = get_first_decoded(&input[i + 1..])?;
 
-            // push c char num times in decoded
-            for _ in 0..num {
-                decoded.push(c);
-            }
+            size = check_decoded_size(size.saturating_add(num))?;
+            runs.push((c, num));
 
             i += ending_index + 1;
         }
 
-        decoded
+        let mut decoded = Vec::with_capacity(size);
+        for (c, num) in runs {
+            // push c char num times in decoded
+            decoded.resize(decoded.len() + num, c);
+        }
+
+        Ok(decoded)
     }
 
     #[cfg(test)]
     mod tests {
 
         use super::*;
+        use crate::{error::CompressorError, utils::MAX_BLOCK_SIZE};
 
         #[test]
         fn rle_simple_test() {
             let text: Vec<u8> = "ABBCCCDDDDFFFFF\n".bytes().collect();
-            let encoded = encode(&text);
-            let decoded = decode(&encoded);
+            let encoded = encode(&text).unwrap();
+            let decoded = decode(&encoded).unwrap();
             assert_eq!(text, decoded);
         }
+
+        #[test]
+        fn rle_empty_and_truncated() {
+            assert!(encode(&[]).unwrap().is_empty());
+            assert!(decode(&[]).unwrap().is_empty());
+
+            // count of the run is missing
+            assert!(decode(&[65, 2, 66]).is_err());
+
+            // runs adding up to more than any block
+            for (num, runs) in [(MAX_BLOCK_SIZE + 1, 1), (usize::MAX, 1), (1 << 29, 3)] {
+                let mut run = vec![65];
+                run.extend_from_slice(&encode_varsize(num));
+                assert!(matches!(
+                    decode(&run.repeat(runs)),
+                    Err(CompressorError::InvalidData(_))
+                ));
+            }
+        }
     }
 }
@@ -0,0 +1,542 @@
+#[allow(non_snake_case)]
+pub mod UnixCompress {
+    use std::{
+        collections::HashMap,
+        io::{self, Read, Write},
+    };
+
+    use crate::{
+        bit_io::{LsbBitWriter, LsbStreamReader},
+        error::{CompressorError, Result},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://en.wikipedia.org/wiki/Compress_(software)
+    // https://github.com/vapier/ncompress/blob/main/compress.c
+    // https://git.savannah.gnu.org/cgit/gzip.git/tree/unlzw.c
+
+    /// first bytes of a `.Z` file
+    pub const MAGIC: [u8; 2] = [0x1F, 0x9D];
+
+    /// largest width of the codes when none is given
+    pub const DEFAULT_MAX_BITS: u8 = 16;
+
+    /// width of the codes after a reset of the dictionary
+    const INIT_BITS: u32 = 9;
+
+    /// the dictionary can be reset by a CLEAR code
+    const BLOCK_MODE: u8 = 0x80;
+    const BITS_MASK: u8 = 0x1F;
+
+    /// code resetting the dictionary
+    const CLEAR: u32 = 256;
+    /// first code of a string of more than one byte
+    const FIRST: u32 = 257;
+
+    /// number of bytes between two checks of the compression ratio, once the dictionary is full
+    const CHECK_GAP: usize = 10_000;
+
+    /// codes kept by a [`UnixCompressWriter`] before writing them, in bytes
+    const OUTPUT_SIZE: usize = 1 << 16;
+
+    /// return the largest width of the codes if it is from 9 to 16 bits
+    pub fn check_max_bits(max_bits: u8) -> Result<u8> {
+        if !(INIT_BITS as u8..=16).contains(&max_bits) {
+            return Err(CompressorError::InvalidArgument(format!(
+                "{max_bits} bits codes are not between 9 and 16 bits"
+            )));
+        }
+
+        Ok(max_bits)
+    }
+
+    /// Width of the codes as `compress` reads them: they get 1 bit wider when the dictionary gets
+    /// bigger than the largest code of the width.
+    ///
+    /// The largest code starts at 511, even for 9 bits codes, and only the widths reached by growing
+    /// are limited to `max_bits`.
+    struct CodeWidth {
+        bits: u32,
+        largest_code: u32,
+    }
+
+    impl CodeWidth {
+        fn new() -> Self {
+            CodeWidth {
+                bits: INIT_BITS,
+                largest_code: (1 << INIT_BITS) - 1,
+            }
+        }
+
+        /// @**returns** bool => true if the width changed for a dictionary going up to `next_code`
+        fn update(&mut self, next_code: u32, max_bits: u32) -> bool {
+            if next_code <= self.largest_code {
+                return false;
+            }
+
+            self.bits += 1;
+            self.largest_code = if self.bits == max_bits {
+                1 << max_bits
+            } else {
+                (1 << self.bits) - 1
+            };
+
+            true
+        }
+    }
+
+    /// Codes written in groups of 8: when their width changes, the group is filled with zeros, as
+    /// `compress` reads a whole group of codes at a time.
+    struct CodeWriter {
+        writer: LsbBitWriter,
+        /// bytes taken from the writer
+        bytes_taken: usize,
+        width: CodeWidth,
+        max_bits: u32,
+        /// codes written since the width changed
+        codes_in_group: usize,
+        /// size of the dictionary of the decoder, which adds the string of a code when reading the next one
+        decoder_next_code: u32,
+        decoder_adds: bool,
+    }
+
+    impl CodeWriter {
+        fn write_code(&mut self, code: u32) {
+            if self.width.update(self.decoder_next_code, self.max_bits) {
+                self.fill_group(self.width.bits - 1);
+            }
+
+            self.writer.write_bits(code, self.width.bits);
+            self.codes_in_group = (self.codes_in_group + 1) % 8;
+        }
+
+        fn write(&mut self, code: u32) {
+            self.write_code(code);
+
+            if self.decoder_adds {
+                self.decoder_next_code = (self.decoder_next_code + 1).min(1 << self.max_bits);
+            }
+            self.decoder_adds = true;
+        }
+
+        /// reset the dictionary of the decoder, its next code adds a string that is never used
+        fn clear(&mut self) {
+            self.write_code(CLEAR);
+            self.fill_group(self.width.bits);
+
+            self.width = CodeWidth::new();
+            self.decoder_next_code = CLEAR;
+        }
+
+        /// number of bits written, including the ones taken from the writer
+        fn bit_len(&self) -> usize {
+            self.bytes_taken * 8 + self.writer.bit_len()
+        }
+
+        fn take_bytes(&mut self) -> Vec<u8> {
+            let bytes = self.writer.take_bytes();
+            self.bytes_taken += bytes.len();
+            bytes
+        }
+
+        fn fill_group(&mut self, bits: u32) {
+            let padding = (8 - self.codes_in_group) % 8;
+            for _ in 0..padding {
+                self.writer.write_bits(0, bits);
+            }
+
+            self.codes_in_group = 0;
+        }
+    }
+
+    /// encode an array of bytes as a `.Z` file of `compress`, with codes from 9 to `max_bits` bits
+    ///
+    /// The codes are packed from the least significant bit and get 1 bit wider each time the
+    /// dictionary gets bigger than they can tell. Once the dictionary is full, it is reset with a
+    /// CLEAR code when the compression ratio goes down.
+    ///
+    /// ```text
+    /// [1F 9D] [BLOCK_MODE | max_bits] [codes]
+    /// ```
+    pub fn encode(input: &[u8], max_bits: u8) -> Result<Vec<u8>> {
+        let mut writer = UnixCompressWriter::new(Vec::new(), max_bits)?;
+        writer.write_all(input)?;
+        writer.finish()
+    }
+
+    /// Compress everything written to it as a `.Z` file, as [`encode`] does.
+    ///
+    /// The codes are written to the inner writer as they come, so the memory used only depends on
+    /// the size of the dictionary. [`UnixCompressWriter::finish`] must be called to write the last
+    /// code, dropping the writer also does it but ignores the errors.
+    pub struct UnixCompressWriter<W: Write> {
+        /// None once finished
+        inner: Option<W>,
+        codes: CodeWriter,
+        /// (code of the prefix, next byte) => code of the string
+        dictionary: HashMap<u32, u32>,
+        next_code: u32,
+        max_code: u32,
+        /// code of the string read so far, None before the first byte
+        current: Option<u32>,
+        /// bytes read after the first one
+        bytes_read: usize,
+        checkpoint: usize,
+        best_ratio: f64,
+    }
+
+    impl<W: Write> UnixCompressWriter<W> {
+        /// write the header of the file, the codes going up to `max_bits` bits (from 9 to 16)
+        pub fn new(inner: W, max_bits: u8) -> Result<Self> {
+            let max_bits = check_max_bits(max_bits)?;
+
+            let mut writer = LsbBitWriter::new();
+            writer.write_bytes(&MAGIC);
+            writer.write_bytes(&[BLOCK_MODE | max_bits]);
+
+            let max_bits = max_bits as u32;
+            Ok(UnixCompressWriter {
+                inner: Some(inner),
+                codes: CodeWriter {
+                    writer,
+                    bytes_taken: 0,
+                    width: CodeWidth::new(),
+                    max_bits,
+                    codes_in_group: 0,
+                    decoder_next_code: FIRST,
+                    decoder_adds: false,
+                },
+                dictionary: HashMap::new(),
+                next_code: FIRST,
+                max_code: 1 << max_bits,
+                current: None,
+                bytes_read: 0,
+                checkpoint: CHECK_GAP,
+                best_ratio: 0.0,
+            })
+        }
+
+        /// write the code of the last string and return the inner writer
+        pub fn finish(mut self) -> Result<W> {
+            self.write_end()?;
+
+            Ok(self.inner.take().expect("the writer is only finished once"))
+        }
+
+        fn inner(&mut self) -> &mut W {
+            self.inner
+                .as_mut()
+                .expect("the writer is not used after being finished")
+        }
+
+        fn push(&mut self, c: u8) {
+            let Some(current) = self.current else {
+                self.current = Some(c as u32);
+                return;
+            };
+
+            self.bytes_read += 1;
+
+            let key = current << 8 | c as u32;
+            if let Some(&code) = self.dictionary.get(&key) {
+                self.current = Some(code);
+                return;
+            }
+
+            self.codes.write(current);
+
+            if self.next_code < self.max_code {
+                self.dictionary.insert(key, self.next_code);
+                self.next_code += 1;
+            } else if self.bytes_read >= self.checkpoint {
+                self.checkpoint = self.bytes_read + CHECK_GAP;
+
+                let ratio = self.bytes_read as f64 / self.codes.bit_len() as f64;
+                if ratio > self.best_ratio {
+                    self.best_ratio = ratio;
+                } else {
+                    // the dictionary does not fit the data anymore
+                    self.best_ratio = 0.0;
+                    self.dictionary.clear();
+                    self.next_code = FIRST;
+                    self.codes.clear();
+                }
+            }
+
+            self.current = Some(c as u32);
+        }
+
+        fn write_end(&mut self) -> Result<()> {
+            if let Some(current) = self.current.take() {
+                self.codes.write(current);
+            }
+            self.codes.writer.align_to_byte();
+
+            let bytes = self.codes.take_bytes();
+            self.inner().write_all(&bytes)?;
+            self.inner().flush()?;
+
+            Ok(())
+        }
+    }
+
+    impl<W: Write> Write for UnixCompressWriter<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for &c in buf {
+                self.push(c);
+            }
+
+            if self.codes.writer.bit_len() >= OUTPUT_SIZE * 8 {
+                let bytes = self.codes.take_bytes();
+                self.inner().write_all(&bytes)?;
+            }
+
+            Ok(buf.len())
+        }
+
+        /// write the whole bytes of codes, the codes of the string being read are only known later
+        fn flush(&mut self) -> io::Result<()> {
+            let bytes = self.codes.take_bytes();
+            self.inner().write_all(&bytes)?;
+            self.inner().flush()
+        }
+    }
+
+    impl<W: Write> Drop for UnixCompressWriter<W> {
+        fn drop(&mut self) {
+            if self.inner.is_some() {
+                let _ = self.write_end();
+            }
+        }
+    }
+
+    /// decode a `.Z` file of `compress`
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        decode_to(input, &mut decoded)?;
+
+        Ok(decoded)
+    }
+
+    /// decode a `.Z` file of `compress` read from `input`, the decoded bytes being written to
+    /// `output` as they come
+    pub fn decode_to(mut input: impl Read, output: &mut impl Write) -> Result<()> {
+        let mut header = [0; 3];
+        input.read_exact(&mut header)?;
+        if header[..2] != MAGIC {
+            return Err(CompressorError::InvalidData(String::from(
+                "not a file of compress",
+            )));
+        }
+
+        let max_bits = header[2] & BITS_MASK;
+        let block_mode = header[2] & BLOCK_MODE != 0;
+        check_max_bits(max_bits).map_err(|_| {
+            CompressorError::InvalidData(format!("compress codes of {max_bits} bits"))
+        })?;
+        let max_bits = max_bits as u32;
+        let max_code = 1 << max_bits;
+
+        // each string is the string of a prefix code followed by a byte
+        let mut prefixes: Vec<u32> = vec![0; max_code as usize];
+        let mut suffixes: Vec<u8> = (0..max_code).map(|code| code as u8).collect();
+
+        let mut reader = LsbStreamReader::new(input);
+        let mut width = CodeWidth::new();
+        let mut codes_in_group = 0;
+        let mut next_code = if block_mode { FIRST } else { CLEAR };
+
+        // skip the rest of the group of codes
+        let skip_group =
+            |reader: &mut LsbStreamReader<_>, codes_in_group: &mut usize, bits: u32| {
+                let padding = (8 - *codes_in_group) % 8;
+                for _ in 0..padding {
+                    if reader.read_bits(bits).is_err() {
+                        break;
+                    }
+                }
+                *codes_in_group = 0;
+            };
+
+        let mut stack = Vec::new();
+        let mut previous: Option<u32> = None;
+        let mut first_byte = 0;
+
+        loop {
+            if width.update(next_code, max_bits) {
+                skip_group(&mut reader, &mut codes_in_group, width.bits - 1);
+            }
+
+            // the last byte can hold part of a code
+            let code = match reader.read_bits(width.bits) {
+                Err(CompressorError::TruncatedInput) => break,
+                code => code?,
+            };
+            codes_in_group = (codes_in_group + 1) % 8;
+
+            let Some(previous_code) = previous else {
+                if code >= CLEAR {
+                    return Err(CompressorError::InvalidData(format!(
+                        "first compress code {code} is not a byte"
+                    )));
+                }
+
+                output.write_all(&[code as u8])?;
+                first_byte = code as u8;
+                previous = Some(code);
+                continue;
+            };
+
+            if block_mode && code == CLEAR {
+                skip_group(&mut reader, &mut codes_in_group, width.bits);
+                width = CodeWidth::new();
+                // the string added by the next code is never used
+                next_code = CLEAR;
+                continue;
+            }
+
+            // a code can be the one being added: the previous string followed by its first byte
+            let mut string_code = code;
+            if code >= next_code {
+                if code > next_code {
+                    return Err(CompressorError::InvalidData(format!(
+                        "compress code {code} is not in the dictionary"
+                    )));
+                }
+
+                stack.push(first_byte);
+                string_code = previous_code;
+            }
+
+            while string_code >= CLEAR {
+                stack.push(suffixes[string_code as usize]);
+                string_code = prefixes[string_code as usize];
+            }
+            first_byte = suffixes[string_code as usize];
+            stack.push(first_byte);
+
+            stack.reverse();
+            output.write_all(&stack)?;
+            stack.clear();
+
+            if next_code < max_code {
+                prefixes[next_code as usize] = previous_code;
+                suffixes[next_code as usize] = first_byte;
+                next_code += 1;
+            }
+            previous = Some(code);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::test_utils::{random_bytes, sample_texts};
+
+        /// a few words in a pattern, repeated far more than the random bytes
+        fn words() -> Vec<u8> {
+            (0..50_000)
+                .flat_map(|i: usize| {
+                    ["the ", "quick ", "brown ", "fox ", "jumps "][i * i % 7 % 5].bytes()
+                })
+                .collect()
+        }
+
+        fn texts() -> Vec<Vec<u8>> {
+            let mut texts = sample_texts(random_bytes(300_000));
+            texts.extend([
+                vec![0xFF; 100_000],
+                "TOBEORNOTTOBEORTOBEORNOT".bytes().collect(),
+                words(),
+            ]);
+            texts
+        }
+
+        #[test]
+        fn unix_compress_round_trip() {
+            for text in texts() {
+                for max_bits in [9, 12, DEFAULT_MAX_BITS] {
+                    let encoded = encode(&text, max_bits).unwrap();
+                    assert_eq!(text, decode(&encoded).unwrap(), "{max_bits} bits");
+                }
+            }
+
+            assert!(matches!(
+                encode(b"abc", 17),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+
+        #[test]
+        fn unix_compress_known_file() {
+            // read back as "TOBEORNOTTOBEORTOBEORNOT" by `gzip -d` and `uncompress`
+            let encoded = [
+                0x1F, 0x9D, 0x90, 0x54, 0x9E, 0x08, 0x29, 0xF2, 0x44, 0x8A, 0x93, 0x27, 0x54, 0x02,
+                0x0E, 0x2C, 0xA8, 0x90, 0xA0, 0x41, 0x84,
+            ];
+
+            let text: Vec<u8> = "TOBEORNOTTOBEORTOBEORNOT".bytes().collect();
+            assert_eq!(encoded.to_vec(), encode(&text, DEFAULT_MAX_BITS).unwrap());
+            assert_eq!(text, decode(&encoded).unwrap());
+        }
+
+        #[test]
+        fn unix_compress_dictionary_reset() {
+            // text changing after the dictionary is full
+            let mut text = words();
+            text.extend_from_slice(&random_bytes(300_000));
+            text.extend_from_slice(&words());
+
+            let encoded = encode(&text, 9).unwrap();
+            assert_eq!(text, decode(&encoded).unwrap());
+        }
+
+        #[test]
+        fn unix_compress_in_parts() {
+            let mut text = words();
+            text.extend_from_slice(&random_bytes(300_000));
+
+            let mut writer = UnixCompressWriter::new(Vec::new(), 12).unwrap();
+            for chunk in text.chunks(7_777) {
+                writer.write_all(chunk).unwrap();
+                writer.flush().unwrap();
+            }
+            let encoded = writer.finish().unwrap();
+
+            assert_eq!(encode(&text, 12).unwrap(), encoded);
+
+            let mut decoded = Vec::new();
+            decode_to(&encoded[..], &mut decoded).unwrap();
+            assert_eq!(text, decoded);
+        }
+
+        #[test]
+        fn unix_compress_invalid_input() {
+            assert!(matches!(
+                decode(&[0x1F]),
+                Err(CompressorError::TruncatedInput)
+            ));
+            assert!(matches!(
+                decode(&[0x1F, 0x8B, 0x90]),
+                Err(CompressorError::InvalidData(_))
+            ));
+            assert!(matches!(
+                decode(&[0x1F, 0x9D, 0x91]),
+                Err(CompressorError::InvalidData(_))
+            ));
+
+            // code 300 while the dictionary only goes to 257
+            let mut writer = LsbBitWriter::new();
+            writer.write_bytes(&[0x1F, 0x9D, 0x90]);
+            writer.write_bits(b'a' as u32, 9);
+            writer.write_bits(300, 9);
+            assert!(matches!(
+                decode(&writer.finish()),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+#[allow(non_snake_case)]
+pub mod ZeroRunLength {
+    use crate::{
+        error::{CompressorError, Result},
+        symbol::Symbol,
+        utils::check_decoded_size,
+    };
+
+    // NOTE: good ressources for implementation
+    // https://github.com/dsnet/compress/blob/master/doc/bzip2-format.pdf (RUNA and RUNB)
+
+    /// digits of the length of a run of zeros, in bijective base 2
+    pub const RUNA: u32 = 0;
+    pub const RUNB: u32 = 1;
+
+    /// last symbol, after the values 1 to 255 shifted by one
+    pub const END_OF_BLOCK: u32 = 257;
+
+    /// RUNA, RUNB, the 255 values other than 0 and the end of block
+    pub const ALPHABET_SIZE: usize = 258;
+
+    /// first byte of the symbols that do not fit in a byte, followed by the symbol minus 255
+    const ESCAPE: u8 = 255;
+
+    /// Symbols of the output of a move-to-front: the runs of zeros are written as their length in
+    /// bijective base 2 with RUNA (1) and RUNB (2), least significant digit first, the other
+    /// values are shifted by one and the symbols end with `end_of_block`.
+    ///
+    /// A single zero takes a single symbol, and a run of n zeros about log2(n) symbols.
+    ///
+    /// ```text
+    /// MTF: [3, 0, 0, 0, 1, 0, 0, 0, 0] => [4, RUNA, RUNA, 2, RUNB, RUNA, EOB]
+    /// ```
+    pub fn encode_runs<S: Symbol>(mtf: &[u8], end_of_block: S) -> Vec<S> {
+        let symbol = |value: u32| S::from_usize(value as usize).expect("smaller than a byte");
+
+        let mut symbols = Vec::with_capacity(mtf.len() + 1);
+        let flush = |symbols: &mut Vec<S>, mut run: usize| {
+            while run > 0 {
+                run -= 1;
+                symbols.push(symbol(if run & 1 == 0 { RUNA } else { RUNB }));
+                run >>= 1;
+            }
+        };
+
+        let mut run = 0;
+        for &value in mtf {
+            if value == 0 {
+                run += 1;
+                continue;
+            }
+
+            flush(&mut symbols, run);
+            run = 0;
+            symbols.push(symbol(value as u32 + 1));
+        }
+        flush(&mut symbols, run);
+        symbols.push(end_of_block);
+
+        symbols
+    }
+
+    /// decode the symbols of [`encode_runs`] with [`END_OF_BLOCK`], which must be the last one
+    ///
+    /// the symbols decoding to more than [`MAX_BLOCK_SIZE`](crate::utils::MAX_BLOCK_SIZE) bytes
+    /// are rejected before anything is written, as no block could hold them
+    pub fn decode_runs(symbols: &[u32]) -> Result<Vec<u8>> {
+        let invalid = |message: &str| CompressorError::InvalidData(String::from(message));
+
+        let Some((&END_OF_BLOCK, symbols)) = symbols.split_last() else {
+            return Err(invalid("zero runs without an end of block"));
+        };
+
+        // size of the decoded bytes, a run adding its digits one by one
+        let mut size: usize = 0;
+        let mut run_digit: usize = 1;
+        for &symbol in symbols {
+            let added = if symbol == RUNA || symbol == RUNB {
+                let digit = run_digit.checked_mul(symbol as usize + 1);
+                run_digit = run_digit.saturating_mul(2);
+                digit
+            } else if symbol < END_OF_BLOCK {
+                run_digit = 1;
+                Some(1)
+            } else {
+                return Err(invalid("zero run symbol after the end of block"));
+            };
+
+            size =
+                check_decoded_size(added.map_or(usize::MAX, |added| size.saturating_add(added)))?;
+        }
+
+        let mut decoded = Vec::with_capacity(size);
+        let mut run: usize = 0;
+        let mut run_digit: usize = 1;
+        for &symbol in symbols {
+            if symbol == RUNA || symbol == RUNB {
+                run += run_digit * (symbol as usize + 1);
+                run_digit *= 2;
+                continue;
+            }
+
+            decoded.resize(decoded.len() + run, 0);
+            run = 0;
+            run_digit = 1;
+            decoded.push((symbol - 1) as u8);
+        }
+        decoded.resize(decoded.len() + run, 0);
+
+        Ok(decoded)
+    }
+
+    /// encode the zero runs of bytes as [`ALPHABET_SIZE`] symbols, for an entropy coder
+    pub fn encode_symbols(input: &[u8]) -> Vec<u32> {
+        encode_runs(input, END_OF_BLOCK)
+    }
+
+    /// encode the zero runs of bytes, each symbol as a byte when it fits
+    ///
+    /// ```text
+    /// [symbol]... with the symbols 255, 256 and 257 written as [255][symbol - 255]
+    /// ```
+    pub fn encode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut encoded = Vec::with_capacity(input.len() / 2);
+        for symbol in encode_symbols(input) {
+            match u8::try_from(symbol) {
+                Ok(byte) if byte != ESCAPE => encoded.push(byte),
+                _ => encoded.extend_from_slice(&[ESCAPE, (symbol - ESCAPE as u32) as u8]),
+            }
+        }
+
+        Ok(encoded)
+    }
+
+    /// decode bytes encoded with [`encode`]
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut symbols = Vec::with_capacity(input.len());
+        let mut bytes = input.iter();
+        while let Some(&byte) = bytes.next() {
+            if byte != ESCAPE {
+                symbols.push(byte as u32);
+                continue;
+            }
+
+            let &offset = bytes.next().ok_or(CompressorError::TruncatedInput)?;
+            symbols.push(ESCAPE as u32 + offset as u32);
+        }
+
+        if symbols.is_empty() {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        decode_runs(&symbols)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::algorithms::run_length_encoding::RLE;
+        use crate::test_utils::pseudo_random;
+
+        #[test]
+        fn zero_runs_symbols() {
+            let mtf = [3, 0, 0, 0, 1, 0, 0, 0, 0];
+            let symbols = encode_symbols(&mtf);
+            assert_eq!(vec![4, RUNA, RUNA, 2, RUNB, RUNA, END_OF_BLOCK], symbols);
+            assert_eq!(mtf.to_vec(), decode_runs(&symbols).unwrap());
+
+            // runs of 1 to 6 zeros
+            for (length, digits) in [
+                (1, vec![RUNA]),
+                (2, vec![RUNB]),
+                (3, vec![RUNA, RUNA]),
+                (4, vec![RUNB, RUNA]),
+                (5, vec![RUNA, RUNB]),
+                (6, vec![RUNB, RUNB]),
+            ] {
+                let mut expected = digits;
+                expected.push(END_OF_BLOCK);
+                assert_eq!(expected, encode_symbols(&vec![0; length]));
+            }
+        }
+
+        #[test]
+        fn zero_runs_round_trip() {
+            let mtf: Vec<u8> = pseudo_random(42)
+                .take(50_000)
+                .map(|seed| {
+                    // mostly zeros, with every value from time to time
+                    if seed >> 28 < 12 {
+                        0
+                    } else {
+                        (seed >> 8) as u8
+                    }
+                })
+                .collect();
+
+            for input in [&[][..], &[0], &[255, 254, 0, 255], &[0; 100_000], &mtf] {
+                let encoded = encode(input).unwrap();
+                assert_eq!(input, decode(&encoded).unwrap());
+            }
+
+            // isolated values keep a single byte, unlike a run-length encoding
+            let encoded = encode(&mtf).unwrap();
+            let rle = RLE::encode(&mtf).unwrap();
+            assert!(
+                encoded.len() < rle.len() * 2 / 3,
+                "{} {}",
+                encoded.len(),
+                rle.len()
+            );
+        }
+
+        #[test]
+        fn zero_runs_invalid_input() {
+            assert!(matches!(decode(&[]), Err(CompressorError::TruncatedInput)));
+            assert!(matches!(
+                decode(&[ESCAPE]),
+                Err(CompressorError::TruncatedInput)
+            ));
+            for invalid in [&[3, 0][..], &[3, ESCAPE, 2, 4, ESCAPE, 2], &[ESCAPE, 3]] {
+                assert!(matches!(
+                    decode(invalid),
+                    Err(CompressorError::InvalidData(_))
+                ));
+            }
+            // 2^41 zeros, longer than any block, and runs of 2^29 zeros adding up to more
+            let mut too_long = vec![RUNB as u8; 40];
+            too_long.extend_from_slice(&[ESCAPE, 2]);
+            let mut run = vec![RUNA as u8; 29];
+            run.push(2);
+            let mut too_many = run.repeat(3);
+            too_many.extend_from_slice(&[ESCAPE, 2]);
+            for invalid in [too_long, too_many] {
+                assert!(matches!(
+                    decode(&invalid),
+                    Err(CompressorError::InvalidData(_))
+                ));
+            }
+            assert!(matches!(
+                decode_runs(
+                    &[RUNB; 70]
+                        .iter()
+                        .chain(&[END_OF_BLOCK])
+                        .copied()
+                        .collect::<Vec<_>>()
+                ),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+#[allow(non_snake_case)]
+pub mod Zlib {
+    use crate::{
+        algorithms::deflate::Deflate,
+        checksum::adler32,
+        error::{CompressorError, Result},
+    };
+
+    // NOTE: good ressources for implementation
+    // https://www.rfc-editor.org/rfc/rfc1950
+
+    /// compression method (DEFLATE) and window size (32 KiB)
+    const DEFLATE_WINDOW_32K: u8 = 0x78;
+
+    const FLAG_DICTIONARY: u8 = 1 << 5;
+
+    /// encode an array of bytes as a zlib stream
+    ///
+    /// ```text
+    /// [78] [flags] [DEFLATE blocks] [Adler-32]
+    /// ```
+    ///
+    /// @**returns** Vec\<u8\> => the 2 bytes header, the DEFLATE stream, then the Adler-32 of the input (big endian)
+    pub fn encode(input: &[u8], level: u8) -> Result<Vec<u8>> {
+        let deflated = Deflate::encode(input, level)?;
+
+        // only informative: 0 fastest, 1 fast, 2 default, 3 slowest
+        let compression_level: u8 = match level {
+            0 | 1 => 0,
+            2..=5 => 1,
+            6 => 2,
+            _ => 3,
+        };
+
+        // the header as a 16 bits number must be a multiple of 31
+        let flags = compression_level << 6;
+        let check = 31 - (u16::from_be_bytes([DEFLATE_WINDOW_32K, flags]) % 31);
+        let flags = flags | (check % 31) as u8;
+
+        let mut encoded = Vec::with_capacity(2 + deflated.len() + 4);
+        encoded.push(DEFLATE_WINDOW_32K);
+        encoded.push(flags);
+        encoded.extend_from_slice(&deflated);
+        encoded.extend_from_slice(&adler32(input).to_be_bytes());
+
+        Ok(encoded)
+    }
+
+    pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let [method, flags] = *input.get(..2).ok_or(CompressorError::TruncatedInput)? else {
+            unreachable!("2 bytes")
+        };
+
+        if method & 0x0F != 8 || method >> 4 > 7 {
+            return Err(CompressorError::InvalidData(format!(
+                "zlib compression method {method:#04x} is not DEFLATE"
+            )));
+        }
+        if u16::from_be_bytes([method, flags]) % 31 != 0 {
+            return Err(CompressorError::InvalidData(String::from(
+                "zlib header check failed",
+            )));
+        }
+        if flags & FLAG_DICTIONARY != 0 {
+            return Err(CompressorError::InvalidData(String::from(
+                "zlib preset dictionaries are not supported",
+            )));
+        }
+
+        let (decoded, size) = Deflate::decode_first(&input[2..])?;
+
+        let trailer = input
+            .get(2 + size..2 + size + 4)
+            .ok_or(CompressorError::TruncatedInput)?;
+        let expected = u32::from_be_bytes(trailer.try_into().expect("4 bytes"));
+        let found = adler32(&decoded);
+        if expected != found {
+            return Err(CompressorError::ChecksumMismatch {
+                what: String::from("uncompressed zlib data"),
+                expected,
+                found,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use crate::algorithms::deflate::Deflate::DEFAULT_LEVEL;
+
+        #[test]
+        fn zlib_round_trip() {
+            let text: Vec<u8> = "AAABBCCDACCAA".repeat(1000).bytes().collect();
+
+            for level in [0, 1, DEFAULT_LEVEL, 9] {
+                let encoded = encode(&text, level).unwrap();
+                assert_eq!(0, u16::from_be_bytes([encoded[0], encoded[1]]) % 31);
+                assert_eq!(text, decode(&encoded).unwrap());
+            }
+
+            // default level header, as written by zlib
+            assert_eq!([0x78, 0x9C], encode(b"", DEFAULT_LEVEL).unwrap()[..2]);
+        }
+
+        #[test]
+        fn zlib_from_zlib() {
+            // python3 -c "import zlib; print(list(zlib.compress(b'hello')))"
+            let encoded = [
+                0x78, 0x9C, 0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00, 0x06, 0x2C, 0x02, 0x15,
+            ];
+            assert_eq!(b"hello".to_vec(), decode(&encoded).unwrap());
+        }
+
+        #[test]
+        fn zlib_invalid_input() {
+            let encoded = encode(b"hello hello", DEFAULT_LEVEL).unwrap();
+
+            for end in 0..encoded.len() {
+                assert!(decode(&encoded[..end]).is_err());
+            }
+
+            let mut corrupted = encoded.clone();
+            let last = corrupted.len() - 1;
+            corrupted[last] ^= 1;
+            assert!(matches!(
+                decode(&corrupted),
+                Err(CompressorError::ChecksumMismatch { .. })
+            ));
+
+            assert!(matches!(
+                decode(&[0x78, 0x9D]),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,569 @@
+// Bits packed from the least significant bit of each byte, as in DEFLATE and Unix compress, or from
+// the most significant one, as in bzip2
+//
+// NOTE: [`crate::compressed_buffer::CompressedBuffer`] writes the bits from the most significant one
+
+use std::io::Read;
+
+use crate::error::{CompressorError, Result};
+
+/// Write values of up to 32 bits, the first bit of a byte being its least significant one.
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::bit_io::{LsbBitReader, LsbBitWriter};
+///
+/// let mut writer = LsbBitWriter::new();
+/// writer.write_bits(0b101, 3);
+/// writer.write_bits(0b11, 2);
+/// let bytes = writer.finish();
+/// assert_eq!(vec![0b0001_1101], bytes);
+///
+/// let mut reader = LsbBitReader::new(&bytes);
+/// assert_eq!(0b101, reader.read_bits(3).unwrap());
+/// assert_eq!(0b11, reader.read_bits(2).unwrap());
+/// ```
+pub struct LsbBitWriter {
+    buffer: Vec<u8>,
+    /// bits not written in the buffer yet
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl LsbBitWriter {
+    pub fn new() -> Self {
+        LsbBitWriter {
+            buffer: Vec::new(),
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// write the `count` lowest bits of `value`
+    pub fn write_bits(&mut self, value: u32, count: u32) {
+        debug_assert!(count <= 32 && (count == 32 || value >> count == 0));
+
+        self.bit_buffer |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+
+        while self.bit_count >= 8 {
+            self.buffer.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    /// fill the current byte with zeros
+    pub fn align_to_byte(&mut self) {
+        if self.bit_count > 0 {
+            self.buffer.push(self.bit_buffer as u8);
+            self.bit_buffer = 0;
+            self.bit_count = 0;
+        }
+    }
+
+    /// write whole bytes, after [`LsbBitWriter::align_to_byte`]
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        debug_assert_eq!(0, self.bit_count);
+
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// number of bits written
+    pub fn bit_len(&self) -> usize {
+        self.buffer.len() * 8 + self.bit_count as usize
+    }
+
+    /// take the whole bytes written so far, the bits of an incomplete byte staying in the writer
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// @**returns** Vec\<u8\> => the written bits, the unused bits of the last byte are zeros
+    pub fn finish(mut self) -> Vec<u8> {
+        self.align_to_byte();
+
+        self.buffer
+    }
+}
+
+impl Default for LsbBitWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read the bits written by a [`LsbBitWriter`].
+pub struct LsbBitReader<'a> {
+    input: &'a [u8],
+    /// next byte to move in the bit buffer
+    position: usize,
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl<'a> LsbBitReader<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        LsbBitReader {
+            input,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.bit_count <= 56 && self.position < self.input.len() {
+            self.bit_buffer |= (self.input[self.position] as u64) << self.bit_count;
+            self.position += 1;
+            self.bit_count += 8;
+        }
+    }
+
+    /// read a value of `count` bits (at most 32)
+    pub fn read_bits(&mut self, count: u32) -> Result<u32> {
+        debug_assert!(count <= 32);
+
+        if self.bit_count < count {
+            self.refill();
+            if self.bit_count < count {
+                return Err(CompressorError::TruncatedInput);
+            }
+        }
+
+        let value = self.bit_buffer & ((1 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+
+        Ok(value as u32)
+    }
+
+    pub fn read_bit(&mut self) -> Result<u32> {
+        self.read_bits(1)
+    }
+
+    /// skip the bits left in the current byte
+    pub fn align_to_byte(&mut self) {
+        let extra = self.bit_count % 8;
+        self.bit_buffer >>= extra;
+        self.bit_count -= extra;
+    }
+
+    /// read whole bytes, after [`LsbBitReader::align_to_byte`]
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        debug_assert_eq!(0, self.bit_count % 8);
+
+        // the bytes in the bit buffer are read again from the input
+        let start = self.bytes_read();
+        let end = start
+            .checked_add(count)
+            .filter(|&end| end <= self.input.len())
+            .ok_or(CompressorError::TruncatedInput)?;
+
+        self.position = end;
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+
+        Ok(&self.input[start..end])
+    }
+
+    /// number of bits that can still be read
+    pub fn bits_left(&self) -> usize {
+        (self.input.len() - self.position) * 8 + self.bit_count as usize
+    }
+
+    /// number of bytes read, a partially read byte counting as read
+    pub fn bytes_read(&self) -> usize {
+        self.position - (self.bit_count / 8) as usize
+    }
+}
+
+/// Same as [`LsbBitReader`], the bytes being read from a stream only once their bits are needed.
+///
+/// Nothing is read past the byte holding the last bit read, so the stream can be read on its own
+/// again after [`LsbStreamReader::align_to_byte`].
+pub struct LsbStreamReader<R: Read> {
+    inner: R,
+    bytes_read: usize,
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl<R: Read> LsbStreamReader<R> {
+    pub fn new(inner: R) -> Self {
+        LsbStreamReader {
+            inner,
+            bytes_read: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// read a value of `count` bits (at most 32)
+    pub fn read_bits(&mut self, count: u32) -> Result<u32> {
+        debug_assert!(count <= 32);
+
+        while self.bit_count < count {
+            let mut byte = [0];
+            self.inner.read_exact(&mut byte)?;
+            self.bytes_read += 1;
+
+            self.bit_buffer |= (byte[0] as u64) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buffer & ((1 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+
+        Ok(value as u32)
+    }
+
+    pub fn read_bit(&mut self) -> Result<u32> {
+        self.read_bits(1)
+    }
+
+    /// skip the bits left in the current byte
+    pub fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    /// fill the buffer with whole bytes, after [`LsbStreamReader::align_to_byte`]
+    pub fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<()> {
+        debug_assert_eq!(0, self.bit_count);
+
+        self.inner.read_exact(buffer)?;
+        self.bytes_read += buffer.len();
+
+        Ok(())
+    }
+
+    /// number of bytes read, a partially read byte counting as read
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+}
+
+/// Read bits packed from the most significant bit of each byte, as written by a
+/// [`CompressedBuffer`](crate::compressed_buffer::CompressedBuffer).
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::bit_io::MsbBitReader;
+///
+/// let mut reader = MsbBitReader::new(&[0b1011_0010]);
+/// assert_eq!(0b101, reader.read_bits(3).unwrap());
+/// assert_eq!(0b10, reader.read_bits(2).unwrap());
+/// ```
+pub struct MsbBitReader<'a> {
+    input: &'a [u8],
+    /// next byte to move in the bit buffer
+    position: usize,
+    /// bits not read yet, from the most significant one
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl<'a> MsbBitReader<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        MsbBitReader {
+            input,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.bit_count <= 56 && self.position < self.input.len() {
+            self.bit_buffer |= (self.input[self.position] as u64) << (56 - self.bit_count);
+            self.position += 1;
+            self.bit_count += 8;
+        }
+    }
+
+    /// read a value of `count` bits (at most 32), from its most significant bit
+    pub fn read_bits(&mut self, count: u32) -> Result<u32> {
+        debug_assert!(count <= 32);
+
+        if count == 0 {
+            return Ok(0);
+        }
+
+        if self.bit_count < count {
+            self.refill();
+            if self.bit_count < count {
+                return Err(CompressorError::TruncatedInput);
+            }
+        }
+
+        let value = self.bit_buffer >> (64 - count);
+        self.bit_buffer <<= count;
+        self.bit_count -= count;
+
+        Ok(value as u32)
+    }
+
+    pub fn read_bit(&mut self) -> Result<u32> {
+        self.read_bits(1)
+    }
+
+    /// the next `count` bits (at most 32) without reading them, zeros past the end of the input
+    pub fn peek_bits(&mut self, count: u32) -> u32 {
+        debug_assert!(0 < count && count <= 32);
+
+        if self.bit_count < count {
+            self.refill();
+        }
+
+        (self.bit_buffer >> (64 - count)) as u32
+    }
+
+    /// skip `count` bits (at most 32), usually after [`MsbBitReader::peek_bits`]
+    pub fn consume(&mut self, count: u32) -> Result<()> {
+        self.read_bits(count).map(|_| ())
+    }
+
+    /// number of bits read
+    pub fn bits_read(&self) -> usize {
+        self.position * 8 - self.bit_count as usize
+    }
+
+    /// skip the bits left in the current byte
+    pub fn align_to_byte(&mut self) {
+        let extra = self.bit_count % 8;
+        self.bit_buffer <<= extra;
+        self.bit_count -= extra;
+    }
+
+    /// number of bytes read, a partially read byte counting as read
+    pub fn bytes_read(&self) -> usize {
+        self.position - (self.bit_count / 8) as usize
+    }
+}
+
+/// Same as [`MsbBitReader`], the bytes being read from a stream only once their bits are needed.
+///
+/// As with [`LsbStreamReader`], nothing is read past the byte holding the last bit read.
+pub struct MsbStreamReader<R: Read> {
+    inner: R,
+    /// bits not read yet, from the most significant one
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl<R: Read> MsbStreamReader<R> {
+    pub fn new(inner: R) -> Self {
+        MsbStreamReader {
+            inner,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// read a value of `count` bits (at most 32), from its most significant bit
+    pub fn read_bits(&mut self, count: u32) -> Result<u32> {
+        debug_assert!(count <= 32);
+
+        if count == 0 {
+            return Ok(0);
+        }
+
+        while self.bit_count < count {
+            let mut byte = [0];
+            self.inner.read_exact(&mut byte)?;
+
+            self.bit_buffer |= (byte[0] as u64) << (56 - self.bit_count);
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buffer >> (64 - count);
+        self.bit_buffer <<= count;
+        self.bit_count -= count;
+
+        Ok(value as u32)
+    }
+
+    pub fn read_bit(&mut self) -> Result<u32> {
+        self.read_bits(1)
+    }
+
+    /// skip the bits left in the current byte
+    pub fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::compressed_buffer::CompressedBuffer;
+
+    #[test]
+    fn lsb_bits_round_trip() {
+        let values: Vec<(u32, u32)> = (0..1000)
+            .map(|i: u32| {
+                let count = i % 33;
+                let value = i.wrapping_mul(2_654_435_761);
+                let value = if count == 32 {
+                    value
+                } else {
+                    value & ((1 << count) - 1)
+                };
+                (value, count)
+            })
+            .collect();
+
+        let mut writer = LsbBitWriter::new();
+        for &(value, count) in &values {
+            writer.write_bits(value, count);
+        }
+        let bits = writer.bit_len();
+        let bytes = writer.finish();
+        assert_eq!(bits.div_ceil(8), bytes.len());
+
+        let mut reader = LsbBitReader::new(&bytes);
+        for &(value, count) in &values {
+            assert_eq!(value, reader.read_bits(count).unwrap());
+        }
+        assert!(matches!(
+            reader.read_bits(8),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn lsb_bits_with_bytes() {
+        let mut writer = LsbBitWriter::new();
+        writer.write_bits(1, 1);
+        writer.align_to_byte();
+        writer.write_bytes(&[0xAB, 0xCD]);
+        writer.write_bits(0b10, 2);
+        let bytes = writer.finish();
+        assert_eq!(vec![0x01, 0xAB, 0xCD, 0b10], bytes);
+
+        let mut writer = LsbBitWriter::new();
+        writer.write_bits(0x1FF, 9);
+        assert_eq!(vec![0xFF], writer.take_bytes());
+        assert_eq!(1, writer.bit_len());
+        writer.write_bits(0, 7);
+        assert_eq!(vec![0x01], writer.finish());
+
+        let mut reader = LsbBitReader::new(&bytes);
+        assert_eq!(1, reader.read_bit().unwrap());
+        assert_eq!(31, reader.bits_left());
+        reader.align_to_byte();
+        assert_eq!(1, reader.bytes_read());
+        assert_eq!(&[0xAB, 0xCD], reader.read_bytes(2).unwrap());
+        assert_eq!(0b10, reader.read_bits(2).unwrap());
+        assert_eq!(4, reader.bytes_read());
+        reader.align_to_byte();
+        assert!(reader.read_bytes(1).is_err());
+    }
+
+    #[test]
+    fn lsb_bits_from_stream() {
+        let mut writer = LsbBitWriter::new();
+        writer.write_bits(0b101, 3);
+        writer.align_to_byte();
+        writer.write_bytes(&[0xAB, 0xCD]);
+        writer.write_bits(0x1234_5678, 32);
+        let mut bytes = writer.finish();
+        bytes.push(0xEF);
+
+        let mut input = &bytes[..];
+        let mut reader = LsbStreamReader::new(&mut input);
+        assert_eq!(0b101, reader.read_bits(3).unwrap());
+        reader.align_to_byte();
+        let mut read = [0; 2];
+        reader.read_bytes(&mut read).unwrap();
+        assert_eq!([0xAB, 0xCD], read);
+        assert_eq!(0x1234_5678, reader.read_bits(32).unwrap());
+        assert_eq!(7, reader.bytes_read());
+
+        // the byte following the bits is left in the stream
+        assert_eq!(&[0xEF], input);
+
+        let mut reader = LsbStreamReader::new(&bytes[..2]);
+        assert!(matches!(
+            reader.read_bits(17),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn msb_bits_from_stream() {
+        let bytes = [0b1010_0000, 0x12, 0x34, 0x56, 0x78, 0xEF];
+
+        let mut input = &bytes[..];
+        let mut reader = MsbStreamReader::new(&mut input);
+        assert_eq!(0b101, reader.read_bits(3).unwrap());
+        reader.align_to_byte();
+        assert_eq!(0x1234_5678, reader.read_bits(32).unwrap());
+
+        // the byte following the bits is left in the stream
+        assert_eq!(&[0xEF], input);
+
+        let mut reader = MsbStreamReader::new(&bytes[..2]);
+        assert!(matches!(
+            reader.read_bits(17),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn msb_bits_from_compressed_buffer() {
+        let values: Vec<(u32, u32)> = (0..1000)
+            .map(|i: u32| {
+                let count = i % 33;
+                let value = i.wrapping_mul(2_654_435_761);
+                let value = if count == 32 {
+                    value
+                } else {
+                    value & ((1 << count) - 1)
+                };
+                (value, count)
+            })
+            .collect();
+
+        let mut buffer = CompressedBuffer::new();
+        for &(value, count) in &values {
+            buffer.push_bits(value as usize, count);
+        }
+        let bytes = buffer.get_buffer();
+
+        let mut reader = MsbBitReader::new(&bytes);
+        for &(value, count) in &values {
+            assert_eq!(value, reader.read_bits(count).unwrap());
+        }
+        reader.align_to_byte();
+        assert_eq!(bytes.len(), reader.bytes_read());
+        assert!(matches!(
+            reader.read_bit(),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn msb_peek_bits() {
+        let mut reader = MsbBitReader::new(&[0b1011_0010, 0b0111_0000]);
+        assert_eq!(0b1011, reader.peek_bits(4));
+        assert_eq!(0b1011_0010_0111, reader.peek_bits(12));
+        reader.consume(3).unwrap();
+        assert_eq!(3, reader.bits_read());
+
+        // zeros past the end
+        assert_eq!(0b1_0010_0111_0000_0000_0000, reader.peek_bits(21));
+        assert!(matches!(
+            reader.consume(14),
+            Err(CompressorError::TruncatedInput)
+        ));
+        reader.consume(13).unwrap();
+        assert_eq!(16, reader.bits_read());
+    }
+}
@@ -1,5 +1,6 @@
 use std::ops::AddAssign;
 
+#[derive(Default)]
 pub struct BitQueue {
     bits_to_follow: usize,
 }
@@ -12,16 +13,21 @@ impl BitQueue {
     /// Returns a bit followed by the number of bits to follow with inversed value
     pub fn bit_followed_by_inverted(&mut self, bit: u8) -> Vec<u8> {
         let mut bits = Vec::with_capacity(self.bits_to_follow + 1);
-        bits.push(bit);
+        self.push_bit_followed_by_inverted(bit, |bit| bits.push(bit));
+
+        bits
+    }
+
+    /// Same as [`BitQueue::bit_followed_by_inverted`], giving the bits one by one instead of allocating them
+    pub fn push_bit_followed_by_inverted(&mut self, bit: u8, mut push: impl FnMut(u8)) {
+        push(bit);
 
         let inverted_bit = !bit & 1;
         for _ in 0..self.bits_to_follow {
-            bits.push(inverted_bit);
+            push(inverted_bit);
         }
 
         self.bits_to_follow = 0;
-
-        bits
     }
 }
 
@@ -0,0 +1,642 @@
+// Huffman codes given only by the length of the code of each symbol
+//
+// NOTE: good ressources for implementation
+// https://en.wikipedia.org/wiki/Canonical_Huffman_code
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.2
+// https://en.wikipedia.org/wiki/Package-merge_algorithm
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+};
+
+use crate::{
+    bit_io::MsbBitReader,
+    error::{CompressorError, Result},
+};
+
+/// Length of the Huffman code of each symbol, none being longer than `max_length` bits.
+///
+/// The lengths of an optimal Huffman tree are used when they fit, otherwise the package-merge
+/// algorithm finds the optimal lengths among the codes of at most `max_length` bits.
+///
+/// Symbols with a frequency of 0 get a length of 0 (no code), a single symbol gets a code of 1 bit.
+///
+/// @**returns** Vec\<u8\> => code length of each symbol
+pub fn code_lengths(frequencies: &[usize], max_length: u8) -> Vec<u8> {
+    let mut lengths = vec![0; frequencies.len()];
+
+    let used: Vec<usize> = (0..frequencies.len())
+        .filter(|&symbol| frequencies[symbol] > 0)
+        .collect();
+    debug_assert!(max_length < 32 && used.len() <= 1 << max_length);
+
+    match used[..] {
+        [] => return lengths,
+        [symbol] => {
+            lengths[symbol] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    // the leaves are the first nodes, then each merge of the two least frequent nodes adds a parent
+    let mut parents = vec![usize::MAX; used.len()];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = used
+        .iter()
+        .enumerate()
+        .map(|(node, &symbol)| Reverse((frequencies[symbol], node)))
+        .collect();
+
+    while let (Some(Reverse((first, a))), Some(Reverse((second, b)))) = (heap.pop(), heap.pop()) {
+        let parent = parents.len();
+        parents.push(usize::MAX);
+        parents[a] = parent;
+        parents[b] = parent;
+        heap.push(Reverse((first + second, parent)));
+    }
+
+    // a parent is always after its children, the root being the last node
+    let mut depths = vec![0; parents.len()];
+    for node in (0..parents.len() - 1).rev() {
+        depths[node] = depths[parents[node]] + 1;
+    }
+
+    if depths[..used.len()]
+        .iter()
+        .any(|&depth| depth > max_length as usize)
+    {
+        package_merge(&mut lengths, frequencies, &used, max_length);
+        return lengths;
+    }
+
+    for (node, &symbol) in used.iter().enumerate() {
+        lengths[symbol] = depths[node] as u8;
+    }
+
+    lengths
+}
+
+/// An item of the package-merge lists, a symbol or a package of two items of the previous list
+enum Item {
+    Leaf(usize),
+    Package(usize, usize),
+}
+
+/// Optimal code lengths of at most `max_length` bits.
+///
+/// A list starts with the symbols ordered by frequency, the items of the list are paired into
+/// packages weighing the sum of their frequencies, which are merged with the symbols into the next
+/// list. After `max_length - 1` lists, the code length of a symbol is the number of times it is
+/// found in the `2n - 2` lightest items.
+///
+/// ```text
+/// A: 1, B: 1, C: 2, D: 4, E: 8 and 3 bits
+/// list 3: A1 B1 C2 D4 E8
+/// list 2: A1 B1 C2 (AB)2 D4 (CD)6 E8
+/// list 1: A1 B1 C2 (AB)2 D4 (C(AB))4 E8 (D(CD))10 => A: 3, B: 3, C: 3, D: 3, E: 1
+/// ```
+fn package_merge(lengths: &mut [u8], frequencies: &[usize], used: &[usize], max_length: u8) {
+    let mut symbols = used.to_vec();
+    symbols.sort_by_key(|&symbol| (frequencies[symbol], symbol));
+
+    // the items of every list, a list holding (weight, item) pairs
+    let mut items: Vec<Item> = symbols.iter().map(|&symbol| Item::Leaf(symbol)).collect();
+    let leaves: Vec<(usize, usize)> = symbols
+        .iter()
+        .enumerate()
+        .map(|(item, &symbol)| (frequencies[symbol], item))
+        .collect();
+
+    let mut list = leaves.clone();
+    for _ in 1..max_length {
+        let packages: Vec<(usize, usize)> = list
+            .chunks_exact(2)
+            .map(|pair| {
+                items.push(Item::Package(pair[0].1, pair[1].1));
+                (pair[0].0 + pair[1].0, items.len() - 1)
+            })
+            .collect();
+
+        // the symbols come before the packages of the same weight
+        let mut merged = Vec::with_capacity(leaves.len() + packages.len());
+        let (mut i, mut j) = (0, 0);
+        while i < leaves.len() || j < packages.len() {
+            if j == packages.len() || (i < leaves.len() && leaves[i].0 <= packages[j].0) {
+                merged.push(leaves[i]);
+                i += 1;
+            } else {
+                merged.push(packages[j]);
+                j += 1;
+            }
+        }
+        list = merged;
+    }
+
+    let mut stack: Vec<usize> = list[..2 * used.len() - 2]
+        .iter()
+        .map(|&(_, item)| item)
+        .collect();
+    while let Some(item) = stack.pop() {
+        match items[item] {
+            Item::Leaf(symbol) => lengths[symbol] += 1,
+            Item::Package(first, second) => stack.extend([first, second]),
+        }
+    }
+}
+
+/// Code of each symbol, the codes of a length being consecutive numbers in the order of the symbols.
+///
+/// ## Example:
+///
+/// ```text
+/// lengths: A: 2, B: 1, C: 3, D: 3 => B: 0, A: 10, C: 110, D: 111
+/// ```
+///
+/// @**returns** Vec\<u32\> => code of each symbol (0 for the symbols without code)
+pub fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let max_length = lengths.iter().copied().max().unwrap_or(0) as usize;
+
+    let mut length_counts = vec![0u32; max_length + 1];
+    for &length in lengths.iter().filter(|&&length| length > 0) {
+        length_counts[length as usize] += 1;
+    }
+
+    // first code of each length
+    let mut next_code = vec![0u32; max_length + 1];
+    for length in 1..=max_length {
+        next_code[length] = (next_code[length - 1] + length_counts[length - 1]) << 1;
+    }
+
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                return 0;
+            }
+
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+            code
+        })
+        .collect()
+}
+
+/// Find back the symbols of canonical codes, one bit at a time.
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::canonical_huffman::CanonicalDecoder;
+///
+/// // A: 10, B: 0, C: 110, D: 111
+/// let decoder = CanonicalDecoder::new(&[2, 1, 3, 3]).unwrap();
+///
+/// let mut bits = [1, 1, 0].into_iter();
+/// assert_eq!(2, decoder.decode(|| Ok(bits.next().unwrap())).unwrap());
+/// ```
+pub struct CanonicalDecoder {
+    /// number of codes of each length
+    length_counts: Vec<u32>,
+    /// symbols ordered by code
+    symbols: Vec<usize>,
+}
+
+impl CanonicalDecoder {
+    /// the lengths can leave codes unused, but not describe more codes than there are
+    pub fn new(lengths: &[u8]) -> Result<Self> {
+        let max_length = lengths.iter().copied().max().unwrap_or(0) as usize;
+
+        let mut length_counts = vec![0u32; max_length + 1];
+        for &length in lengths.iter().filter(|&&length| length > 0) {
+            length_counts[length as usize] += 1;
+        }
+
+        // codes left for each length
+        let mut left: i64 = 1;
+        for &count in &length_counts[1..] {
+            left = 2 * left - count as i64;
+            if left < 0 {
+                return Err(CompressorError::InvalidData(String::from(
+                    "more huffman codes than their lengths allow",
+                )));
+            }
+        }
+
+        let mut symbols: Vec<usize> = (0..lengths.len())
+            .filter(|&symbol| lengths[symbol] > 0)
+            .collect();
+        symbols.sort_by_key(|&symbol| lengths[symbol]);
+
+        Ok(CanonicalDecoder {
+            length_counts,
+            symbols,
+        })
+    }
+
+    /// read the bits of a code, from the first one
+    pub fn decode(&self, mut next_bit: impl FnMut() -> Result<u32>) -> Result<usize> {
+        // first code and index in the symbols of the current length
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+
+        for &count in &self.length_counts[1..] {
+            code |= next_bit()?;
+            if code < first + count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(CompressorError::InvalidData(String::from(
+            "bits not matching any huffman code",
+        )))
+    }
+}
+
+/// most bits of the codes looked up at once, the longer codes continuing in other tables
+const TABLE_BITS: u32 = 11;
+
+/// longest code of a [`TableDecoder`]
+pub const MAX_TABLE_CODE_LENGTH: u8 = 32;
+
+/// an entry is `symbol << 6 | bits of the code left` or, with [`LINK`], `offset << 6 | bits` of the
+/// table of the following bits
+const LINK: u32 = 1 << 31;
+const BITS_MASK: u32 = (1 << 6) - 1;
+
+/// most symbols of a [`TableDecoder`], a symbol or an offset of an entry has the 25 bits between
+/// the bits of the code left and [`LINK`]
+pub const MAX_TABLE_SYMBOLS: usize = 1 << 25;
+
+/// entry of the bits not matching any code, no code has a length of 0
+const NO_CODE: u32 = 0;
+
+/// Find back the symbols of Huffman codes with lookup tables, several bits at a time.
+///
+/// The next [`TABLE_BITS`] bits index a first table giving the symbol and the length of its code.
+/// The codes longer than that share their first bits with a link to another table, indexed by their
+/// following bits.
+///
+/// ```text
+/// A: 0, B: 10, C: 110, D: 1110, E: 1111 with tables of 2 bits
+/// first table: 00 => A (1), 01 => A (1), 10 => B (2), 11 => link to a table of 2 bits
+/// linked table: 00 => C (1), 01 => C (1), 10 => D (2), 11 => E (2)
+/// ```
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::{bit_io::MsbBitReader, canonical_huffman::TableDecoder};
+///
+/// // A: 10, B: 0, C: 110, D: 111
+/// let decoder = TableDecoder::new(&[2, 1, 3, 3]).unwrap();
+///
+/// let mut reader = MsbBitReader::new(&[0b1100_1000]);
+/// assert_eq!(2, decoder.decode(&mut reader).unwrap());
+/// assert_eq!(1, decoder.decode(&mut reader).unwrap());
+/// assert_eq!(0, decoder.decode(&mut reader).unwrap());
+/// ```
+pub struct TableDecoder {
+    /// number of bits of the first table
+    bits: u32,
+    /// the first table, followed by the linked tables
+    entries: Vec<u32>,
+}
+
+impl TableDecoder {
+    /// decoder of the canonical codes of these lengths, which can leave codes unused
+    pub fn new(lengths: &[u8]) -> Result<Self> {
+        // also checks that the lengths do not describe too many codes
+        CanonicalDecoder::new(lengths)?;
+
+        let codes: Vec<(usize, u32, u8)> = canonical_codes(lengths)
+            .into_iter()
+            .zip(lengths.iter().copied())
+            .enumerate()
+            .map(|(symbol, (code, length))| (symbol, code, length))
+            .collect();
+
+        TableDecoder::from_codes(&codes)
+    }
+
+    /// decoder of any prefix codes given as (symbol, code, length), which can leave codes unused
+    ///
+    /// the codes of length 0 are ignored
+    pub fn from_codes(codes: &[(usize, u32, u8)]) -> Result<Self> {
+        let codes: Vec<(usize, u32, u32)> = codes
+            .iter()
+            .filter(|&&(_, _, length)| length > 0)
+            .map(|&(symbol, code, length)| (symbol, code, length as u32))
+            .collect();
+
+        if let Some(&(symbol, _, _)) = codes
+            .iter()
+            .find(|&&(symbol, _, _)| symbol >= MAX_TABLE_SYMBOLS)
+        {
+            return Err(CompressorError::InvalidData(format!(
+                "huffman symbol {symbol}, a table decodes less than {MAX_TABLE_SYMBOLS} symbols"
+            )));
+        }
+
+        let max_length = codes
+            .iter()
+            .map(|&(_, _, length)| length)
+            .max()
+            .unwrap_or(1);
+        if max_length > MAX_TABLE_CODE_LENGTH as u32 {
+            return Err(CompressorError::InvalidData(format!(
+                "huffman code of {max_length} bits, longer than {MAX_TABLE_CODE_LENGTH} bits"
+            )));
+        }
+
+        let bits = max_length.min(TABLE_BITS);
+        let mut entries = vec![NO_CODE; 1 << bits];
+        fill_table(&mut entries, 0, bits, &codes)?;
+
+        Ok(TableDecoder { bits, entries })
+    }
+
+    /// read the bits of a code
+    pub fn decode(&self, reader: &mut MsbBitReader) -> Result<usize> {
+        let mut offset = 0;
+        let mut bits = self.bits;
+
+        loop {
+            let entry = self.entries[offset + reader.peek_bits(bits) as usize];
+
+            if entry & LINK == 0 {
+                if entry == NO_CODE {
+                    return Err(CompressorError::InvalidData(String::from(
+                        "bits not matching any huffman code",
+                    )));
+                }
+
+                reader.consume(entry & BITS_MASK)?;
+                return Ok((entry >> 6) as usize);
+            }
+
+            reader.consume(bits)?;
+            offset = ((entry & !LINK) >> 6) as usize;
+            bits = entry & BITS_MASK;
+        }
+    }
+}
+
+/// fill the table of `bits` bits starting at `offset` with codes given as (symbol, bits of the code
+/// left, number of bits left), adding the tables of the longer codes after the others
+fn fill_table(
+    entries: &mut Vec<u32>,
+    offset: usize,
+    bits: u32,
+    codes: &[(usize, u32, u32)],
+) -> Result<()> {
+    let prefix_error =
+        || CompressorError::InvalidData(String::from("huffman code prefix of another one"));
+
+    // the codes longer than the table, by their first bits
+    let mut longer: BTreeMap<usize, Vec<(usize, u32, u32)>> = BTreeMap::new();
+
+    for &(symbol, code, length) in codes {
+        if length > bits {
+            let rest = length - bits;
+            let index = (code >> rest) as usize;
+            longer
+                .entry(index)
+                .or_default()
+                .push((symbol, code & ((1 << rest) - 1), rest));
+            continue;
+        }
+
+        // every index starting with the code
+        let first = offset + (code << (bits - length)) as usize;
+        for entry in &mut entries[first..first + (1 << (bits - length))] {
+            if *entry != NO_CODE {
+                return Err(prefix_error());
+            }
+            *entry = (symbol as u32) << 6 | length;
+        }
+    }
+
+    for (index, codes) in longer {
+        if entries[offset + index] != NO_CODE {
+            return Err(prefix_error());
+        }
+
+        let table_bits = codes
+            .iter()
+            .map(|&(_, _, length)| length)
+            .max()
+            .unwrap_or(1)
+            .min(TABLE_BITS);
+        let table_offset = entries.len();
+        if table_offset >= MAX_TABLE_SYMBOLS {
+            return Err(CompressorError::InvalidData(String::from(
+                "too many huffman codes longer than the first table",
+            )));
+        }
+        entries[offset + index] = LINK | (table_offset as u32) << 6 | table_bits;
+        entries.resize(table_offset + (1 << table_bits), NO_CODE);
+
+        fill_table(entries, table_offset, table_bits, &codes)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::compressed_buffer::CompressedBuffer;
+
+    fn kraft_sum(lengths: &[u8]) -> f64 {
+        lengths
+            .iter()
+            .filter(|&&length| length > 0)
+            .map(|&length| 0.5f64.powi(length as i32))
+            .sum()
+    }
+
+    #[test]
+    fn code_lengths_optimal() {
+        assert!(code_lengths(&[], 15).is_empty());
+        assert_eq!(vec![0, 0], code_lengths(&[0, 0], 15));
+        assert_eq!(vec![0, 1, 0], code_lengths(&[0, 7, 0], 15));
+        assert_eq!(vec![1, 1], code_lengths(&[3, 1], 15));
+
+        let lengths = code_lengths(&[5, 9, 12, 13, 16, 45], 15);
+        assert_eq!(vec![4, 4, 3, 3, 3, 1], lengths);
+        assert_eq!(1.0, kraft_sum(&lengths));
+    }
+
+    #[test]
+    fn code_lengths_limited() {
+        // fibonacci frequencies give the deepest trees
+        let mut frequencies = vec![1, 1];
+        for i in 2..30 {
+            frequencies.push(frequencies[i - 1] + frequencies[i - 2]);
+        }
+        assert_eq!(29, *code_lengths(&frequencies, 31).iter().max().unwrap());
+
+        for max_length in [5, 7, 15] {
+            let lengths = code_lengths(&frequencies, max_length);
+            assert_eq!(max_length, *lengths.iter().max().unwrap());
+            assert_eq!(1.0, kraft_sum(&lengths));
+
+            // more frequent symbols never get longer codes
+            assert!(lengths.windows(2).all(|pair| pair[0] >= pair[1]));
+        }
+
+        assert_eq!(vec![3, 3, 3, 3, 1], code_lengths(&[1, 1, 2, 4, 8], 3));
+        assert_eq!(vec![2, 2, 2, 2], code_lengths(&[1, 1, 2, 4], 2));
+    }
+
+    #[test]
+    fn code_lengths_limited_optimal() {
+        // cost of the best lengths of at most `max_length` bits, trying every one
+        fn best_cost(frequencies: &[usize], max_length: u8, lengths: &mut Vec<u8>) -> usize {
+            if lengths.len() == frequencies.len() {
+                return if kraft_sum(lengths) <= 1.0 {
+                    let cost = frequencies.iter().zip(lengths.iter());
+                    cost.map(|(&frequency, &length)| frequency * length as usize)
+                        .sum()
+                } else {
+                    usize::MAX
+                };
+            }
+
+            (1..=max_length)
+                .map(|length| {
+                    lengths.push(length);
+                    let cost = best_cost(frequencies, max_length, lengths);
+                    lengths.pop();
+                    cost
+                })
+                .min()
+                .unwrap()
+        }
+
+        let frequencies = [1, 2, 3, 5, 8, 13, 40];
+        for max_length in 3..=6 {
+            let lengths = code_lengths(&frequencies, max_length);
+            assert!(*lengths.iter().max().unwrap() <= max_length);
+
+            let cost: usize = frequencies
+                .iter()
+                .zip(lengths.iter())
+                .map(|(&frequency, &length)| frequency * length as usize)
+                .sum();
+            assert_eq!(best_cost(&frequencies, max_length, &mut Vec::new()), cost);
+        }
+    }
+
+    #[test]
+    fn canonical_codes_round_trip() {
+        let lengths = [2, 1, 3, 3, 0];
+        assert_eq!(vec![0b10, 0b0, 0b110, 0b111, 0], canonical_codes(&lengths));
+
+        let frequencies: Vec<usize> = (0..300).map(|i| (i * 7919) % 1000).collect();
+        let lengths = code_lengths(&frequencies, 12);
+        let codes = canonical_codes(&lengths);
+        let decoder = CanonicalDecoder::new(&lengths).unwrap();
+
+        for symbol in (0..300).filter(|&symbol| frequencies[symbol] > 0) {
+            let length = lengths[symbol] as u32;
+            let mut bits = (0..length).rev().map(|i| codes[symbol] >> i & 1);
+            assert_eq!(symbol, decoder.decode(|| Ok(bits.next().unwrap())).unwrap());
+        }
+    }
+
+    #[test]
+    fn table_decoder_same_symbols() {
+        // fibonacci frequencies give codes of up to 29 bits, followed by 270 short codes
+        let mut frequencies = vec![1, 1];
+        for i in 2..30 {
+            frequencies.push(frequencies[i - 1] + frequencies[i - 2]);
+        }
+        frequencies.extend((0..270).map(|i| 1000 + i));
+
+        for max_length in [9, 15, 31] {
+            let lengths = code_lengths(&frequencies, max_length);
+            let codes = canonical_codes(&lengths);
+
+            let mut buffer = CompressedBuffer::new();
+            let symbols: Vec<usize> = (0..frequencies.len()).chain((0..30).rev()).collect();
+            for &symbol in &symbols {
+                buffer.push_bits(codes[symbol] as usize, lengths[symbol] as u32);
+            }
+            let bytes = buffer.get_buffer();
+
+            let decoder = TableDecoder::new(&lengths).unwrap();
+            let mut reader = MsbBitReader::new(&bytes);
+            for &symbol in &symbols {
+                assert_eq!(symbol, decoder.decode(&mut reader).unwrap());
+            }
+            reader.align_to_byte();
+            assert_eq!(bytes.len(), reader.bytes_read());
+        }
+    }
+
+    #[test]
+    fn table_decoder_any_codes() {
+        // A: 1, B: 01, C: 0011, D: 000 as a tree could give, 0010 is not used
+        let codes = [(0, 0b1, 1), (1, 0b01, 2), (2, 0b0011, 4), (3, 0b000, 3)];
+        let decoder = TableDecoder::from_codes(&codes).unwrap();
+
+        let mut reader = MsbBitReader::new(&[0b0000_1001, 0b1101_0010]);
+        for symbol in [3, 1, 2, 0, 1] {
+            assert_eq!(symbol, decoder.decode(&mut reader).unwrap());
+        }
+        assert!(matches!(
+            decoder.decode(&mut reader),
+            Err(CompressorError::InvalidData(_))
+        ));
+
+        let mut reader = MsbBitReader::new(&[0b0011_0000]);
+        reader.consume(6).unwrap();
+        assert!(matches!(
+            decoder.decode(&mut reader),
+            Err(CompressorError::TruncatedInput)
+        ));
+
+        // 01 is the start of 011
+        assert!(matches!(
+            TableDecoder::from_codes(&[(0, 0b1, 1), (1, 0b01, 2), (2, 0b011, 3)]),
+            Err(CompressorError::InvalidData(_))
+        ));
+        assert!(matches!(
+            TableDecoder::new(&[1, 1, 1]),
+            Err(CompressorError::InvalidData(_))
+        ));
+
+        // the last symbol an entry can hold, and the first one past it
+        let codes = [(MAX_TABLE_SYMBOLS - 1, 0b1, 1), (1, 0b0, 1)];
+        let decoder = TableDecoder::from_codes(&codes).unwrap();
+        let mut reader = MsbBitReader::new(&[0b1000_0000]);
+        assert_eq!(MAX_TABLE_SYMBOLS - 1, decoder.decode(&mut reader).unwrap());
+        assert!(matches!(
+            TableDecoder::from_codes(&[(MAX_TABLE_SYMBOLS, 0b1, 1), (1, 0b0, 1)]),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn canonical_decoder_invalid_lengths() {
+        assert!(matches!(
+            CanonicalDecoder::new(&[1, 1, 1]),
+            Err(CompressorError::InvalidData(_))
+        ));
+
+        // the code 1 is not used
+        let decoder = CanonicalDecoder::new(&[1]).unwrap();
+        assert!(matches!(
+            decoder.decode(|| Ok(1)),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+}
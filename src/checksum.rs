@@ -0,0 +1,167 @@
+// Cyclic redundancy check used to detect corrupted data
+
+/// Reversed representation of the CRC-32 (IEEE 802.3) polynomial
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// precompute the remainder of every possible byte
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// Compute the CRC-32 of given bytes (same as the one used by zip, gzip and png)
+///
+/// based on the following: <https://en.wikipedia.org/wiki/Cyclic_redundancy_check>
+pub fn crc32(bytes: &[u8]) -> u32 {
+    crc32_continue(0, bytes)
+}
+
+/// Continue the CRC-32 of the previous bytes with the following ones, for data read in parts
+///
+/// `crc32_continue(crc32(a), b)` is the CRC-32 of `a` followed by `b`
+pub fn crc32_continue(checksum: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !checksum;
+
+    for &byte in bytes {
+        let index = (crc ^ byte as u32) & 0xFF;
+        crc = (crc >> 8) ^ CRC32_TABLE[index as usize];
+    }
+
+    !crc
+}
+
+/// CRC-32 polynomial, with the first bit of the data as its highest term
+const CRC32_MSB_POLYNOMIAL: u32 = 0x04C1_1DB7;
+
+const CRC32_MSB_TABLE: [u32; 256] = crc32_msb_table();
+
+/// same as [`crc32_table`], the bits of every byte being taken from the most significant one
+const fn crc32_msb_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ CRC32_MSB_POLYNOMIAL
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// Compute the CRC-32 of given bytes as bzip2 does, from the most significant bit of each byte
+pub fn crc32_bzip2(bytes: &[u8]) -> u32 {
+    crc32_bzip2_continue(0, bytes)
+}
+
+/// Continue the bzip2 CRC-32 of the previous bytes with the following ones, as [`crc32_continue`] does
+pub fn crc32_bzip2_continue(checksum: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !checksum;
+
+    for &byte in bytes {
+        let index = (crc >> 24) ^ byte as u32;
+        crc = (crc << 8) ^ CRC32_MSB_TABLE[index as usize];
+    }
+
+    !crc
+}
+
+/// largest prime smaller than 2^16
+const ADLER32_MODULO: u32 = 65521;
+
+/// Compute the Adler-32 of given bytes (the checksum of zlib)
+///
+/// based on the following: <https://www.rfc-editor.org/rfc/rfc1950#section-9>
+pub fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1, 0);
+
+    // the sums cannot overflow before 5552 bytes
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= ADLER32_MODULO;
+        b %= ADLER32_MODULO;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        let text: Vec<u8> = "123456789".bytes().collect();
+        assert_eq!(0xCBF4_3926, crc32(&text));
+
+        assert_eq!(0, crc32(&[]));
+
+        let (start, end) = text.split_at(4);
+        assert_eq!(0xCBF4_3926, crc32_continue(crc32(start), end));
+    }
+
+    #[test]
+    fn crc32_bzip2_check_value() {
+        let text: Vec<u8> = "123456789".bytes().collect();
+        assert_eq!(0xFC89_1918, crc32_bzip2(&text));
+
+        assert_eq!(0, crc32_bzip2(&[]));
+
+        let (start, end) = text.split_at(4);
+        assert_eq!(0xFC89_1918, crc32_bzip2_continue(crc32_bzip2(start), end));
+    }
+
+    #[test]
+    fn adler32_check_value() {
+        let text: Vec<u8> = "Wikipedia".bytes().collect();
+        assert_eq!(0x11E6_0398, adler32(&text));
+
+        assert_eq!(1, adler32(&[]));
+        assert_eq!(0xB623_EB2B, adler32(&[0xFF; 10_000]));
+    }
+
+    #[test]
+    fn crc32_detects_flipped_bit() {
+        let mut text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
+        let checksum = crc32(&text);
+
+        text[4] ^= 0b0000_0100;
+        assert_ne!(checksum, crc32(&text));
+    }
+}
@@ -0,0 +1,1587 @@
+use std::sync::Arc;
+
+use crate::{
+    algorithms::{
+        adaptive_huffman::AdaptiveHuffman,
+        ans_encoder::AnsEncoder,
+        arithmetic_encoder::ArithmeticEncoder,
+        burrows_wheeler::BurrowsWheeler,
+        bzip2::Bzip2,
+        context_mixing::ContextMixing,
+        deflate::Deflate,
+        dynamic_markov::DynamicMarkov,
+        gzip::Gzip,
+        huffman_tree::{self, HuffmanTree},
+        lzss::LZSS,
+        lzw_encoder::LZWEncoder::{self, DictionaryFull},
+        move_to_front::MoveToFront::{self, Variant},
+        ppm::Ppm,
+        run_length_encoding::RLE,
+        unix_compress::UnixCompress,
+        zero_run_length::ZeroRunLength,
+        zlib::Zlib,
+    },
+    container::CHAIN_SEPARATOR,
+    error::{CompressorError, Result},
+    utils::{check_input_size, parse_size},
+    varsize::{encode_varsize, get_first_decoded},
+};
+
+/// characters a codec name cannot have: they separate the algorithms of the header, the options
+/// from the name and the stage from the entropy coder
+const RESERVED_CHARS: [char; 3] = [CHAIN_SEPARATOR, ':', '+'];
+
+/// An algorithm that can be part of a compression chain.
+///
+/// `encode` must store everything `decode` needs to restore the input
+/// (frequencies, trees, indexes, ...) in its own output.
+pub trait Codec: Send + Sync {
+    /// name recorded in the header of the compressed files
+    fn name(&self) -> &str;
+
+    /// other names the codec can be selected with
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>>;
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>>;
+
+    /// same codec configured with `key=value` options (`--algo=name:key=value,...`)
+    ///
+    /// the options are only given when encoding, `decode` must read them from the encoded data
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let (key, _) = options.first().copied().unwrap_or_default();
+
+        Err(CompressorError::InvalidArgument(format!(
+            "unknown option '{key}' for '{}'",
+            self.name()
+        )))
+    }
+
+    /// the codec as the first part of a `stage+coder` codec, when it can output integer symbols
+    fn as_symbol_stage(&self) -> Option<&dyn SymbolStage> {
+        None
+    }
+
+    /// the codec as the last part of a `stage+coder` codec, when it can entropy code integer symbols
+    fn as_symbol_coder(&self) -> Option<&dyn SymbolCoder> {
+        None
+    }
+}
+
+/// A codec whose output is made of integer symbols (codes, lengths, offsets, ...), which a
+/// [`SymbolCoder`] can entropy code directly instead of the bytes they are written with.
+pub trait SymbolStage: Send + Sync {
+    /// @**returns** (Vec\<u8\>, Vec\<u32\>) => (what `decode_symbols` needs besides the symbols, the symbols)
+    fn encode_symbols(&self, input: &[u8]) -> Result<(Vec<u8>, Vec<u32>)>;
+
+    fn decode_symbols(&self, side: &[u8], symbols: &[u32]) -> Result<Vec<u8>>;
+}
+
+/// A codec that can entropy code integer symbols wider than bytes.
+pub trait SymbolCoder: Send + Sync {
+    fn encode_symbols(&self, symbols: &[u32]) -> Result<Vec<u8>>;
+
+    fn decode_symbols(&self, input: &[u8]) -> Result<Vec<u32>>;
+}
+
+/// A [`SymbolStage`] handing its symbols to a [`SymbolCoder`], selected as `stage+coder`
+/// (`--algo=lzw+huff`), each of them with its own options.
+///
+/// ```text
+/// [varsize size of the side bytes][side bytes of the stage][symbols encoded by the coder]
+/// ```
+struct SymbolPipeline {
+    /// `stage+coder`, recorded in the header of the compressed files
+    name: String,
+    stage: Arc<dyn Codec>,
+    coder: Arc<dyn Codec>,
+}
+
+impl SymbolPipeline {
+    fn new(stage: Arc<dyn Codec>, coder: Arc<dyn Codec>) -> Result<Self> {
+        if stage.as_symbol_stage().is_none() {
+            return Err(CompressorError::InvalidArgument(format!(
+                "'{}' does not output symbols for '{}'",
+                stage.name(),
+                coder.name()
+            )));
+        }
+        if coder.as_symbol_coder().is_none() {
+            return Err(CompressorError::InvalidArgument(format!(
+                "'{}' cannot code the symbols of '{}'",
+                coder.name(),
+                stage.name()
+            )));
+        }
+
+        Ok(SymbolPipeline {
+            name: format!("{}+{}", stage.name(), coder.name()),
+            stage,
+            coder,
+        })
+    }
+
+    fn stage(&self) -> &dyn SymbolStage {
+        self.stage.as_symbol_stage().expect("checked by new")
+    }
+
+    fn coder(&self) -> &dyn SymbolCoder {
+        self.coder.as_symbol_coder().expect("checked by new")
+    }
+}
+
+impl Codec for SymbolPipeline {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        let (side, symbols) = self.stage().encode_symbols(input)?;
+
+        let mut encoded = encode_varsize(side.len());
+        encoded.extend_from_slice(&side);
+        encoded.extend_from_slice(&self.coder().encode_symbols(&symbols)?);
+
+        Ok(encoded)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let (side_size, side_start) = get_first_decoded(input)?;
+        let side_end = side_start
+            .checked_add(side_size)
+            .filter(|&end| end <= input.len())
+            .ok_or(CompressorError::TruncatedInput)?;
+
+        let symbols = self.coder().decode_symbols(&input[side_end..])?;
+
+        self.stage()
+            .decode_symbols(&input[side_start..side_end], &symbols)
+    }
+}
+
+/// Set of codecs that can be selected by name or alias.
+///
+/// The default registry contains every algorithm of this crate, other codecs can be added with [`CodecRegistry::register`].
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::{
+///     codec::{Codec, CodecRegistry},
+///     error::Result,
+/// };
+///
+/// struct Reverse;
+///
+/// impl Codec for Reverse {
+///     fn name(&self) -> &str {
+///         "rev"
+///     }
+///
+///     fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+///         Ok(input.iter().rev().copied().collect())
+///     }
+///
+///     fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+///         self.encode(input)
+///     }
+/// }
+///
+/// let mut registry = CodecRegistry::default();
+/// registry.register(Reverse).unwrap();
+///
+/// let codec = registry.get("rev").unwrap();
+/// assert_eq!(vec![3, 2, 1], codec.encode(&[1, 2, 3]).unwrap());
+/// ```
+pub struct CodecRegistry {
+    codecs: Vec<Arc<dyn Codec>>,
+}
+
+impl CodecRegistry {
+    /// create a registry without any codec
+    pub fn new() -> Self {
+        CodecRegistry { codecs: Vec::new() }
+    }
+
+    /// add a codec to the registry, its name and aliases must not be used by another codec
+    ///
+    /// they must not be empty nor have any of the characters `,`, `:` and `+`
+    pub fn register(&mut self, codec: impl Codec + 'static) -> Result<()> {
+        let names = std::iter::once(codec.name()).chain(codec.aliases().iter().copied());
+
+        for name in names {
+            if name.is_empty() || name.contains(RESERVED_CHARS) {
+                return Err(CompressorError::InvalidArgument(format!(
+                    "codec name '{name}' should not be empty nor have any of {RESERVED_CHARS:?}"
+                )));
+            }
+            if self.get(name).is_ok() {
+                return Err(CompressorError::AlgorithmAlreadyRegistered(String::from(
+                    name,
+                )));
+            }
+        }
+
+        self.codecs.push(Arc::new(codec));
+
+        Ok(())
+    }
+
+    /// find a codec by its name or one of its aliases, [`CodecRegistry::get_shared`] also takes options
+    pub fn get(&self, algo: &str) -> Result<&dyn Codec> {
+        self.find(algo).map(|codec| codec.as_ref())
+    }
+
+    /// same as [`CodecRegistry::get`], for the readers and writers that outlive the registry
+    ///
+    /// the algorithm can be followed by options: `name:key=value,key=value`, and a codec outputting
+    /// symbols can hand them to an entropy coder: `stage+coder` (see [`SymbolStage`])
+    ///
+    /// ## Example:
+    ///
+    /// ```
+    /// use simple_file_compressor::codec::CodecRegistry;
+    ///
+    /// let registry = CodecRegistry::default();
+    /// let text = b"TOBEORNOTTOBEORTOBEORNOT".repeat(10);
+    ///
+    /// for algo in ["dmc:memory=1M", "lzw+huff", "lzss:window=4K+aarith"] {
+    ///     let codec = registry.get_shared(algo).unwrap();
+    ///     let encoded = codec.encode(&text).unwrap();
+    ///     assert_eq!(text, codec.decode(&encoded).unwrap());
+    /// }
+    ///
+    /// // the options and the pipelines are only known from there
+    /// assert!(registry.get("dmc:memory=1M").is_err());
+    /// assert!(registry.get("lzw+huff").is_err());
+    /// ```
+    pub fn get_shared(&self, algo: &str) -> Result<Arc<dyn Codec>> {
+        if let Some((stage, coder)) = algo.split_once('+') {
+            let pipeline = SymbolPipeline::new(self.get_shared(stage)?, self.get_shared(coder)?)?;
+            return Ok(Arc::new(pipeline));
+        }
+
+        let Some((name, options)) = algo.split_once(':') else {
+            return self.find(algo).map(Arc::clone);
+        };
+
+        let options = options
+            .split(',')
+            .map(|option| {
+                option.split_once('=').ok_or_else(|| {
+                    CompressorError::InvalidArgument(format!(
+                        "option '{option}' of '{name}' should be key=value"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.find(name)?.with_options(&options)
+    }
+
+    fn find(&self, algo: &str) -> Result<&Arc<dyn Codec>> {
+        self.codecs
+            .iter()
+            .find(|codec| codec.name() == algo || codec.aliases().contains(&algo))
+            .ok_or_else(|| CompressorError::UnknownAlgorithm(String::from(algo)))
+    }
+
+    /// names of the registered codecs (in registration order)
+    pub fn names(&self) -> Vec<&str> {
+        self.codecs.iter().map(|codec| codec.name()).collect()
+    }
+}
+
+impl Default for CodecRegistry {
+    /// create a registry with every algorithm of this crate
+    fn default() -> Self {
+        let mut registry = CodecRegistry::new();
+
+        registry.codecs.push(Arc::new(HuffmanCodec::default()));
+        registry.codecs.push(Arc::new(AdaptiveHuffmanCodec));
+        registry.codecs.push(Arc::new(LZWCodec::default()));
+        registry.codecs.push(Arc::new(UnixCompressCodec::default()));
+        registry.codecs.push(Arc::new(LzssCodec::default()));
+        registry.codecs.push(Arc::new(DeflateCodec::default()));
+        registry.codecs.push(Arc::new(ZlibCodec::default()));
+        registry.codecs.push(Arc::new(GzipCodec::default()));
+        registry.codecs.push(Arc::new(Bzip2Codec::default()));
+        registry.codecs.push(Arc::new(BurrowsWheelerCodec));
+        registry
+            .codecs
+            .push(Arc::new(MoveToFrontCodec(Variant::MoveToFront)));
+        registry
+            .codecs
+            .push(Arc::new(MoveToFrontCodec(Variant::Mtf1)));
+        registry
+            .codecs
+            .push(Arc::new(MoveToFrontCodec(Variant::Mtf2)));
+        registry
+            .codecs
+            .push(Arc::new(MoveToFrontCodec(Variant::Sticky)));
+        registry.codecs.push(Arc::new(ZeroRunCodec));
+        registry.codecs.push(Arc::new(ArithmeticCodec));
+        registry.codecs.push(Arc::new(AdaptiveArithmeticCodec));
+        registry.codecs.push(Arc::new(RansCodec));
+        registry.codecs.push(Arc::new(TansCodec));
+        registry.codecs.push(Arc::new(DmcCodec::default()));
+        registry.codecs.push(Arc::new(PpmCodec::default()));
+        registry.codecs.push(Arc::new(CmCodec::default()));
+        registry.codecs.push(Arc::new(RLECodec));
+
+        registry
+    }
+}
+
+/// [`HuffmanTree`] with the code lengths stored before the encoded data
+///
+/// options: `max_length=<8-31>` longest code in bits (default: 31)
+pub struct HuffmanCodec {
+    max_code_length: u8,
+}
+
+impl Default for HuffmanCodec {
+    fn default() -> Self {
+        HuffmanCodec {
+            max_code_length: huffman_tree::MAX_CODE_LENGTH,
+        }
+    }
+}
+
+impl Codec for HuffmanCodec {
+    fn name(&self) -> &str {
+        "huff"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["huffman"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        HuffmanTree::encode_with_max_length(input, self.max_code_length)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        HuffmanTree::decode_with_metadatas(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut max_code_length = self.max_code_length;
+
+        for &(key, value) in options {
+            match key {
+                "max_length" => {
+                    max_code_length = value
+                        .parse()
+                        .ok()
+                        .filter(|length| {
+                            (huffman_tree::MIN_CODE_LENGTH..=huffman_tree::MAX_CODE_LENGTH)
+                                .contains(length)
+                        })
+                        .ok_or_else(|| {
+                            CompressorError::InvalidArgument(format!(
+                                "code length '{value}' is not between {} and {}",
+                                huffman_tree::MIN_CODE_LENGTH,
+                                huffman_tree::MAX_CODE_LENGTH
+                            ))
+                        })?
+                }
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'huff'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(HuffmanCodec { max_code_length }))
+    }
+
+    fn as_symbol_coder(&self) -> Option<&dyn SymbolCoder> {
+        Some(self)
+    }
+}
+
+/// canonical codes of the symbols found in the input, stored before the encoded data
+impl SymbolCoder for HuffmanCodec {
+    fn encode_symbols(&self, symbols: &[u32]) -> Result<Vec<u8>> {
+        HuffmanTree::encode_symbols(symbols, self.max_code_length)
+    }
+
+    fn decode_symbols(&self, input: &[u8]) -> Result<Vec<u32>> {
+        HuffmanTree::decode_symbols(input)
+    }
+}
+
+/// [`AdaptiveHuffman`] coding in one pass, only the number of bytes is stored before the encoded data
+pub struct AdaptiveHuffmanCodec;
+
+impl Codec for AdaptiveHuffmanCodec {
+    fn name(&self) -> &str {
+        "ahuff"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["adaptive-huffman"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        AdaptiveHuffman::encode(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        AdaptiveHuffman::decode(input)
+    }
+}
+
+/// [`LZWEncoder`] with the unique chars stored before the encoded data
+///
+/// options: `dict=<size>` largest number of codes of the dictionary (default: 64K),
+/// `full=<reset|freeze|adaptive>` what happens once the dictionary is full (default: adaptive),
+/// either of them writes bit-packed codes instead of varsize indexes
+#[derive(Default)]
+pub struct LZWCodec {
+    /// largest number of codes and policy of the bit-packed codes
+    packed: Option<(usize, DictionaryFull)>,
+}
+
+impl Codec for LZWCodec {
+    fn name(&self) -> &str {
+        "lzw"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["lempel-ziv-welch"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        match self.packed {
+            Some((max_dict_size, when_full)) => {
+                LZWEncoder::encode_packed(input, max_dict_size, when_full)
+            }
+            None => LZWEncoder::encode_with_metadatas(input),
+        }
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        LZWEncoder::decode_with_metadatas(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let (mut max_dict_size, mut when_full) = self
+            .packed
+            .unwrap_or((LZWEncoder::DEFAULT_DICT_SIZE, DictionaryFull::AdaptiveReset));
+
+        for &(key, value) in options {
+            match key {
+                "dict" => max_dict_size = parse_size(value)?,
+                "full" => {
+                    when_full = match value {
+                        "reset" => DictionaryFull::Reset,
+                        "freeze" => DictionaryFull::Freeze,
+                        "adaptive" => DictionaryFull::AdaptiveReset,
+                        _ => {
+                            return Err(CompressorError::InvalidArgument(format!(
+                                "invalid dictionary policy '{value}', expected reset, freeze or adaptive"
+                            )));
+                        }
+                    }
+                }
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'lzw'"
+                    )));
+                }
+            }
+        }
+
+        if !(LZWEncoder::MIN_DICT_SIZE..=LZWEncoder::MAX_DICT_SIZE).contains(&max_dict_size) {
+            return Err(CompressorError::InvalidArgument(format!(
+                "lzw dictionary of {max_dict_size} codes is not between {} and {} codes",
+                LZWEncoder::MIN_DICT_SIZE,
+                LZWEncoder::MAX_DICT_SIZE
+            )));
+        }
+
+        Ok(Arc::new(LZWCodec {
+            packed: Some((max_dict_size, when_full)),
+        }))
+    }
+
+    fn as_symbol_stage(&self) -> Option<&dyn SymbolStage> {
+        Some(self)
+    }
+}
+
+/// the codes of the bit-packed dictionary, the default one without options
+impl SymbolStage for LZWCodec {
+    fn encode_symbols(&self, input: &[u8]) -> Result<(Vec<u8>, Vec<u32>)> {
+        let (max_dict_size, when_full) = self
+            .packed
+            .unwrap_or((LZWEncoder::DEFAULT_DICT_SIZE, DictionaryFull::AdaptiveReset));
+
+        LZWEncoder::encode_symbols(input, max_dict_size, when_full)
+    }
+
+    fn decode_symbols(&self, side: &[u8], symbols: &[u32]) -> Result<Vec<u8>> {
+        LZWEncoder::decode_symbols(side, symbols)
+    }
+}
+
+/// [`UnixCompress`] LZW, the same as a `.Z` file of `compress`
+///
+/// options: `bits=<9-16>` largest width of the codes, the size of the dictionary (default: 16)
+pub struct UnixCompressCodec {
+    max_bits: u8,
+}
+
+impl Default for UnixCompressCodec {
+    fn default() -> Self {
+        UnixCompressCodec {
+            max_bits: UnixCompress::DEFAULT_MAX_BITS,
+        }
+    }
+}
+
+impl Codec for UnixCompressCodec {
+    fn name(&self) -> &str {
+        "compress"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["lzw-z", "unix-compress"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        UnixCompress::encode(input, self.max_bits)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        UnixCompress::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut codec = UnixCompressCodec { ..*self };
+
+        for &(key, value) in options {
+            match key {
+                "bits" => {
+                    codec.max_bits = value
+                        .parse()
+                        .ok()
+                        .filter(|bits| (9..=16).contains(bits))
+                        .ok_or_else(|| {
+                            CompressorError::InvalidArgument(format!(
+                                "code width '{value}' is not between 9 and 16"
+                            ))
+                        })?
+                }
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'compress'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(codec))
+    }
+}
+
+/// [`LZSS`] with the window size stored before the flags and tokens
+///
+/// options: `window=<size>` maximum distance of a repeat (default: 1M),
+/// `chain=<number>` previous positions compared at each position (default: 64)
+pub struct LzssCodec {
+    window_size: usize,
+    max_chain: usize,
+}
+
+impl Default for LzssCodec {
+    fn default() -> Self {
+        LzssCodec {
+            window_size: LZSS::DEFAULT_WINDOW,
+            max_chain: LZSS::DEFAULT_MAX_CHAIN,
+        }
+    }
+}
+
+impl Codec for LzssCodec {
+    fn name(&self) -> &str {
+        "lzss"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["lz77"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        LZSS::encode(input, self.window_size, self.max_chain)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        LZSS::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut codec = LzssCodec { ..*self };
+
+        for &(key, value) in options {
+            match key {
+                "window" => codec.window_size = parse_size(value)?,
+                "chain" => {
+                    codec.max_chain = value.parse().map_err(|_| {
+                        CompressorError::InvalidArgument(format!("invalid chain length '{value}'"))
+                    })?
+                }
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'lzss'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(codec))
+    }
+
+    fn as_symbol_stage(&self) -> Option<&dyn SymbolStage> {
+        Some(self)
+    }
+}
+
+/// literals, and the buckets of the lengths and offsets of the repeats
+impl SymbolStage for LzssCodec {
+    fn encode_symbols(&self, input: &[u8]) -> Result<(Vec<u8>, Vec<u32>)> {
+        LZSS::encode_symbols(input, self.window_size, self.max_chain)
+    }
+
+    fn decode_symbols(&self, side: &[u8], symbols: &[u32]) -> Result<Vec<u8>> {
+        LZSS::decode_symbols(side, symbols)
+    }
+}
+
+/// level of the `level=<0-9>` option of the DEFLATE based codecs
+fn deflate_level(name: &str, options: &[(&str, &str)]) -> Result<u8> {
+    let mut level = Deflate::DEFAULT_LEVEL;
+
+    for &(key, value) in options {
+        match key {
+            "level" => {
+                level = value
+                    .parse()
+                    .ok()
+                    .filter(|&level| level <= 9)
+                    .ok_or_else(|| {
+                        CompressorError::InvalidArgument(format!(
+                            "level '{value}' is not between 0 and 9"
+                        ))
+                    })?
+            }
+            _ => {
+                return Err(CompressorError::InvalidArgument(format!(
+                    "unknown option '{key}' for '{name}'"
+                )));
+            }
+        }
+    }
+
+    Ok(level)
+}
+
+/// [`Deflate`] raw stream, without header nor checksum
+///
+/// options: `level=<0-9>` how hard repeats are looked for, 0 storing the data (default: 6)
+pub struct DeflateCodec {
+    level: u8,
+}
+
+impl Default for DeflateCodec {
+    fn default() -> Self {
+        DeflateCodec {
+            level: Deflate::DEFAULT_LEVEL,
+        }
+    }
+}
+
+impl Codec for DeflateCodec {
+    fn name(&self) -> &str {
+        "deflate"
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        Deflate::encode(input, self.level)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Deflate::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        Ok(Arc::new(DeflateCodec {
+            level: deflate_level(self.name(), options)?,
+        }))
+    }
+}
+
+/// [`Zlib`] stream, DEFLATE with a 2 bytes header and an Adler-32
+///
+/// options: `level=<0-9>` (default: 6)
+pub struct ZlibCodec {
+    level: u8,
+}
+
+impl Default for ZlibCodec {
+    fn default() -> Self {
+        ZlibCodec {
+            level: Deflate::DEFAULT_LEVEL,
+        }
+    }
+}
+
+impl Codec for ZlibCodec {
+    fn name(&self) -> &str {
+        "zlib"
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        Zlib::encode(input, self.level)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Zlib::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        Ok(Arc::new(ZlibCodec {
+            level: deflate_level(self.name(), options)?,
+        }))
+    }
+}
+
+/// [`Gzip`] member, DEFLATE with a gzip header and a CRC-32, the same as a `.gz` file
+///
+/// options: `level=<0-9>` (default: 6)
+pub struct GzipCodec {
+    level: u8,
+}
+
+impl Default for GzipCodec {
+    fn default() -> Self {
+        GzipCodec {
+            level: Deflate::DEFAULT_LEVEL,
+        }
+    }
+}
+
+impl Codec for GzipCodec {
+    fn name(&self) -> &str {
+        "gzip"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["gz"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        Gzip::encode(input, self.level)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Gzip::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        Ok(Arc::new(GzipCodec {
+            level: deflate_level(self.name(), options)?,
+        }))
+    }
+}
+
+/// [`Bzip2`] stream, the same as a `.bz2` file
+///
+/// options: `level=<1-9>` size of the blocks, from 100k to 900k bytes (default: 9)
+pub struct Bzip2Codec {
+    level: u8,
+}
+
+impl Default for Bzip2Codec {
+    fn default() -> Self {
+        Bzip2Codec {
+            level: Bzip2::DEFAULT_LEVEL,
+        }
+    }
+}
+
+impl Codec for Bzip2Codec {
+    fn name(&self) -> &str {
+        "bzip2"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["bz2"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        Bzip2::encode(input, self.level)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Bzip2::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut codec = Bzip2Codec { ..*self };
+
+        for &(key, value) in options {
+            match key {
+                "level" => {
+                    codec.level = value
+                        .parse()
+                        .ok()
+                        .filter(|level| (1..=9).contains(level))
+                        .ok_or_else(|| {
+                            CompressorError::InvalidArgument(format!(
+                                "bzip2 level '{value}' is not between 1 and 9"
+                            ))
+                        })?
+                }
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'bzip2'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(codec))
+    }
+}
+
+/// [`BurrowsWheeler`] with the index of the original row stored before the transformed data
+pub struct BurrowsWheelerCodec;
+
+impl Codec for BurrowsWheelerCodec {
+    fn name(&self) -> &str {
+        "bwt"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["burrows-wheeler", "burrows-wheeler-transform"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        BurrowsWheeler::encode_with_metadata(input, true)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        BurrowsWheeler::decode_with_metadata(input)
+    }
+}
+
+/// [`MoveToFront`] transform, one codec for each variant
+pub struct MoveToFrontCodec(pub Variant);
+
+impl Codec for MoveToFrontCodec {
+    fn name(&self) -> &str {
+        match self.0 {
+            Variant::MoveToFront => "mtf",
+            Variant::Mtf1 => "mtf-1",
+            Variant::Mtf2 => "mtf-2",
+            Variant::Sticky => "sticky-mtf",
+        }
+    }
+
+    fn aliases(&self) -> &[&str] {
+        match self.0 {
+            Variant::MoveToFront => &["move-to-front"],
+            Variant::Mtf1 => &["mtf1"],
+            Variant::Mtf2 => &["mtf2"],
+            Variant::Sticky => &["sticky-move-to-front"],
+        }
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        MoveToFront::encode_variant(input, self.0)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        MoveToFront::decode_variant(input, self.0)
+    }
+}
+
+/// [`ZeroRunLength`] of the output of a move-to-front, as bytes or as symbols for `zrle+coder`
+pub struct ZeroRunCodec;
+
+impl Codec for ZeroRunCodec {
+    fn name(&self) -> &str {
+        "zrle"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["zero-run-length", "runa-runb"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        ZeroRunLength::encode(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        ZeroRunLength::decode(input)
+    }
+
+    fn as_symbol_stage(&self) -> Option<&dyn SymbolStage> {
+        Some(self)
+    }
+}
+
+impl SymbolStage for ZeroRunCodec {
+    fn encode_symbols(&self, input: &[u8]) -> Result<(Vec<u8>, Vec<u32>)> {
+        Ok((Vec::new(), ZeroRunLength::encode_symbols(input)))
+    }
+
+    fn decode_symbols(&self, side: &[u8], symbols: &[u32]) -> Result<Vec<u8>> {
+        if !side.is_empty() {
+            return Err(CompressorError::InvalidData(String::from(
+                "zero runs with side bytes",
+            )));
+        }
+
+        ZeroRunLength::decode_runs(symbols)
+    }
+}
+
+/// [`ArithmeticEncoder`] with the frequencies stored before the encoded data
+pub struct ArithmeticCodec;
+
+impl Codec for ArithmeticCodec {
+    fn name(&self) -> &str {
+        "arith"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["arithmetic"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        ArithmeticEncoder::encode_with_metadatas(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        ArithmeticEncoder::decode_with_metadatas(input)
+    }
+}
+
+/// [`ArithmeticEncoder`] in adaptive mode, only the number of symbols is stored before the encoded data
+pub struct AdaptiveArithmeticCodec;
+
+impl Codec for AdaptiveArithmeticCodec {
+    fn name(&self) -> &str {
+        "aarith"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["adaptive-arithmetic"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        ArithmeticEncoder::encode_adaptive(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        ArithmeticEncoder::decode_adaptive(input)
+    }
+
+    fn as_symbol_coder(&self) -> Option<&dyn SymbolCoder> {
+        Some(self)
+    }
+}
+
+/// the same model over the symbols from 0 to the largest one
+impl SymbolCoder for AdaptiveArithmeticCodec {
+    fn encode_symbols(&self, symbols: &[u32]) -> Result<Vec<u8>> {
+        ArithmeticEncoder::encode_adaptive_symbols(symbols)
+    }
+
+    fn decode_symbols(&self, input: &[u8]) -> Result<Vec<u32>> {
+        ArithmeticEncoder::decode_adaptive_symbols(input)
+    }
+}
+
+/// [`AnsEncoder`] with range ANS, the normalized frequencies are stored before the encoded data
+pub struct RansCodec;
+
+impl Codec for RansCodec {
+    fn name(&self) -> &str {
+        "rans"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["range-ans"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        AnsEncoder::encode_rans(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        AnsEncoder::decode_rans(input)
+    }
+}
+
+/// [`AnsEncoder`] with table ANS, the normalized frequencies are stored before the encoded data
+pub struct TansCodec;
+
+impl Codec for TansCodec {
+    fn name(&self) -> &str {
+        "tans"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["table-ans"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        AnsEncoder::encode_tans(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        AnsEncoder::decode_tans(input)
+    }
+}
+
+/// [`DynamicMarkov`] with the number of states stored before the encoded data
+///
+/// options: `memory=<size>` memory used by the model before it is reset (default: 32M)
+pub struct DmcCodec {
+    max_states: usize,
+}
+
+impl Default for DmcCodec {
+    fn default() -> Self {
+        DmcCodec {
+            max_states: DynamicMarkov::max_states(DynamicMarkov::DEFAULT_MEMORY),
+        }
+    }
+}
+
+impl Codec for DmcCodec {
+    fn name(&self) -> &str {
+        "dmc"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["dynamic-markov"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        DynamicMarkov::encode(input, self.max_states)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        DynamicMarkov::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut codec = DmcCodec { ..*self };
+
+        for &(key, value) in options {
+            match key {
+                "memory" => codec.max_states = DynamicMarkov::max_states(parse_size(value)?),
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'dmc'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(codec))
+    }
+}
+
+/// [`Ppm`] with the maximum order and memory stored before the encoded data
+///
+/// options: `order=<0-16>` longest context in bytes (default: [`Ppm::DEFAULT_ORDER`]),
+/// `memory=<size>` memory used by the model before it is reset (default: 64M)
+pub struct PpmCodec {
+    max_order: usize,
+    memory: usize,
+}
+
+impl Default for PpmCodec {
+    fn default() -> Self {
+        PpmCodec {
+            max_order: Ppm::DEFAULT_ORDER,
+            memory: Ppm::DEFAULT_MEMORY,
+        }
+    }
+}
+
+impl Codec for PpmCodec {
+    fn name(&self) -> &str {
+        "ppm"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["prediction-by-partial-matching"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        Ppm::encode(input, self.max_order, self.memory)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Ppm::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut codec = PpmCodec { ..*self };
+
+        for &(key, value) in options {
+            match key {
+                "order" => {
+                    codec.max_order = value
+                        .parse()
+                        .ok()
+                        .filter(|&order| order <= Ppm::MAX_ORDER)
+                        .ok_or_else(|| {
+                            CompressorError::InvalidArgument(format!(
+                                "order '{value}' is not between 0 and {}",
+                                Ppm::MAX_ORDER
+                            ))
+                        })?
+                }
+                "memory" => codec.memory = parse_size(value)?,
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'ppm'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(codec))
+    }
+}
+
+/// [`ContextMixing`] with the memory level stored before the encoded data
+///
+/// options: `level=<1-9>` memory level, the models take 2^(21 + level) bytes (default: 5, 64M)
+pub struct CmCodec {
+    level: u8,
+}
+
+impl Default for CmCodec {
+    fn default() -> Self {
+        CmCodec {
+            level: ContextMixing::DEFAULT_LEVEL,
+        }
+    }
+}
+
+impl Codec for CmCodec {
+    fn name(&self) -> &str {
+        "cm"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["context-mixing"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        ContextMixing::encode(input, self.level)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        ContextMixing::decode(input)
+    }
+
+    fn with_options(&self, options: &[(&str, &str)]) -> Result<Arc<dyn Codec>> {
+        let mut level = self.level;
+
+        for &(key, value) in options {
+            match key {
+                "level" => {
+                    level = value
+                        .parse()
+                        .ok()
+                        .filter(|level| {
+                            (ContextMixing::MIN_LEVEL..=ContextMixing::MAX_LEVEL).contains(level)
+                        })
+                        .ok_or_else(|| {
+                            CompressorError::InvalidArgument(format!(
+                                "memory level '{value}' is not between {} and {}",
+                                ContextMixing::MIN_LEVEL,
+                                ContextMixing::MAX_LEVEL
+                            ))
+                        })?
+                }
+                _ => {
+                    return Err(CompressorError::InvalidArgument(format!(
+                        "unknown option '{key}' for 'cm'"
+                    )));
+                }
+            }
+        }
+
+        Ok(Arc::new(CmCodec { level }))
+    }
+}
+
+/// [`RLE`] with varsize counts
+pub struct RLECodec;
+
+impl Codec for RLECodec {
+    fn name(&self) -> &str {
+        "rle"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["run-length-encoding"]
+    }
+
+    fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        check_input_size(input)?;
+        RLE::encode(input)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        RLE::decode(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::pseudo_random;
+    use crate::utils::MAX_BLOCK_SIZE;
+
+    struct Identity(&'static str, &'static [&'static str]);
+
+    impl Codec for Identity {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn aliases(&self) -> &[&str] {
+            self.1
+        }
+
+        fn encode(&self, input: &[u8]) -> Result<Vec<u8>> {
+            Ok(input.to_vec())
+        }
+
+        fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+            Ok(input.to_vec())
+        }
+    }
+
+    #[test]
+    fn default_codecs_round_trip() {
+        let registry = CodecRegistry::default();
+        let text: Vec<u8> = "AAABBCCDACCAA\0\0\0\u{FF}AB".bytes().collect();
+
+        for name in registry.names() {
+            let codec = registry.get(name).unwrap();
+            let encoded = codec.encode(&text).unwrap();
+
+            assert_eq!(text, codec.decode(&encoded).unwrap(), "codec {name}");
+        }
+    }
+
+    #[test]
+    fn default_codecs_reject_oversized_input() {
+        let registry = CodecRegistry::default();
+        // the pages are never touched, only the length is checked
+        let oversized = vec![0; MAX_BLOCK_SIZE + 1];
+
+        for name in registry
+            .names()
+            .into_iter()
+            .chain(["lzw+huff", "zrle+aarith"])
+        {
+            assert!(
+                matches!(
+                    registry.get_shared(name).unwrap().encode(&oversized),
+                    Err(CompressorError::InvalidArgument(_))
+                ),
+                "codec {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn get_by_alias() {
+        let registry = CodecRegistry::default();
+
+        assert_eq!("huff", registry.get("huffman").unwrap().name());
+        assert_eq!(
+            "bwt",
+            registry.get("burrows-wheeler-transform").unwrap().name()
+        );
+        assert!(matches!(
+            registry.get("zip"),
+            Err(CompressorError::UnknownAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn codec_options() {
+        let registry = CodecRegistry::default();
+
+        assert!(matches!(
+            registry.get_shared("huff:level=9"),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            registry.get_shared("dmc:memory"),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            registry.get_shared("dmc:size=1M"),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+
+        let text: Vec<u8> = "AAABBCCDACCAA".repeat(100).bytes().collect();
+        for algo in [
+            "dmc:memory=64K",
+            "ppm:order=2,memory=64K",
+            "prediction-by-partial-matching:order=16",
+            "cm:level=1",
+            "context-mixing:level=2",
+            "huff:max_length=8",
+            "huffman:max_length=15",
+            "lz77:window=4K,chain=8",
+            "lzw:dict=512,full=reset",
+            "lzw:full=freeze",
+            "lempel-ziv-welch:dict=64K",
+            "compress:bits=9",
+            "deflate:level=0",
+            "zlib:level=1",
+            "gz:level=9",
+            "bz2:level=1",
+        ] {
+            let codec = registry.get_shared(algo).unwrap();
+            assert_eq!(text, codec.decode(&codec.encode(&text).unwrap()).unwrap());
+        }
+        for algo in [
+            "lzss:chain=many",
+            "huff:max_length=7",
+            "huff:max_length=32",
+            "gzip:level=10",
+            "deflate:window=4K",
+            "lzw-z:bits=17",
+            "lzw:dict=256",
+            "lzw:full=never",
+            "bzip2:level=0",
+            "ppm:order=17",
+            "ppm:memory=0",
+            "cm:level=0",
+            "cm:level=10",
+            "cm:memory=64M",
+        ] {
+            assert!(matches!(
+                registry.get_shared(algo),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+
+        assert!(matches!(
+            registry.get_shared("zip:level=9"),
+            Err(CompressorError::UnknownAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn zero_runs_after_move_to_front() {
+        let registry = CodecRegistry::default();
+
+        let text: Vec<u8> = "the quick brown fox jumps over the lazy dog\n"
+            .repeat(300)
+            .bytes()
+            .chain((0..5000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 64))
+            .collect();
+        let bwt = registry.get("bwt").unwrap().encode(&text).unwrap();
+
+        for (mtf, alias) in [
+            ("mtf", "move-to-front"),
+            ("mtf-1", "mtf1"),
+            ("mtf-2", "mtf2"),
+            ("sticky-mtf", "sticky-move-to-front"),
+        ] {
+            let codec = registry.get(alias).unwrap();
+            assert_eq!(mtf, codec.name());
+
+            let transformed = codec.encode(&bwt).unwrap();
+            assert_eq!(bwt, codec.decode(&transformed).unwrap(), "codec {mtf}");
+
+            for algo in ["zrle", "zrle+huff", "runa-runb+aarith"] {
+                let zero_runs = registry.get_shared(algo).unwrap();
+                let encoded = zero_runs.encode(&transformed).unwrap();
+                assert_eq!(transformed, zero_runs.decode(&encoded).unwrap());
+            }
+        }
+
+        // the runs of zeros take fewer bytes than with a run-length encoding of every byte
+        let mtf = registry.get("mtf").unwrap().encode(&bwt).unwrap();
+        let huff = registry.get("huff").unwrap();
+        let rle = registry.get("rle").unwrap();
+        let rle_huff = huff.encode(&rle.encode(&mtf).unwrap()).unwrap();
+        let zrle_huff = registry.get_shared("zrle+huff").unwrap();
+        assert!(zrle_huff.encode(&mtf).unwrap().len() < rle_huff.len());
+
+        assert!(matches!(
+            registry.get_shared("zrle").unwrap().decode(&[3, 0]),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn symbol_pipelines() {
+        let registry = CodecRegistry::default();
+
+        let mut text: Vec<u8> = "AAABBCCDACCAA\0\0\0\u{FF}AB".repeat(500).bytes().collect();
+        text.extend((0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 32));
+
+        for algo in [
+            "lzw+huff",
+            "lzw+aarith",
+            "lzss+huff",
+            "lz77+adaptive-arithmetic",
+            "lzw:dict=512,full=reset+huffman:max_length=12",
+            "lzss:window=4K+aarith",
+            "zrle+huff",
+            "zero-run-length+aarith",
+        ] {
+            let codec = registry.get_shared(algo).unwrap();
+            let encoded = codec.encode(&text).unwrap();
+            assert_eq!(text, codec.decode(&encoded).unwrap(), "codec {algo}");
+
+            // the name recorded in the header finds the same codec
+            let recorded = registry.get_shared(codec.name()).unwrap();
+            assert_eq!(text, recorded.decode(&encoded).unwrap(), "codec {algo}");
+
+            for empty in [&[][..], &[0]] {
+                let encoded = codec.encode(empty).unwrap();
+                assert_eq!(empty, codec.decode(&encoded).unwrap(), "codec {algo}");
+            }
+        }
+        assert_eq!(
+            "lzw+huff",
+            registry
+                .get_shared("lempel-ziv-welch+huffman")
+                .unwrap()
+                .name()
+        );
+
+        // the entropy coded symbols are smaller than the coded bytes of the stage
+        let words = [
+            "the ", "quick ", "brown ", "fox ", "jumps ", "over ", "lazy ", "dog\n",
+        ];
+        let words: Vec<u8> = pseudo_random(42)
+            .take(100_000)
+            .flat_map(|seed| words[(seed >> 16) as usize % words.len()].bytes())
+            .collect();
+        let lzw_huff = registry.get_shared("lzw+huff").unwrap();
+        let lzw = registry.get_shared("lzw").unwrap();
+        let huff = registry.get_shared("huff").unwrap();
+        let chained = huff.encode(&lzw.encode(&words).unwrap()).unwrap();
+        assert!(lzw_huff.encode(&words).unwrap().len() < chained.len());
+
+        for algo in [
+            "huff+lzw",
+            "lzw+rle",
+            "lzw+huff+huff",
+            "lzw+huff:max_length=7",
+        ] {
+            assert!(
+                matches!(
+                    registry.get_shared(algo),
+                    Err(CompressorError::InvalidArgument(_))
+                ),
+                "{algo}"
+            );
+        }
+        assert!(matches!(
+            registry.get_shared("lzw+zip"),
+            Err(CompressorError::UnknownAlgorithm(_))
+        ));
+
+        // a dictionary of 64K codes does not fit codes of 8 bits
+        let codec = registry.get_shared("lzw+huff:max_length=8").unwrap();
+        assert!(matches!(
+            codec.encode(&text),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+
+        let encoded = lzw_huff.encode(&text).unwrap();
+        for end in 0..100 {
+            assert!(lzw_huff.decode(&encoded[..end]).is_err());
+        }
+
+        // a run of about 2^31 symbols in the alphabet of the codes
+        assert!(matches!(
+            lzw_huff.decode(&[0x00, 0xFF, 0x01, 0x00, 0x87, 0xFF, 0xFF, 0xFF, 0x7E]),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn register_codec() {
+        let mut registry = CodecRegistry::new();
+        assert!(registry.get("id").is_err());
+
+        registry.register(Identity("id", &["identity"])).unwrap();
+        assert_eq!("id", registry.get("identity").unwrap().name());
+
+        // name or alias already taken
+        assert!(matches!(
+            registry.register(Identity("identity", &[])),
+            Err(CompressorError::AlgorithmAlreadyRegistered(_))
+        ));
+        assert!(matches!(
+            registry.register(Identity("other", &["id"])),
+            Err(CompressorError::AlgorithmAlreadyRegistered(_))
+        ));
+        assert_eq!(vec!["id"], registry.names());
+
+        // names read back as something else from a header or an --algo
+        static INVALID_NAMES: [&str; 4] = ["", "a,b", "lzw:dict=512", "lzw+huff"];
+        for i in 0..INVALID_NAMES.len() {
+            assert!(matches!(
+                registry.register(Identity(INVALID_NAMES[i], &[])),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+            assert!(matches!(
+                registry.register(Identity("other", &INVALID_NAMES[i..=i])),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+        assert_eq!(vec!["id"], registry.names());
+    }
+}
@@ -1,5 +1,6 @@
 use std::ops::Index;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Bit {
     ZERO,
@@ -57,12 +58,8 @@ impl CompressedBuffer {
             .last_mut()
             .expect("In src/compressed_buffer => push_bit: cannot access last byte");
 
-        match bit {
-            Bit::ONE => {
-                *last_byte = *last_byte | (1 << self.current_bit_index);
-            }
-            Bit::ZERO => { /*Val already at 0, no need to set*/ }
-        }
+        // ZERO is 0 and ONE is 1, the value is already at 0 for a ZERO
+        *last_byte |= (bit as u8) << self.current_bit_index;
 
         // DEBUG
         // println!("last_byte: {:#010b}", *last_byte);
@@ -74,14 +71,32 @@ impl CompressedBuffer {
         };
     }
 
-    pub fn push_byte(&mut self, byte: u8) {
-        self.buffer.push(byte);
-
-        self.current_bit_index = 7;
+    /// push the `count` lowest bits of `value`, the most significant one first
+    pub fn push_bits(&mut self, value: usize, count: u32) {
+        debug_assert!(
+            count as usize <= usize::BITS as usize && value.checked_shr(count).unwrap_or(0) == 0
+        );
+
+        for i in (0..count).rev() {
+            let bit = if value >> i & 1 == 0 {
+                Bit::ZERO
+            } else {
+                Bit::ONE
+            };
+            self.push_bit(bit);
+        }
     }
 
-    pub fn insert_byte(&mut self, index: usize, byte: u8) {
-        self.buffer.insert(index, byte);
+    /// take the whole bytes pushed so far, the byte being filled staying in the buffer
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        let whole = if self.current_bit_index == 7 {
+            self.buffer.len()
+        } else {
+            self.buffer.len() - 1
+        };
+
+        let filling = self.buffer.split_off(whole);
+        std::mem::replace(&mut self.buffer, filling)
     }
 
     pub fn get_current_bit_index(&self) -> u8 {
@@ -89,6 +104,12 @@ impl CompressedBuffer {
     }
 }
 
+impl Default for CompressedBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Index<usize> for CompressedBuffer {
     type Output = u8;
 
@@ -141,4 +162,24 @@ mod tests {
         assert_eq!(compressed_buffer[0], 0b0110_0100);
         assert_eq!(compressed_buffer[1], 0b1111_0100);
     }
+
+    #[test]
+    fn pushing_bits() {
+        let mut compressed_buffer = CompressedBuffer::new();
+
+        compressed_buffer.push_bits(0b1_0110_0101, 9);
+        compressed_buffer.push_bits(0, 0);
+        compressed_buffer.push_bits(0b11, 2);
+
+        assert_eq!(
+            vec![0b1011_0010, 0b1110_0000],
+            compressed_buffer.get_buffer()
+        );
+
+        // the byte being filled stays
+        assert_eq!(vec![0b1011_0010], compressed_buffer.take_bytes());
+        compressed_buffer.push_bits(0b10101, 5);
+        assert_eq!(vec![0b1111_0101], compressed_buffer.take_bytes());
+        assert!(compressed_buffer.get_buffer().is_empty());
+    }
 }
@@ -0,0 +1,447 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::{
+    error::{CompressorError, Result},
+    varsize::{encode_varsize, read_varsize},
+};
+
+/// Bytes every compressed file starts with
+pub const MAGIC: [u8; 4] = *b"SFCZ";
+
+/// Version of the container layout written by this build
+pub const FORMAT_VERSION: u8 = 4;
+
+/// Set when a checksum of the input of every algorithm follows the checksum of the original data
+pub const FLAG_STAGE_CHECKSUMS: u8 = 0b0000_0001;
+
+/// Original size written after the last block
+const END_OF_BLOCKS: usize = 0;
+
+pub(crate) const CHAIN_SEPARATOR: char = ',';
+
+/// Header written at the beginning of every compressed file.
+///
+/// It records the algorithm chain used during compression so the file can
+/// be uncompressed without having to pass the same `--algo` list again.
+///
+/// ## Layout:
+///
+/// ```text
+/// [magic (4 bytes)][version (1 byte)][chain size (varsize)][chain (utf-8)]   [flags (1 byte)]
+/// [S, F, C, Z]     [4]               [12]                  ["bwt,mtf,huff"]  [1]
+/// ```
+///
+/// The compressed data follows as a sequence of [`BlockHeader`] and their content, ended by a single `0`
+/// and the [`BlockIndex`].
+///
+/// Only [`FORMAT_VERSION`] is read, any other version is rejected.
+#[derive(Debug, PartialEq)]
+pub struct Header {
+    pub version: u8,
+    pub algos: Vec<String>,
+    pub flags: u8,
+}
+
+impl Header {
+    pub fn new(algos: &[&str]) -> Self {
+        Header {
+            version: FORMAT_VERSION,
+            algos: algos.iter().map(|algo| String::from(*algo)).collect(),
+            flags: 0,
+        }
+    }
+
+    /// whether the checksum of the input of every algorithm is stored
+    pub fn has_stage_checksums(&self) -> bool {
+        self.flags & FLAG_STAGE_CHECKSUMS != 0
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let chain = self.algos.join(&CHAIN_SEPARATOR.to_string());
+        let chain_size = encode_varsize(chain.len());
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 2 + chain_size.len() + chain.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(self.version);
+        bytes.extend_from_slice(&chain_size);
+        bytes.extend_from_slice(chain.as_bytes());
+        bytes.push(self.flags);
+
+        bytes
+    }
+
+    /// Read the header at the beginning of the given bytes.
+    ///
+    /// @**returns** Option<(Header, usize)> => (the header, index of the first byte following it),
+    /// None if the bytes do not start with the magic number (e.g. files created before the header existed)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Option<(Header, usize)>> {
+        if !bytes.starts_with(&MAGIC) {
+            return Ok(None);
+        }
+
+        let mut reader = Cursor::new(bytes);
+        let header = Header::read_from(&mut reader)?;
+
+        Ok(Some((header, reader.position() as usize)))
+    }
+
+    /// Read the header from the beginning of a stream, leaving it at the first byte following the header
+    pub fn read_from(reader: &mut impl Read) -> Result<Header> {
+        let magic: [u8; 4] = read_array(reader)?;
+        if magic != MAGIC {
+            return Err(CompressorError::InvalidData(String::from(
+                "the file does not start with a header",
+            )));
+        }
+
+        let [version] = read_array(reader)?;
+        if version != FORMAT_VERSION {
+            return Err(CompressorError::UnsupportedVersion(version));
+        }
+
+        let chain_size = read_varsize(reader)?;
+        let mut chain = Vec::new();
+        reader.take(chain_size as u64).read_to_end(&mut chain)?;
+        if chain.len() < chain_size {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        let chain = String::from_utf8(chain).map_err(|_| {
+            CompressorError::InvalidData(String::from("invalid algorithm chain found in header"))
+        })?;
+        let algos: Vec<String> = chain
+            .split(CHAIN_SEPARATOR)
+            .filter(|algo| !algo.is_empty())
+            .map(String::from)
+            .collect();
+
+        let [flags] = read_array(reader)?;
+
+        Ok(Header {
+            version,
+            algos,
+            flags,
+        })
+    }
+}
+
+/// Written before the content of every block of compressed data.
+///
+/// ## Layout:
+///
+/// ```text
+/// [original size (varsize)][compressed size (varsize)][crc32 of original data (4 bytes)][crc32 of each stage input (4 bytes each)]
+/// [1048576]                [3012]                     [0x3A, 0x10, 0x7F, 0x01]          [0x3A, 0x10, 0x7F, 0x01, ...]
+/// ```
+///
+/// The stage checksums are only there when the header has the [`FLAG_STAGE_CHECKSUMS`] flag.
+#[derive(Debug, PartialEq)]
+pub struct BlockHeader {
+    pub size: usize,
+    pub compressed_size: usize,
+    /// CRC-32 of the original data of the block
+    pub checksum: u32,
+    /// CRC-32 of the data given to each algorithm of the chain (in order)
+    pub stage_checksums: Option<Vec<u32>>,
+}
+
+impl BlockHeader {
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = encode_varsize(self.size);
+        bytes.extend_from_slice(&encode_varsize(self.compressed_size));
+        bytes.extend_from_slice(&self.checksum.to_be_bytes());
+
+        if let Some(stage_checksums) = &self.stage_checksums {
+            for stage_checksum in stage_checksums {
+                bytes.extend_from_slice(&stage_checksum.to_be_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// bytes written after the last block
+    pub fn end_of_blocks() -> Vec<u8> {
+        encode_varsize(END_OF_BLOCKS)
+    }
+
+    /// Read the header of the next block of a stream
+    ///
+    /// @**returns** Option<BlockHeader> => None once the end of the blocks is reached
+    pub fn read_from(reader: &mut impl Read, header: &Header) -> Result<Option<BlockHeader>> {
+        let size = read_varsize(reader)?;
+        if size == END_OF_BLOCKS {
+            return Ok(None);
+        }
+
+        let compressed_size = read_varsize(reader)?;
+        let checksum = read_u32(reader)?;
+        let stage_checksums = if header.has_stage_checksums() {
+            Some(read_checksums(reader, header.algos.len())?)
+        } else {
+            None
+        };
+
+        Ok(Some(BlockHeader {
+            size,
+            compressed_size,
+            checksum,
+            stage_checksums,
+        }))
+    }
+}
+
+/// Position of a block in the compressed data
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BlockIndexEntry {
+    /// size of the original data of the block
+    pub size: usize,
+    /// size of the block header and the compressed data
+    pub record_size: usize,
+}
+
+/// Written after the last block so any block can be found without reading the ones before it.
+///
+/// ## Layout:
+///
+/// ```text
+/// [block count (varsize)][original size, record size (varsize each) of every block][index size (4 bytes)]
+/// [2]                    [1048576, 3012, 20, 9]                                    [0x00, 0x00, 0x00, 0x08]
+/// ```
+///
+/// The index size does not count itself, it is used to read the index from the end of a file.
+#[derive(Debug, PartialEq, Default)]
+pub struct BlockIndex {
+    pub blocks: Vec<BlockIndexEntry>,
+}
+
+impl BlockIndex {
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = encode_varsize(self.blocks.len());
+        for block in &self.blocks {
+            bytes.extend_from_slice(&encode_varsize(block.size));
+            bytes.extend_from_slice(&encode_varsize(block.record_size));
+        }
+
+        let index_size = bytes.len() as u32;
+        bytes.extend_from_slice(&index_size.to_be_bytes());
+
+        bytes
+    }
+
+    /// Read the index following the end of the blocks
+    pub fn read_from(reader: &mut impl Read) -> Result<BlockIndex> {
+        let mut reader = CountingReader {
+            inner: reader,
+            count: 0,
+        };
+
+        let block_count = read_varsize(&mut reader)?;
+        let mut blocks = Vec::new();
+        for _ in 0..block_count {
+            blocks.push(BlockIndexEntry {
+                size: read_varsize(&mut reader)?,
+                record_size: read_varsize(&mut reader)?,
+            });
+        }
+
+        let index_size = reader.count;
+        if read_u32(&mut reader)? as usize != index_size {
+            return Err(CompressorError::InvalidData(String::from(
+                "invalid block index size",
+            )));
+        }
+
+        Ok(BlockIndex { blocks })
+    }
+
+    /// Read the index at the end of a compressed file
+    pub fn read_from_end<R: Read + Seek>(reader: &mut R) -> Result<BlockIndex> {
+        reader.seek(SeekFrom::End(-4))?;
+        let index_size = read_u32(reader)?;
+
+        reader.seek(SeekFrom::End(-4 - index_size as i64))?;
+        BlockIndex::read_from(reader)
+    }
+
+    /// offset of each block, starting from the first one
+    pub fn offsets(&self) -> Vec<usize> {
+        self.blocks
+            .iter()
+            .scan(0, |offset, block| {
+                let block_offset = *offset;
+                *offset += block.record_size;
+                Some(block_offset)
+            })
+            .collect()
+    }
+}
+
+/// Keeps track of the number of bytes read
+struct CountingReader<R: Read> {
+    inner: R,
+    count: usize,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read;
+
+        Ok(read)
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    Ok(u32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_checksums(reader: &mut impl Read, count: usize) -> Result<Vec<u32>> {
+    (0..count).map(|_| read_u32(reader)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn header_to_bytes() {
+        let mut header = Header::new(&["bwt", "mtf", "huff"]);
+        header.flags = FLAG_STAGE_CHECKSUMS;
+        let bytes = header.as_bytes();
+
+        let mut expected = vec![b'S', b'F', b'C', b'Z', FORMAT_VERSION, 12];
+        expected.extend_from_slice(b"bwt,mtf,huff");
+        expected.push(FLAG_STAGE_CHECKSUMS);
+
+        assert_eq!(expected, bytes);
+    }
+
+    #[test]
+    fn header_from_bytes() {
+        let header = Header::new(&["lzw", "huff"]);
+        let mut bytes = header.as_bytes();
+        let header_size = bytes.len();
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        let (decoded, content_start) = Header::from_bytes(&bytes).unwrap().unwrap();
+
+        assert_eq!(header, decoded);
+        assert_eq!(header_size, content_start);
+        assert_eq!(&[1, 2, 3], &bytes[content_start..]);
+
+        // flags missing
+        assert!(matches!(
+            Header::from_bytes(&bytes[..header_size - 1]),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn no_header() {
+        let bytes = [3, 65, 66, 67, 0, 0, 1, 4, 2, 2, 6];
+
+        assert_eq!(None, Header::from_bytes(&bytes).unwrap());
+        assert!(Header::read_from(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        for version in [0, 1, 2, 3, FORMAT_VERSION + 1] {
+            let bytes = [b'S', b'F', b'C', b'Z', version, 0, 0];
+
+            assert!(matches!(
+                Header::from_bytes(&bytes),
+                Err(CompressorError::UnsupportedVersion(v)) if v == version
+            ));
+        }
+    }
+
+    #[test]
+    fn block_headers() {
+        let mut header = Header::new(&["lzw", "huff"]);
+        let block = BlockHeader {
+            size: 512,
+            compressed_size: 3,
+            checksum: 0x3A10_7F01,
+            stage_checksums: None,
+        };
+
+        let mut bytes = block.as_bytes();
+        assert_eq!(vec![0x84, 0x00, 3, 0x3A, 0x10, 0x7F, 0x01], bytes);
+        bytes.extend_from_slice(&BlockHeader::end_of_blocks());
+
+        let mut reader = &bytes[..];
+        assert_eq!(
+            Some(block),
+            BlockHeader::read_from(&mut reader, &header).unwrap()
+        );
+        assert_eq!(None, BlockHeader::read_from(&mut reader, &header).unwrap());
+        assert!(matches!(
+            BlockHeader::read_from(&mut reader, &header),
+            Err(CompressorError::TruncatedInput)
+        ));
+
+        // with stage checksums
+        header.flags = FLAG_STAGE_CHECKSUMS;
+        let block = BlockHeader {
+            size: 1,
+            compressed_size: 1,
+            checksum: 1,
+            stage_checksums: Some(vec![2, 3]),
+        };
+        let bytes = block.as_bytes();
+        assert_eq!(
+            Some(block),
+            BlockHeader::read_from(&mut &bytes[..], &header).unwrap()
+        );
+        assert!(matches!(
+            BlockHeader::read_from(&mut &bytes[..bytes.len() - 1], &header),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn block_index() {
+        let index = BlockIndex {
+            blocks: vec![
+                BlockIndexEntry {
+                    size: 512,
+                    record_size: 30,
+                },
+                BlockIndexEntry {
+                    size: 20,
+                    record_size: 9,
+                },
+            ],
+        };
+
+        let bytes = index.as_bytes();
+        assert_eq!(vec![2, 0x84, 0x00, 30, 20, 9, 0, 0, 0, 6], bytes);
+        assert_eq!(vec![0, 30], index.offsets());
+
+        assert_eq!(index, BlockIndex::read_from(&mut &bytes[..]).unwrap());
+
+        // from the end of a file
+        let mut file = vec![1, 2, 3];
+        file.extend_from_slice(&bytes);
+        assert_eq!(
+            index,
+            BlockIndex::read_from_end(&mut Cursor::new(file)).unwrap()
+        );
+
+        // wrong size
+        let mut bytes = bytes;
+        let last = bytes.len() - 1;
+        bytes[last] = 5;
+        assert!(BlockIndex::read_from(&mut &bytes[..]).is_err());
+    }
+}
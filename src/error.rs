@@ -0,0 +1,151 @@
+use std::{fmt, io};
+
+/// Every error that can occur while compressing or uncompressing
+#[derive(Debug)]
+pub enum CompressorError {
+    /// the input ended before all the expected data could be read
+    TruncatedInput,
+    /// a variable size number does not fit in a usize
+    InvalidVarsize,
+    /// a serialized huffman tree could not be rebuilt
+    InvalidTree,
+    /// the encoded data is not valid for the algorithm decoding it
+    InvalidData(String),
+    UnknownAlgorithm(String),
+    /// an option given by the user is not valid
+    InvalidArgument(String),
+    /// a codec with the same name or alias is already in the registry
+    AlgorithmAlreadyRegistered(String),
+    UnsupportedVersion(u8),
+    /// the given algorithms differ from the ones recorded in the file header
+    AlgorithmMismatch {
+        given: Vec<String>,
+        expected: Vec<String>,
+    },
+    ChecksumMismatch {
+        what: String,
+        expected: u32,
+        found: u32,
+    },
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, CompressorError>;
+
+/// only print the error as a warning when forced to keep going
+pub(crate) fn warn_or_fail(error: CompressorError, force: bool) -> Result<()> {
+    if force {
+        eprintln!("WARNING: {error}");
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+impl fmt::Display for CompressorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressorError::TruncatedInput => {
+                write!(f, "Unexpected end of input, the data is truncated.")
+            }
+            CompressorError::InvalidVarsize => write!(f, "Invalid variable size number found."),
+            CompressorError::InvalidTree => write!(f, "Invalid huffman tree found."),
+            CompressorError::InvalidData(reason) => write!(f, "Invalid encoded data: {reason}."),
+            CompressorError::UnknownAlgorithm(algo) => {
+                write!(f, "Invalid algorithm selected: {algo}")
+            }
+            CompressorError::InvalidArgument(reason) => write!(f, "Invalid argument: {reason}."),
+            CompressorError::AlgorithmAlreadyRegistered(algo) => {
+                write!(f, "An algorithm named {algo} is already registered.")
+            }
+            CompressorError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version {version}.")
+            }
+            CompressorError::AlgorithmMismatch { given, expected } => write!(
+                f,
+                "Given algorithms [{}] do not match the ones used to compress the file [{}].",
+                given.join(", "),
+                expected.join(", ")
+            ),
+            CompressorError::ChecksumMismatch {
+                what,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Checksum mismatch for the {what}: expected {expected:#010x}, got {found:#010x}. The file is corrupted."
+            ),
+            CompressorError::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for CompressorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompressorError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CompressorError {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            // the reader ran out of data before the end of the compressed content
+            io::ErrorKind::UnexpectedEof => CompressorError::TruncatedInput,
+            // errors that went through a reader or writer of this crate
+            _ if value
+                .get_ref()
+                .is_some_and(|inner| inner.is::<CompressorError>()) =>
+            {
+                let inner = value.into_inner().expect("checked above");
+                *inner.downcast::<CompressorError>().expect("checked above")
+            }
+            _ => CompressorError::Io(value),
+        }
+    }
+}
+
+/// Needed to return errors from the `Read` and `Write` implementations
+impl From<CompressorError> for io::Error {
+    fn from(value: CompressorError) -> Self {
+        match value {
+            CompressorError::Io(error) => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn io_error_round_trip() {
+        let error = io::Error::from(CompressorError::TruncatedInput);
+        assert!(matches!(
+            CompressorError::from(error),
+            CompressorError::TruncatedInput
+        ));
+
+        let error = io::Error::from(CompressorError::UnknownAlgorithm(String::from("zip")));
+        assert!(matches!(
+            CompressorError::from(error),
+            CompressorError::UnknownAlgorithm(_)
+        ));
+
+        let error = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert!(matches!(
+            CompressorError::from(error),
+            CompressorError::TruncatedInput
+        ));
+
+        let error = io::Error::from(io::ErrorKind::NotFound);
+        assert!(matches!(
+            CompressorError::from(error),
+            CompressorError::Io(_)
+        ));
+    }
+}
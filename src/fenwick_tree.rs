@@ -2,6 +2,8 @@ use std::{collections::HashMap, hash::Hash};
 
 pub struct FenwickTree<T> {
     indexes: HashMap<T, usize>,
+    /// value of each index, to find them back when searching a range
+    keys: Vec<T>,
     values: Vec<isize>,
     sum: Vec<isize>,
 }
@@ -9,21 +11,21 @@ pub struct FenwickTree<T> {
 impl<T> FenwickTree<T> {
     pub fn new(freq: Vec<(T, isize)>) -> Self
     where
-        T: Eq + Hash,
+        T: Eq + Hash + Clone,
     {
         let capacity = freq.len();
 
         let mut tree = FenwickTree::<T> {
             indexes: HashMap::with_capacity(capacity),
+            keys: Vec::with_capacity(capacity),
             values: Vec::with_capacity(capacity),
             sum: vec![0; capacity],
         };
 
-        let mut i = 1;
-        for (value, frequency) in freq {
+        for (i, (value, frequency)) in (1..).zip(freq) {
             tree.values.push(frequency);
+            tree.keys.push(value.clone());
             tree.indexes.insert(value, i);
-            i += 1;
         }
 
         tree.update();
@@ -84,8 +86,16 @@ impl<T> FenwickTree<T> {
         T: Eq + Hash,
     {
         let index = self.indexes[&index] - 1;
-        self.values[index] += 1;
-        self.update();
+        self.add(index, 1);
+    }
+
+    /// add `count` to the count of a value, when the counts do not grow one at a time
+    pub fn increase_count(&mut self, index: T, count: isize)
+    where
+        T: Eq + Hash,
+    {
+        let index = self.indexes[&index] - 1;
+        self.add(index, count);
     }
 
     pub fn reduce_count(&mut self, index: T)
@@ -93,7 +103,27 @@ impl<T> FenwickTree<T> {
         T: Eq + Hash,
     {
         let index = self.indexes[&index] - 1;
-        self.values[index] -= 1;
+        self.add(index, -1);
+    }
+
+    /// add a count to a value, only the sums covering it are updated
+    fn add(&mut self, index: usize, count: isize) {
+        self.values[index] += count;
+
+        let mut i = index + 1;
+        while i <= self.sum.len() {
+            self.sum[i - 1] += count;
+            // next sum covering the index
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// halve every count (keeping them at least at 1), so the most recent counts weigh more
+    pub fn halve_counts(&mut self) {
+        for value in self.values.iter_mut() {
+            *value = (*value / 2).max(1);
+        }
+
         self.update();
     }
 
@@ -108,19 +138,28 @@ impl<T> FenwickTree<T> {
         (low, high)
     }
 
+    /// find the value whose bounds contain the range (low <= range < high)
     pub fn search_range(&self, range: isize) -> Option<T>
     where
-        T: Eq + Hash + Copy,
+        T: Copy,
     {
-        for (value, _) in &self.indexes {
-            let (low, high) = self.get_bounds(*value);
+        if range < 0 || self.is_empty() {
+            return None;
+        }
 
-            if low <= range && range < high {
-                return Some(*value);
+        // going down the tree, from the biggest sums to the smallest
+        let mut index = 0;
+        let mut remaining = range;
+        let mut step = 1 << self.sum.len().ilog2();
+        while step > 0 {
+            if index + step <= self.sum.len() && self.sum[index + step - 1] <= remaining {
+                index += step;
+                remaining -= self.sum[index - 1];
             }
+            step >>= 1;
         }
 
-        None
+        self.keys.get(index).copied()
     }
 
     pub fn get_total_count(&self) -> isize {
@@ -130,6 +169,10 @@ impl<T> FenwickTree<T> {
     pub fn len(&self) -> usize {
         self.values.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
 }
 
 #[cfg(test)]
@@ -195,5 +238,39 @@ mod tests {
         assert_eq!(Some('D'), tree.search_range(7));
         assert_eq!(Some('D'), tree.search_range(8));
         assert_eq!(Some('D'), tree.search_range(9));
+
+        assert_eq!(None, tree.search_range(10));
+        assert_eq!(None, tree.search_range(-1));
+    }
+
+    #[test]
+    fn fenwick_tree_search_after_update() {
+        let freq: Vec<(u8, isize)> = (0..=255).map(|c| (c, 1)).collect();
+        let mut tree = FenwickTree::new(freq);
+
+        for _ in 0..10 {
+            tree.add_count(7);
+        }
+        tree.add_count(255);
+
+        assert_eq!(Some(6), tree.search_range(6));
+        assert_eq!(Some(7), tree.search_range(7));
+        assert_eq!(Some(7), tree.search_range(17));
+        assert_eq!(Some(8), tree.search_range(18));
+        assert_eq!(Some(255), tree.search_range(266));
+        assert_eq!(None, tree.search_range(267));
+        assert_eq!(267, tree.get_total_count());
+
+        tree.halve_counts();
+        assert_eq!((7, 12), tree.get_bounds(7));
+        assert_eq!(260, tree.get_total_count());
+
+        tree.reduce_count(7);
+        assert_eq!((7, 11), tree.get_bounds(7));
+
+        tree.increase_count(7, 32);
+        assert_eq!((7, 43), tree.get_bounds(7));
+        assert_eq!(Some(7), tree.search_range(42));
+        assert_eq!(Some(8), tree.search_range(43));
     }
 }
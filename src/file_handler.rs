@@ -1,156 +1,322 @@
 use std::{
     fs::{self, File},
-    io::Write,
+    io::{self, BufReader, BufWriter, Cursor, Read, Write},
     path::{Path, PathBuf},
 };
 
-use crate::algorithms::{
-    arithmetic_encoder::ArithmeticEncoder, burrows_wheeler::BurrowsWheeler,
-    huffman_tree::HuffmanTree, lzw_encoder::LZWEncoder, move_to_front::MoveToFront,
-    run_length_encoding::RLE,
+use crate::{
+    algorithms::{
+        bzip2::Bzip2::{self, Bzip2Writer},
+        deflate::Deflate,
+        gzip::Gzip::{self, GzipWriter},
+        unix_compress::UnixCompress::{self, UnixCompressWriter},
+    },
+    codec::CodecRegistry,
+    container::MAGIC,
+    error::{CompressorError, Result},
+    stream::{
+        CompressWriter, DEFAULT_BLOCK_SIZE, DecompressReader, check_block_size, decode_chain,
+    },
 };
 
-macro_rules! match_algo {
-    ($algo:expr => {$huff:expr, $lzw:expr, $bwt:expr, $mtf:expr, $arith:expr, $rle:expr}) => {
-        match $algo {
-            "huff" | "huffman" => $huff,
-            "lzw" | "lempel-ziv-welch" => $lzw,
-            "bwt" | "burrows-wheeler" | "burrows-wheeler-transform" => $bwt,
-            "mtf" | "move-to-front" => $mtf,
-            "arith" | "arithmetic" => $arith,
-            "rle" | "run-length-encoding" => $rle,
-            _ => panic!("Invalid algorithm selected: {}", $algo),
-            // _ => $default,
-        }
-    };
-}
+const DEFAULT_COMPRESSION: [&str; 2] = ["lzw", "huff"];
 
-const DEFAULT_COMPRESSION: [&'static str; 2] = ["lzw", "huff"];
+/// files of other tools, recognized by their first bytes: (first bytes, extension, algorithm reading them)
+const OTHER_FORMATS: [(&[u8], &str, &str); 3] = [
+    (&Gzip::MAGIC, "gz", "gzip"),
+    (&UnixCompress::MAGIC, "Z", "compress"),
+    (&Bzip2::MAGIC, "bz2", "bzip2"),
+];
 
-fn inputname_to_outputname(input_file: &str) -> String {
+/// return the name under which an algorithm is recorded in the file header
+fn canonical_algo_name(registry: &CodecRegistry, algo: &str) -> Result<String> {
+    Ok(String::from(registry.get_shared(algo)?.name()))
+}
+
+fn inputname_to_outputname(input_file: &str, extension: &str) -> String {
     let tree_path = PathBuf::from(input_file);
 
     let current_dir = Path::new("");
-    let base_filename = tree_path.file_name().unwrap();
+    let base_filename = tree_path.file_name().unwrap_or(tree_path.as_os_str());
 
-    let filepath = current_dir.join(format!("{}.compressed", base_filename.to_string_lossy()));
+    let filepath = current_dir.join(format!("{}.{extension}", base_filename.to_string_lossy()));
 
-    String::from(filepath.to_str().unwrap())
+    filepath.to_string_lossy().into_owned()
 }
 
 fn get_original_filename(filename: &str) -> String {
-    let filename = filename.strip_suffix(".compressed").unwrap_or(filename);
+    let filename = std::iter::once("compressed")
+        .chain(OTHER_FORMATS.map(|(_, extension, _)| extension))
+        .find_map(|extension| filename.strip_suffix(&format!(".{extension}")))
+        .unwrap_or(filename);
 
     String::from(filename)
 }
 
-fn create_file(output_file: &str, content: &Vec<u8>) {
-    let mut output_f = File::create(output_file).expect("Failed to create file.");
-
-    output_f
-        .write_all(&content)
-        .expect("Failed to write to file.");
+/// create the output file and fill it with `write`, the file is removed if anything fails
+fn write_file(
+    output_file: &str,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    let mut output = BufWriter::new(File::create(output_file)?);
+
+    let result = write(&mut output).and_then(|_| Ok(output.flush()?));
+    if result.is_err() {
+        drop(output);
+        let _ = fs::remove_file(output_file);
+    }
 
-    output_f.flush().expect("Failed to flush");
+    result
 }
 
-fn apply_compressing_algos(algos: &mut Vec<&str>, to_encode: &[u8]) -> Vec<u8> {
-    let algo = algos.remove(0);
-
-    let mut encoded = match_algo!(
-        algo => {
-            HuffmanTree::encode_with_metadatas(to_encode),
-            LZWEncoder::encode_with_metadatas(to_encode),
-            BurrowsWheeler::encode_with_metadata(to_encode, true),
-            MoveToFront::encode(to_encode),
-            ArithmeticEncoder::encode_with_metadatas(to_encode),
-            RLE::encode(to_encode)
-        }
-    );
+/// compress a file with the given algorithms (in order) taken from the registry
+///
+/// The file is read and compressed in blocks of `block_size` bytes (1 MiB by default), several of them in parallel.
+///
+/// @**returns** String => name of the compressed file
+pub fn compress(
+    registry: &CodecRegistry,
+    input_file: &str,
+    output_file: Option<&str>,
+    algos: Option<Vec<&str>>,
+    with_stage_checksums: bool,
+    block_size: Option<usize>,
+) -> Result<String> {
+    let mut input = File::open(input_file)?;
+
+    let algos: Vec<&str> = algos.unwrap_or(DEFAULT_COMPRESSION.to_vec());
 
-    if algos.len() > 0 {
-        encoded = apply_compressing_algos(algos, &encoded);
+    // checked before creating the output file
+    for algo in &algos {
+        registry.get_shared(algo)?;
     }
+    let block_size = check_block_size(block_size.unwrap_or(DEFAULT_BLOCK_SIZE))?;
 
-    encoded
-}
+    // getting file name
+    let output_file = match output_file {
+        Some(filename) => filename,
+        None => &inputname_to_outputname(input_file, "compressed"),
+    };
 
-fn apply_uncompressing_algos(algos: &mut Vec<&str>, to_decode: &[u8]) -> Vec<u8> {
-    let algo = algos.pop().unwrap();
+    write_file(output_file, |output| {
+        let mut writer = CompressWriter::new(output, registry, &algos)?
+            .with_stage_checksums(with_stage_checksums)
+            .with_block_size(block_size)?;
 
-    let mut decoded = match_algo!(
-        algo => {
-            HuffmanTree::decode_with_metadatas(to_decode),
-            LZWEncoder::decode_with_metadatas(to_decode),
-            BurrowsWheeler::decode_with_metadata(to_decode),
-            MoveToFront::decode(to_decode),
-            ArithmeticEncoder::decode_with_metadatas(to_decode),
-            RLE::decode(to_decode)
-         }
-    );
+        io::copy(&mut input, &mut writer)?;
+        writer.finish()?;
 
-    if algos.len() > 0 {
-        decoded = apply_uncompressing_algos(algos, &decoded);
-    }
+        Ok(())
+    })?;
 
-    decoded
+    Ok(String::from(output_file))
 }
 
-pub fn compress(input_file: &str, output_file: Option<&str>, algos: Option<Vec<&str>>) -> String {
-    let bytes =
-        fs::read(input_file).expect("Failed to read file in src/filereader.rs => fn compress_file");
-
-    let mut algos: Vec<&str> = match algos {
-        Some(al) => al,
-        None => DEFAULT_COMPRESSION.to_vec(),
-    };
-
-    let encoded = apply_compressing_algos(&mut algos, &bytes);
+/// compress a file in the format of another tool, `compress` writing the content of the input to the output
+fn compress_with(
+    input_file: &str,
+    output_file: Option<&str>,
+    extension: &str,
+    compress: impl FnOnce(&mut File, &mut BufWriter<File>) -> Result<()>,
+) -> Result<String> {
+    let mut input = File::open(input_file)?;
 
     // getting file name
     let output_file = match output_file {
         Some(filename) => filename,
-        None => &inputname_to_outputname(input_file),
+        None => &inputname_to_outputname(input_file, extension),
     };
 
-    create_file(output_file, &encoded);
+    write_file(output_file, |output| compress(&mut input, output))?;
+
+    Ok(String::from(output_file))
+}
+
+/// compress a file as a standard `.gz` file, readable by `gunzip`
+///
+/// The level goes from 0 (stored) to 9 (smallest), 6 by default. The file is compressed in parts
+/// of 1 MiB, see [`GzipWriter`].
+///
+/// @**returns** String => name of the compressed file
+pub fn compress_gzip(
+    input_file: &str,
+    output_file: Option<&str>,
+    level: Option<u8>,
+) -> Result<String> {
+    // checked before creating the output file
+    let level = Deflate::check_level(level.unwrap_or(Deflate::DEFAULT_LEVEL))?;
+
+    compress_with(input_file, output_file, "gz", |input, output| {
+        let mut writer = GzipWriter::new(output, level)?;
+        io::copy(input, &mut writer)?;
+        writer.finish()?;
+
+        Ok(())
+    })
+}
+
+/// compress a file as a `.Z` file of `compress`, readable by `uncompress` and `gzip -d`
+///
+/// The codes go up to `max_bits` bits, from 9 to 16 (16 by default). They are written as the file
+/// is read, see [`UnixCompressWriter`].
+///
+/// @**returns** String => name of the compressed file
+pub fn compress_unix(
+    input_file: &str,
+    output_file: Option<&str>,
+    max_bits: Option<u8>,
+) -> Result<String> {
+    // checked before creating the output file
+    let max_bits =
+        UnixCompress::check_max_bits(max_bits.unwrap_or(UnixCompress::DEFAULT_MAX_BITS))?;
+
+    compress_with(input_file, output_file, "Z", |input, output| {
+        let mut writer = UnixCompressWriter::new(output, max_bits)?;
+        io::copy(input, &mut writer)?;
+        writer.finish()?;
+
+        Ok(())
+    })
+}
 
-    String::from(output_file)
+/// compress a file as a `.bz2` file, readable by `bunzip2`
+///
+/// The level sets the size of the blocks, from 1 (100k) to 9 (900k, the default). A few blocks are
+/// compressed at a time, see [`Bzip2Writer`].
+///
+/// @**returns** String => name of the compressed file
+pub fn compress_bzip2(
+    input_file: &str,
+    output_file: Option<&str>,
+    level: Option<u8>,
+) -> Result<String> {
+    // checked before creating the output file
+    let level = Bzip2::check_level(level.unwrap_or(Bzip2::DEFAULT_LEVEL))?;
+
+    compress_with(input_file, output_file, "bz2", |input, output| {
+        let mut writer = Bzip2Writer::new(output, level)?;
+        io::copy(input, &mut writer)?;
+        writer.finish()?;
+
+        Ok(())
+    })
 }
 
+/// uncompress a file with the algorithms recorded in its header, taken from the registry
+///
+/// The blocks of the file are uncompressed in parallel, nothing is kept if any block is corrupted
+/// unless `force` is set. Files compressed by gzip, compress or bzip2 are recognized and uncompressed
+/// as well, with the 'gzip', 'compress' and 'bzip2' algorithms, being read as they are uncompressed.
+///
+/// @**returns** String => name of the uncompressed file
 pub fn uncompress(
+    registry: &CodecRegistry,
     compressed_filepath: &str,
     output_file: Option<&str>,
     algos: Option<Vec<&str>>,
-) -> String {
-    let compressed_content = fs::read(compressed_filepath)
-        .expect("Failed to read file in src/filereader.rs => fn uncompress");
-
-    let mut algos = match algos {
-        Some(al) => al,
-        None => DEFAULT_COMPRESSION.to_vec(),
+    force: bool,
+) -> Result<String> {
+    let mut input = BufReader::new(File::open(compressed_filepath)?);
+
+    let given_algos: Option<Vec<String>> = match algos {
+        Some(al) => Some(
+            al.into_iter()
+                .map(|algo| canonical_algo_name(registry, algo))
+                .collect::<Result<_>>()?,
+        ),
+        None => None,
     };
 
-    let decoded = apply_uncompressing_algos(&mut algos, &compressed_content);
-
     // getting file name
     let output_file = match output_file {
         Some(output) => output,
         None => &get_original_filename(compressed_filepath),
     };
 
-    create_file(output_file, &decoded);
+    let mut start = Vec::with_capacity(MAGIC.len());
+    (&mut input)
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+
+    let other_format = OTHER_FORMATS
+        .iter()
+        .find(|(magic, _, _)| start.starts_with(magic));
+    if let Some(&(_, _, algo)) = other_format {
+        if let Some(given_algos) = given_algos
+            && given_algos != [algo]
+        {
+            return Err(CompressorError::AlgorithmMismatch {
+                given: given_algos,
+                expected: vec![String::from(algo)],
+            });
+        }
+
+        let input = Cursor::new(start).chain(input);
+        write_file(output_file, |output| match algo {
+            "gzip" => Gzip::decode_to(input, output, force),
+            "compress" => UnixCompress::decode_to(input, output),
+            "bzip2" => Bzip2::decode_to(input, output, force),
+            _ => unreachable!("{algo} files are not recognized"),
+        })?;
+
+        return Ok(String::from(output_file));
+    }
+
+    if start != MAGIC {
+        // files compressed before the header existed
+        let mut compressed_content = start;
+        input.read_to_end(&mut compressed_content)?;
+
+        let algos = given_algos.unwrap_or(DEFAULT_COMPRESSION.map(String::from).to_vec());
+        let codecs = algos
+            .iter()
+            .map(|algo| registry.get_shared(algo))
+            .collect::<Result<Vec<_>>>()?;
+        let decoded = decode_chain(&codecs, &compressed_content, None, "", force)?;
+
+        write_file(output_file, |output| Ok(output.write_all(&decoded)?))?;
+
+        return Ok(String::from(output_file));
+    }
 
-    String::from(output_file)
+    let mut reader =
+        DecompressReader::new(Cursor::new(start).chain(input), registry)?.with_force(force);
+
+    // the given algorithms are only used to verify the header
+    let header_algos = &reader.header().algos;
+    if let Some(given_algos) = given_algos
+        && given_algos != *header_algos
+    {
+        return Err(CompressorError::AlgorithmMismatch {
+            given: given_algos,
+            expected: header_algos.clone(),
+        });
+    }
+
+    write_file(output_file, |output| {
+        io::copy(&mut reader, output)?;
+        Ok(())
+    })?;
+
+    Ok(String::from(output_file))
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::algorithms::burrows_wheeler::BurrowsWheeler;
+    use crate::algorithms::{
+        burrows_wheeler::BurrowsWheeler, huffman_tree::HuffmanTree, lzw_encoder::LZWEncoder,
+        move_to_front::MoveToFront, run_length_encoding::RLE,
+    };
+    use crate::container::{BlockIndex, Header};
+    use crate::utils::MAX_BLOCK_SIZE;
 
     use super::*;
 
+    fn registry() -> CodecRegistry {
+        CodecRegistry::default()
+    }
+
     /* NOTE the file test test_uncommpressed_file.txt was generated with:
      ```sh
      python3 -c "print('a'*5+'b'*9+'c'*12+'d'*13+'e'*16+'f'*45)" > tests/test_uncommpressed_file.txt
@@ -162,7 +328,15 @@ mod tests {
         let output_file = "tests/test_compressed_file.compressed";
 
         // compress_file(input_file, Some(output_file));
-        compress(input_file, Some(output_file), None);
+        compress(
+            &registry(),
+            input_file,
+            Some(output_file),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
 
         let input_content =
             fs::read(input_file).expect("Failed to read file in src/filereader.rs => in test");
@@ -170,26 +344,394 @@ mod tests {
 
         // let output_file = inputname_to_outputname(&input_file);
         let restored_file = "tests/restored.txt";
-        uncompress(&output_file, Some(&restored_file), None);
+        uncompress(&registry(), output_file, Some(restored_file), None, false).unwrap();
 
         let output_content =
-            fs::read(&restored_file).expect("Failed to read file in src/filereader.rs => in test");
+            fs::read(restored_file).expect("Failed to read file in src/filereader.rs => in test");
         let output_content = String::from_utf8(output_content).unwrap();
 
         assert_eq!(input_content, output_content);
     }
 
+    #[test]
+    fn uncompress_using_algos_from_header() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_header_file.compressed";
+        let restored_file = "tests/restored_from_header.txt";
+
+        compress(
+            &registry(),
+            input_file,
+            Some(output_file),
+            Some(vec!["burrows-wheeler", "mtf", "huffman"]),
+            false,
+            None,
+        )
+        .unwrap();
+
+        let compressed_content = fs::read(output_file).unwrap();
+        let (header, _) = Header::from_bytes(&compressed_content).unwrap().unwrap();
+        assert_eq!(vec!["bwt", "mtf", "huff"], header.algos);
+
+        // no algorithm given
+        uncompress(&registry(), output_file, Some(restored_file), None, false).unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+
+        // algorithm given to verify the header
+        uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["bwt", "move-to-front", "huff"]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+    }
+
+    #[test]
+    fn uncompress_with_wrong_algos() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_wrong_algos_file.compressed";
+        let restored_file = "tests/restored_wrong_algos.txt";
+
+        compress(
+            &registry(),
+            input_file,
+            Some(output_file),
+            Some(vec!["lzw", "huff"]),
+            false,
+            None,
+        )
+        .unwrap();
+        let result = uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["huff", "lzw"]),
+            false,
+        );
+        assert!(matches!(
+            result,
+            Err(CompressorError::AlgorithmMismatch { .. })
+        ));
+
+        let result = uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["zip"]),
+            false,
+        );
+        assert!(matches!(result, Err(CompressorError::UnknownAlgorithm(_))));
+    }
+
+    #[test]
+    fn compress_with_invalid_block_size() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_invalid_block_size.compressed";
+
+        for block_size in [0, MAX_BLOCK_SIZE + 1] {
+            let result = compress(
+                &registry(),
+                input_file,
+                Some(output_file),
+                None,
+                false,
+                Some(block_size),
+            );
+            assert!(matches!(result, Err(CompressorError::InvalidArgument(_))));
+            assert!(!Path::new(output_file).exists());
+        }
+    }
+
+    #[test]
+    fn uncompress_file_without_header() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_no_header_file.compressed";
+        let restored_file = "tests/restored_no_header.txt";
+
+        let input_content = fs::read(input_file).unwrap();
+        let encoded = RLE::encode(&input_content).unwrap();
+        let encoded = HuffmanTree::encode_with_metadatas(&encoded).unwrap();
+        fs::write(output_file, &encoded).unwrap();
+
+        uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["rle", "huff"]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(input_content, fs::read(restored_file).unwrap());
+    }
+
+    /// compress the test file and flip a bit in the last byte of the compressed data
+    fn compress_and_corrupt(output_file: &str, algos: Vec<&str>, with_stage_checksums: bool) {
+        let input_file = "tests/test_uncompressed_file.txt";
+        compress(
+            &registry(),
+            input_file,
+            Some(output_file),
+            Some(algos),
+            with_stage_checksums,
+            None,
+        )
+        .unwrap();
+
+        let mut compressed_content = fs::read(output_file).unwrap();
+        // the end of the blocks and the block index follow the data
+        let index = BlockIndex::read_from_end(&mut Cursor::new(&compressed_content)).unwrap();
+        let last = compressed_content.len() - index.as_bytes().len() - 2;
+        compressed_content[last] ^= 0b1000_0000;
+        fs::write(output_file, &compressed_content).unwrap();
+    }
+
+    #[test]
+    fn uncompress_corrupted_file() {
+        let output_file = "tests/test_corrupted_file.compressed";
+        let restored_file = "tests/restored_corrupted.txt";
+
+        compress_and_corrupt(output_file, vec!["huff"], false);
+        let result = uncompress(&registry(), output_file, Some(restored_file), None, false);
+
+        match result {
+            Err(CompressorError::ChecksumMismatch { what, .. }) => {
+                assert_eq!("uncompressed data of block 1", what)
+            }
+            _ => panic!("corruption not detected"),
+        }
+
+        // nothing is written on mismatch
+        assert!(fs::read(restored_file).is_err());
+    }
+
+    #[test]
+    fn uncompress_corrupted_file_with_stage_checksums() {
+        let output_file = "tests/test_corrupted_stages_file.compressed";
+        let restored_file = "tests/restored_corrupted_stages.txt";
+
+        compress_and_corrupt(output_file, vec!["rle", "huff"], true);
+        let result = uncompress(&registry(), output_file, Some(restored_file), None, false);
+
+        match result {
+            Err(CompressorError::ChecksumMismatch { what, .. }) => {
+                assert_eq!("data restored by 'huff' (stage 2) of block 1", what)
+            }
+            _ => panic!("corruption not detected"),
+        }
+    }
+
+    #[test]
+    fn uncompress_truncated_file() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_truncated_file.compressed";
+        let restored_file = "tests/restored_truncated.txt";
+
+        compress(
+            &registry(),
+            input_file,
+            Some(output_file),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+
+        let compressed_content = fs::read(output_file).unwrap();
+        for end in 0..compressed_content.len() {
+            fs::write(output_file, &compressed_content[..end]).unwrap();
+            assert!(
+                uncompress(&registry(), output_file, Some(restored_file), None, false).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn compress_n_uncompress_gzip() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_gzip_file.gz";
+        let restored_file = "tests/restored_gzip.txt";
+
+        compress_gzip(input_file, Some(output_file), Some(9)).unwrap();
+        let compressed_content = fs::read(output_file).unwrap();
+        assert!(compressed_content.starts_with(&Gzip::MAGIC));
+
+        uncompress(&registry(), output_file, Some(restored_file), None, false).unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+
+        // gzip files are only made with gzip
+        let result = uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["lzw", "huff"]),
+            false,
+        );
+        assert!(matches!(
+            result,
+            Err(CompressorError::AlgorithmMismatch { .. })
+        ));
+        uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["gz"]),
+            false,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            compress_gzip(input_file, Some(output_file), Some(10)),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn uncompress_corrupted_gzip() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_corrupted_gzip_file.gz";
+        let restored_file = "tests/restored_corrupted_gzip.txt";
+
+        compress_gzip(input_file, Some(output_file), None).unwrap();
+        let mut compressed_content = fs::read(output_file).unwrap();
+        let crc = compressed_content.len() - 8;
+        compressed_content[crc] ^= 1;
+        fs::write(output_file, compressed_content).unwrap();
+
+        assert!(matches!(
+            uncompress(&registry(), output_file, Some(restored_file), None, false),
+            Err(CompressorError::ChecksumMismatch { .. })
+        ));
+        assert!(!Path::new(restored_file).exists());
+
+        // the checksum is only a warning when forced
+        uncompress(&registry(), output_file, Some(restored_file), None, true).unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+    }
+
+    #[test]
+    fn compress_n_uncompress_unix_compress() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_unix_compress_file.Z";
+        let restored_file = "tests/restored_unix_compress.txt";
+
+        compress_unix(input_file, Some(output_file), Some(12)).unwrap();
+        let compressed_content = fs::read(output_file).unwrap();
+        assert!(compressed_content.starts_with(&UnixCompress::MAGIC));
+
+        uncompress(
+            &registry(),
+            output_file,
+            Some(restored_file),
+            Some(vec!["unix-compress"]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+
+        assert_eq!("file.txt", get_original_filename("file.txt.Z"));
+        assert_eq!("file.txt", get_original_filename("file.txt.gz"));
+        assert_eq!("file.txt", get_original_filename("file.txt.compressed"));
+    }
+
+    #[test]
+    fn compress_n_uncompress_bzip2() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_bzip2_file.bz2";
+        let restored_file = "tests/restored_bzip2.txt";
+
+        compress_bzip2(input_file, Some(output_file), Some(1)).unwrap();
+        let compressed_content = fs::read(output_file).unwrap();
+        assert!(compressed_content.starts_with(b"BZh1"));
+
+        uncompress(&registry(), output_file, Some(restored_file), None, false).unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+
+        assert!(matches!(
+            uncompress(
+                &registry(),
+                output_file,
+                Some(restored_file),
+                Some(vec!["gzip"]),
+                false
+            ),
+            Err(CompressorError::AlgorithmMismatch { .. })
+        ));
+        assert_eq!("file.txt", get_original_filename("file.txt.bz2"));
+    }
+
+    #[test]
+    fn uncompress_corrupted_bzip2() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_corrupted_bzip2_file.bz2";
+        let restored_file = "tests/restored_corrupted_bzip2.txt";
+
+        compress_bzip2(input_file, Some(output_file), None).unwrap();
+        let mut compressed_content = fs::read(output_file).unwrap();
+        // first byte of the CRC of the first block, after the header and the block magic
+        compressed_content[10] ^= 1;
+        fs::write(output_file, compressed_content).unwrap();
+
+        assert!(matches!(
+            uncompress(&registry(), output_file, Some(restored_file), None, false),
+            Err(CompressorError::ChecksumMismatch { .. })
+        ));
+        assert!(!Path::new(restored_file).exists());
+
+        // the checksums are only warnings when forced
+        uncompress(&registry(), output_file, Some(restored_file), None, true).unwrap();
+        assert_eq!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+    }
+
+    #[test]
+    fn force_uncompress_corrupted_file() {
+        let input_file = "tests/test_uncompressed_file.txt";
+        let output_file = "tests/test_forced_file.compressed";
+        let restored_file = "tests/restored_forced.txt";
+
+        compress_and_corrupt(output_file, vec!["huff"], false);
+        uncompress(&registry(), output_file, Some(restored_file), None, true).unwrap();
+
+        assert_ne!(
+            fs::read(input_file).unwrap(),
+            fs::read(restored_file).unwrap()
+        );
+    }
+
     #[test]
     fn compress_with_lzw_then_huffman() {
         let text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
 
-        let encoded_lzw = LZWEncoder::encode_with_metadatas(&text);
-        let encoded_huff = HuffmanTree::encode_with_metadatas(&encoded_lzw);
+        let encoded_lzw = LZWEncoder::encode_with_metadatas(&text).unwrap();
+        let encoded_huff = HuffmanTree::encode_with_metadatas(&encoded_lzw).unwrap();
 
-        let decoded = HuffmanTree::decode_with_metadatas(&encoded_huff);
+        let decoded = HuffmanTree::decode_with_metadatas(&encoded_huff).unwrap();
         assert_eq!(encoded_lzw, decoded);
 
-        let decoded = LZWEncoder::decode_with_metadatas(&decoded);
+        let decoded = LZWEncoder::decode_with_metadatas(&decoded).unwrap();
         assert_eq!(text, decoded)
     }
 
@@ -197,13 +739,13 @@ mod tests {
     fn compress_with_huffman_then_lzw() {
         let text: Vec<u8> = "AAABBCCDACCAA".bytes().collect();
 
-        let encoded_huff = HuffmanTree::encode_with_metadatas(&text);
-        let encoded_lzw = LZWEncoder::encode_with_metadatas(&encoded_huff);
+        let encoded_huff = HuffmanTree::encode_with_metadatas(&text).unwrap();
+        let encoded_lzw = LZWEncoder::encode_with_metadatas(&encoded_huff).unwrap();
 
-        let decoded = LZWEncoder::decode_with_metadatas(&encoded_lzw);
+        let decoded = LZWEncoder::decode_with_metadatas(&encoded_lzw).unwrap();
         assert_eq!(encoded_huff, decoded);
 
-        let decoded = HuffmanTree::decode_with_metadatas(&decoded);
+        let decoded = HuffmanTree::decode_with_metadatas(&decoded).unwrap();
         assert_eq!(text, decoded)
     }
 
@@ -215,9 +757,9 @@ mod tests {
             text.extend_from_slice(&bloat);
         }
 
-        let encoded_bwt = BurrowsWheeler::encode_with_metadata(&text, false);
-        let encoded_mtf = MoveToFront::encode(&encoded_bwt);
-        let encoded_huff = HuffmanTree::encode_with_metadatas(&encoded_mtf);
+        let encoded_bwt = BurrowsWheeler::encode_with_metadata(&text, false).unwrap();
+        let encoded_mtf = MoveToFront::encode(&encoded_bwt).unwrap();
+        let encoded_huff = HuffmanTree::encode_with_metadatas(&encoded_mtf).unwrap();
 
         // println!("text size: {}", text.len());
 
@@ -230,13 +772,13 @@ mod tests {
         // println!("encoded_huff {encoded_huff:?}");
         // println!("encoded_huff size {}", encoded_huff.len());
 
-        let decoded_huff = HuffmanTree::decode_with_metadatas(&encoded_huff);
+        let decoded_huff = HuffmanTree::decode_with_metadatas(&encoded_huff).unwrap();
         assert_eq!(decoded_huff, encoded_mtf);
 
-        let decoded_mft = MoveToFront::decode(&decoded_huff);
+        let decoded_mft = MoveToFront::decode(&decoded_huff).unwrap();
         assert_eq!(decoded_mft, encoded_bwt);
 
-        let decoded_bwt = BurrowsWheeler::decode_with_metadata(&decoded_mft);
+        let decoded_bwt = BurrowsWheeler::decode_with_metadata(&decoded_mft).unwrap();
         assert_eq!(text, decoded_bwt);
     }
 }
@@ -0,0 +1,29 @@
+//! Compress files by chaining simple compression algorithms.
+//!
+//! Every algorithm is available as a [`codec::Codec`] through a [`codec::CodecRegistry`],
+//! which is also where your own codecs can be added to be used by [`file_handler`].
+
+pub mod algorithms;
+pub mod bit_io;
+pub mod bit_queue;
+pub mod canonical_huffman;
+pub mod checksum;
+pub mod codec;
+pub mod compressed_buffer;
+pub mod container;
+pub mod error;
+pub mod fenwick_tree;
+pub mod file_handler;
+pub mod range_coder;
+pub mod stream;
+pub mod suffix_array;
+pub mod symbol;
+#[cfg(test)]
+mod test_utils;
+pub mod utils;
+pub mod varsize;
+
+/// the examples of the README are compiled with the tests, so they follow the API
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
@@ -1,36 +1,73 @@
-use core::panic;
+use std::process;
 
 use docopt::Docopt;
-use file_handler::{compress, uncompress};
-
-mod algorithms;
-mod big_num;
-mod bit_queue;
-mod compressed_buffer;
-mod fenwick_tree;
-mod file_handler;
-mod utils;
-mod varsize;
+use simple_file_compressor::{
+    codec::CodecRegistry,
+    file_handler::{compress, compress_bzip2, compress_gzip, compress_unix, uncompress},
+    utils::parse_size,
+};
 
 // http://docopt.org/
-const USAGE: &'static str = "
+const USAGE: &str = "
 Usage:
-    simple-file-compressor (--compress | -c) [--algo=<algorithm>...] <file> [<output_file>]
-    simple-file-compressor (--uncompress | -u) [--algo=<algorithm>...] <file> [<output_file>]
+    simple-file-compressor (--compress | -c) [--stage-checksums] [--block-size=<size>] [--algo=<algorithm>...] <file> [<output_file>]
+    simple-file-compressor (--compress | -c) --gzip [--level=<level>] <file> [<output_file>]
+    simple-file-compressor (--compress | -c) --unix-compress [--bits=<bits>] <file> [<output_file>]
+    simple-file-compressor (--compress | -c) --bzip2 [--level=<level>] <file> [<output_file>]
+    simple-file-compressor (--uncompress | -u) [--force] [--algo=<algorithm>...] <file> [<output_file>]
     simple-file-compressor (--help | -h)
 
 Options:
     -h, --help               Show this message.
     -c, --compress           compress a given file.
     -u, --uncompress         uncompress a given file.
+    --stage-checksums        also store a checksum of the input of every algorithm,
+                             to know which one failed when the file is corrupted.
+    --gzip                   write a standard .gz file instead, readable by gunzip.
+    --level=<level>          gzip compression level, from 0 (stored) to 9 (smallest)
+                             (default: 6), or bzip2 block size, from 1 (100k) to 9 (900k)
+                             (default: 9).
+    --unix-compress          write a .Z file of compress instead, readable by uncompress
+                             and gzip -d.
+    --bits=<bits>            largest width of the codes of compress, from 9 to 16
+                             (default: 16).
+    --bzip2                  write a .bz2 file of bzip2 instead, readable by bunzip2.
+                             (.gz, .Z and .bz2 files are always recognized when uncompressing)
+    --force                  write the uncompressed file even if its checksum does not match.
+    --block-size=<size>      size of the blocks compressed in parallel, in bytes or
+                             with a K, M or G suffix (default: 1M, at most 1G).
     --algo=<algorithm>       Compression algorithm(s) to use (in order).
-                                [default: huff lzw]
+                                (default: lzw huff)
+                                Options are given after the name: name:key=value,...
+                                lzw, lzss and zrle can hand their codes to huff or aarith
+                                instead of bytes: stage+coder (e.g. lzw+huff).
+                                When uncompressing, the algorithms are read from the
+                                file itself and the given ones are only used to verify it.
                                 Options:
-                                    - huff, huffman
-                                    - lzw, lempel-ziv-welch
+                                    - huff, huffman (options: max_length=<8-31>)
+                                    - ahuff, adaptive-huffman
+                                    - lzw, lempel-ziv-welch (options: dict=<size>,
+                                      full=<reset|freeze|adaptive>)
+                                    - compress, lzw-z, unix-compress (options: bits=<9-16>)
+                                    - lzss, lz77 (options: window=<size>, chain=<number>)
+                                    - deflate (options: level=<0-9>)
+                                    - zlib (options: level=<0-9>)
+                                    - gzip, gz (options: level=<0-9>)
+                                    - bzip2, bz2 (options: level=<1-9>)
                                     - bwt, burrows-wheeler, burrows-wheeler-transform
                                     - mtf, move-to-front
+                                    - mtf-1, mtf1
+                                    - mtf-2, mtf2
+                                    - sticky-mtf, sticky-move-to-front
+                                    - zrle, zero-run-length, runa-runb
                                     - arith, arithmetic
+                                    - aarith, adaptive-arithmetic
+                                    - rans, range-ans
+                                    - tans, table-ans
+                                    - dmc, dynamic-markov (options: memory=<size>)
+                                    - ppm, prediction-by-partial-matching (options: order=<0-16>,
+                                      memory=<size>)
+                                    - cm, context-mixing (options: level=<1-9>)
                                     - rle, run-length-encoding
                                     - others to come soon
 ";
@@ -39,7 +76,7 @@ fn main() {
     let argv = std::env::args();
 
     let args = Docopt::new(USAGE)
-        .and_then(|d| d.argv(argv.into_iter()).parse())
+        .and_then(|d| d.argv(argv).parse())
         .unwrap_or_else(|e| e.exit());
 
     // DEBUG
@@ -57,23 +94,82 @@ fn main() {
     };
 
     let algos: Vec<&str> = args.get_vec("--algo");
-    let algos = if algos.len() == 0 { None } else { Some(algos) };
+    let algos = if algos.is_empty() { None } else { Some(algos) };
+
+    let registry = CodecRegistry::default();
+
+    let level = match args.get_str("--level") {
+        "" => None,
+        level => Some(
+            level
+                .parse()
+                .unwrap_or_else(|_| exit_with_error(format!("Invalid level '{level}'."))),
+        ),
+    };
+
+    if compressing && args.get_bool("--gzip") {
+        let compressed_filename =
+            compress_gzip(file, output_file, level).unwrap_or_else(|e| exit_with_error(e));
+
+        println!("Succesfully compressed as {}", compressed_filename);
+    } else if compressing && args.get_bool("--unix-compress") {
+        let max_bits = match args.get_str("--bits") {
+            "" => None,
+            bits => Some(
+                bits.parse()
+                    .unwrap_or_else(|_| exit_with_error(format!("Invalid code width '{bits}'."))),
+            ),
+        };
 
-    if compressing {
+        let compressed_filename =
+            compress_unix(file, output_file, max_bits).unwrap_or_else(|e| exit_with_error(e));
+
+        println!("Succesfully compressed as {}", compressed_filename);
+    } else if compressing && args.get_bool("--bzip2") {
+        let compressed_filename =
+            compress_bzip2(file, output_file, level).unwrap_or_else(|e| exit_with_error(e));
+
+        println!("Succesfully compressed as {}", compressed_filename);
+    } else if compressing {
         // compress file
-        let compressed_filename = compress(file, output_file, algos);
+        let with_stage_checksums = args.get_bool("--stage-checksums");
+        let block_size = match args.get_str("--block-size") {
+            "" => None,
+            size => Some(parse_size(size).unwrap_or_else(|e| exit_with_error(e))),
+        };
+
+        let compressed_filename = compress(
+            &registry,
+            file,
+            output_file,
+            algos,
+            with_stage_checksums,
+            block_size,
+        )
+        .unwrap_or_else(|e| exit_with_error(e));
 
         println!("Succesfully compressed as {}", compressed_filename);
     } else if uncompressing {
         // validate file format
-        if let None = file.find(".compressed") {
-            panic!(
-                "Invalid file given. Compressed file should end with the extension '.compressed'."
+        if file.find(".compressed").is_none()
+            && ![".gz", ".Z", ".bz2"]
+                .iter()
+                .any(|extension| file.ends_with(extension))
+        {
+            exit_with_error(
+                "Invalid file given. Compressed file should end with the extension '.compressed', '.gz', '.Z' or '.bz2'.",
             );
         }
 
         // uncompress file
-        let uncompressed_filename = uncompress(file, output_file, algos);
+        let force = args.get_bool("--force");
+        let uncompressed_filename = uncompress(&registry, file, output_file, algos, force)
+            .unwrap_or_else(|e| exit_with_error(e));
         println!("Succesfully uncompressed as {}", uncompressed_filename);
     }
 }
+
+fn exit_with_error(error: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {error}");
+    process::exit(1);
+}
@@ -0,0 +1,332 @@
+// Fixed-precision arithmetic coding of symbols given by their cumulative frequencies
+//
+// NOTE: good ressources for implementation
+// https://dl.acm.org/doi/10.1145/214762.214771
+
+use crate::{
+    bit_queue::BitQueue,
+    compressed_buffer::{Bit, CompressedBuffer},
+    error::{CompressorError, Result},
+};
+
+/// number of bits of the bounderies
+const CODE_BITS: u32 = 32;
+const TOP_VALUE: u64 = (1 << CODE_BITS) - 1;
+const FIRST_QUARTER: u64 = (TOP_VALUE >> 2) + 1;
+const THIRD_QUARTER: u64 = 3 * FIRST_QUARTER;
+
+/// number of leading bits that are the same in both bounderies
+fn shared_bits(low: u64, high: u64) -> u32 {
+    ((low ^ high) << (u64::BITS - CODE_BITS))
+        .leading_zeros()
+        .min(CODE_BITS)
+}
+
+/// Largest total count a symbol can be coded with.
+///
+/// The range is always bigger than a quarter of the bounderies, keeping the total well below it
+/// leaves every symbol an interval close to its probability.
+pub const MAX_TOTAL: u64 = 1 << 20;
+
+/// Narrow an interval to the bounds of each symbol, the bits shared by both ends of the interval are
+/// written as soon as they are known.
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::range_coder::{RangeDecoder, RangeEncoder};
+///
+/// // A: [0, 3), B: [3, 4)
+/// let mut encoder = RangeEncoder::new();
+/// encoder.encode(0, 3, 4);
+/// encoder.encode(3, 4, 4);
+/// let encoded = encoder.finish();
+///
+/// let mut decoder = RangeDecoder::new(&encoded);
+/// assert!(decoder.target(4) < 3);
+/// decoder.consume(0, 3, 4).unwrap();
+/// assert_eq!(3, decoder.target(4));
+/// ```
+pub struct RangeEncoder {
+    low: u64,
+    high: u64,
+    bit_queue: BitQueue,
+    buffer: CompressedBuffer,
+}
+
+impl RangeEncoder {
+    pub fn new() -> Self {
+        RangeEncoder {
+            low: 0,
+            high: TOP_VALUE,
+            bit_queue: BitQueue::new(),
+            buffer: CompressedBuffer::new(),
+        }
+    }
+
+    /// code a symbol occupying [symbol_low, symbol_high) of the total count
+    pub fn encode(&mut self, symbol_low: u64, symbol_high: u64, total: u64) {
+        debug_assert!(symbol_low < symbol_high && symbol_high <= total && total <= MAX_TOTAL);
+
+        let range = self.high - self.low + 1;
+        self.high = self.low + range * symbol_high / total - 1;
+        self.low += range * symbol_low / total;
+
+        // the leading bits shared by low and high will not change anymore
+        let shared = shared_bits(self.low, self.high);
+        if shared > 0 {
+            self.push_bits((self.low >> (CODE_BITS - 1)) as u8);
+            for i in (CODE_BITS - shared..CODE_BITS - 1).rev() {
+                let bit = if self.low >> i & 1 == 0 {
+                    Bit::ZERO
+                } else {
+                    Bit::ONE
+                };
+                self.buffer.push_bit(bit);
+            }
+
+            // rescaling
+            self.low = (self.low << shared) & TOP_VALUE;
+            self.high = (self.high << shared) & TOP_VALUE | ((1 << shared) - 1);
+        }
+
+        // low in the second quarter and high in the third, the first 2 bits are opposite
+        while self.low >= FIRST_QUARTER && self.high < THIRD_QUARTER {
+            self.low = 2 * (self.low - FIRST_QUARTER);
+            self.high = 2 * (self.high - FIRST_QUARTER) + 1;
+            self.bit_queue += 1;
+        }
+    }
+
+    /// code a single bit, the 1 having a probability of `probability` out of `total`
+    pub fn encode_bit(&mut self, bit: u8, probability: u64, total: u64) {
+        debug_assert!(0 < probability && probability < total);
+
+        if bit == 0 {
+            self.encode(0, total - probability, total);
+        } else {
+            self.encode(total - probability, total, total);
+        }
+    }
+
+    fn push_bits(&mut self, bit: u8) {
+        let buffer = &mut self.buffer;
+        self.bit_queue.push_bit_followed_by_inverted(bit, |bit| {
+            buffer.push_bit(if bit == 0 { Bit::ZERO } else { Bit::ONE })
+        });
+    }
+
+    /// write the bits telling in which quarter the interval ends
+    ///
+    /// @**returns** Vec\<u8\> => the encoded bits, the unused bits of the last byte are zeros
+    pub fn finish(mut self) -> Vec<u8> {
+        self.bit_queue += 1;
+        let bit = if self.low >= FIRST_QUARTER { 1 } else { 0 };
+        self.push_bits(bit);
+
+        self.buffer.buffer
+    }
+}
+
+impl Default for RangeEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Follow the intervals of a [`RangeEncoder`] to find back its symbols.
+pub struct RangeDecoder<'a> {
+    encoded: &'a [u8],
+    bit_index: usize,
+    low: u64,
+    high: u64,
+    value: u64,
+}
+
+impl<'a> RangeDecoder<'a> {
+    pub fn new(encoded: &'a [u8]) -> Self {
+        let mut decoder = RangeDecoder {
+            encoded,
+            bit_index: 0,
+            low: 0,
+            high: TOP_VALUE,
+            value: 0,
+        };
+
+        for _ in 0..CODE_BITS {
+            decoder.value = 2 * decoder.value + decoder.next_bit();
+        }
+
+        decoder
+    }
+
+    /// the bits following the encoded ones are zeros
+    fn next_bit(&mut self) -> u64 {
+        let bit = self
+            .encoded
+            .get(self.bit_index / 8)
+            .map_or(0, |byte| (byte >> (7 - self.bit_index % 8)) & 1);
+        self.bit_index += 1;
+
+        bit as u64
+    }
+
+    /// cumulative count within the bounds of the next symbol
+    pub fn target(&self, total: u64) -> u64 {
+        debug_assert!(total > 0, "no symbol to decode");
+
+        let range = self.high - self.low + 1;
+
+        // scalling value
+        ((self.value - self.low + 1) * total - 1) / range
+    }
+
+    /// decode a bit coded with [`RangeEncoder::encode_bit`], with the same probability
+    pub fn decode_bit(&mut self, probability: u64, total: u64) -> Result<u8> {
+        if self.target(total) < total - probability {
+            self.consume(0, total - probability, total)?;
+            Ok(0)
+        } else {
+            self.consume(total - probability, total, total)?;
+            Ok(1)
+        }
+    }
+
+    /// remove a symbol found with [`RangeDecoder::target`], with the same bounds it was encoded with
+    pub fn consume(&mut self, symbol_low: u64, symbol_high: u64, total: u64) -> Result<()> {
+        let range = self.high - self.low + 1;
+        self.high = self.low + range * symbol_high / total - 1;
+        self.low += range * symbol_low / total;
+
+        if self.value < self.low || self.value > self.high {
+            return Err(CompressorError::InvalidData(String::from(
+                "encoded value outside of the symbol bounds",
+            )));
+        }
+
+        // the leading bits shared by low, high and value
+        let shared = shared_bits(self.low, self.high);
+        if shared > 0 {
+            self.value = (self.value << shared) & TOP_VALUE;
+            for i in (0..shared).rev() {
+                self.value |= self.next_bit() << i;
+            }
+
+            self.low = (self.low << shared) & TOP_VALUE;
+            self.high = (self.high << shared) & TOP_VALUE | ((1 << shared) - 1);
+        }
+
+        // low ->  in second quarter
+        // high -> in third quarter
+        while self.low >= FIRST_QUARTER && self.high < THIRD_QUARTER {
+            self.low = 2 * (self.low - FIRST_QUARTER);
+            self.high = 2 * (self.high - FIRST_QUARTER) + 1;
+            self.value = 2 * (self.value - FIRST_QUARTER) + self.next_bit();
+        }
+
+        // the encoder writes at least as many bits as the decoder reads past the precision
+        if self.bit_index > self.encoded.len() * 8 + CODE_BITS as usize {
+            return Err(CompressorError::TruncatedInput);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// (low, high) of each symbol, total of 10
+    const BOUNDS: [(u64, u64); 4] = [(0, 1), (1, 3), (3, 6), (6, 10)];
+
+    fn decode(encoded: &[u8], size: usize) -> Result<Vec<usize>> {
+        let mut decoder = RangeDecoder::new(encoded);
+
+        let mut decoded = Vec::new();
+        for _ in 0..size {
+            let target = decoder.target(10);
+            let symbol = BOUNDS
+                .iter()
+                .position(|&(low, high)| low <= target && target < high)
+                .unwrap();
+            decoder.consume(BOUNDS[symbol].0, BOUNDS[symbol].1, 10)?;
+            decoded.push(symbol);
+        }
+
+        Ok(decoded)
+    }
+
+    #[test]
+    fn range_coder_round_trip() {
+        let symbols: Vec<usize> = (0..10_000).map(|i| (i * 7 + i / 3) % 4).collect();
+
+        let mut encoder = RangeEncoder::new();
+        for &symbol in &symbols {
+            encoder.encode(BOUNDS[symbol].0, BOUNDS[symbol].1, 10);
+        }
+        let encoded = encoder.finish();
+
+        assert_eq!(symbols, decode(&encoded, symbols.len()).unwrap());
+
+        // close to the information content of the symbols
+        let bits: f64 = symbols
+            .iter()
+            .map(|&symbol| -(((BOUNDS[symbol].1 - BOUNDS[symbol].0) as f64) / 10.0).log2())
+            .sum();
+        assert!((encoded.len() * 8) as f64 <= bits + 16.0);
+
+        assert!(matches!(
+            decode(&encoded[..encoded.len() / 2], symbols.len()),
+            Err(CompressorError::TruncatedInput)
+        ));
+    }
+
+    #[test]
+    fn range_coder_bits() {
+        // the probability of a 1 slowly going up
+        let bits: Vec<(u8, u64)> = (0..5000)
+            .map(|i| (u8::from(i % 7 < 5), 1 + i as u64 * 4094 / 5000))
+            .collect();
+
+        let mut encoder = RangeEncoder::new();
+        for &(bit, probability) in &bits {
+            encoder.encode_bit(bit, probability, 4096);
+        }
+        let encoded = encoder.finish();
+
+        let mut decoder = RangeDecoder::new(&encoded);
+        for &(bit, probability) in &bits {
+            assert_eq!(bit, decoder.decode_bit(probability, 4096).unwrap());
+        }
+    }
+
+    #[test]
+    fn range_coder_large_total() {
+        // one very likely symbol and one with a count of 1
+        let total = MAX_TOTAL;
+        let symbols: Vec<bool> = (0..1000).map(|i| i % 100 == 99).collect();
+
+        let mut encoder = RangeEncoder::new();
+        for &rare in &symbols {
+            if rare {
+                encoder.encode(total - 1, total, total);
+            } else {
+                encoder.encode(0, total - 1, total);
+            }
+        }
+        let encoded = encoder.finish();
+
+        let mut decoder = RangeDecoder::new(&encoded);
+        for &rare in &symbols {
+            let target = decoder.target(total);
+            assert_eq!(rare, target == total - 1);
+            if rare {
+                decoder.consume(total - 1, total, total).unwrap();
+            } else {
+                decoder.consume(0, total - 1, total).unwrap();
+            }
+        }
+    }
+}
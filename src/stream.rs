@@ -0,0 +1,667 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    mem,
+    sync::Arc,
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    checksum::crc32,
+    codec::{Codec, CodecRegistry},
+    container::{BlockHeader, BlockIndex, BlockIndexEntry, FLAG_STAGE_CHECKSUMS, Header},
+    error::{CompressorError, Result, warn_or_fail},
+    utils::MAX_BLOCK_SIZE,
+};
+
+/// Size of the blocks the data is split into before going through the algorithms
+pub const DEFAULT_BLOCK_SIZE: usize = 1 << 20;
+
+/// return the block size if it is from 1 to [`MAX_BLOCK_SIZE`]
+pub(crate) fn check_block_size(block_size: usize) -> Result<usize> {
+    if !(1..=MAX_BLOCK_SIZE).contains(&block_size) {
+        return Err(CompressorError::InvalidArgument(format!(
+            "block size of {block_size} bytes, it should be from 1 to {MAX_BLOCK_SIZE} bytes"
+        )));
+    }
+
+    Ok(block_size)
+}
+
+/// Compress everything written to it through a chain of codecs.
+///
+/// The data is split in blocks of at most `block_size` bytes, each of them compressed on its own
+/// and written after a [`BlockHeader`]. As many blocks as there are threads in the rayon pool
+/// are compressed in parallel, so the memory used only depends on the block size.
+///
+/// [`CompressWriter::finish`] must be called to write the end of the stream, dropping the
+/// writer also does it but ignores the errors.
+///
+/// ## Example:
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use simple_file_compressor::{
+///     codec::CodecRegistry,
+///     stream::{CompressWriter, DecompressReader},
+/// };
+///
+/// let registry = CodecRegistry::default();
+///
+/// let mut writer = CompressWriter::new(Vec::new(), &registry, &["lzw", "huff"]).unwrap();
+/// writer.write_all(b"AAABBCCDACCAA").unwrap();
+/// let compressed = writer.finish().unwrap();
+///
+/// let mut reader = DecompressReader::new(&compressed[..], &registry).unwrap();
+/// let mut decompressed = Vec::new();
+/// reader.read_to_end(&mut decompressed).unwrap();
+///
+/// assert_eq!(b"AAABBCCDACCAA", &decompressed[..]);
+/// ```
+pub struct CompressWriter<W: Write> {
+    /// None once finished
+    inner: Option<W>,
+    codecs: Vec<Arc<dyn Codec>>,
+    block: Vec<u8>,
+    /// full blocks waiting to be compressed together
+    pending: Vec<Vec<u8>>,
+    index: BlockIndex,
+    block_size: usize,
+    with_stage_checksums: bool,
+    header_written: bool,
+}
+
+impl<W: Write> CompressWriter<W> {
+    /// create a writer compressing with the given algorithms (in order) taken from the registry
+    pub fn new(inner: W, registry: &CodecRegistry, algos: &[&str]) -> Result<Self> {
+        let codecs = algos
+            .iter()
+            .map(|algo| registry.get_shared(algo))
+            .collect::<Result<_>>()?;
+
+        Ok(CompressWriter {
+            inner: Some(inner),
+            codecs,
+            block: Vec::new(),
+            pending: Vec::new(),
+            index: BlockIndex::default(),
+            block_size: DEFAULT_BLOCK_SIZE,
+            with_stage_checksums: false,
+            header_written: false,
+        })
+    }
+
+    /// maximum number of bytes compressed at once, from 1 to [`MAX_BLOCK_SIZE`]
+    pub fn with_block_size(mut self, block_size: usize) -> Result<Self> {
+        self.block_size = check_block_size(block_size)?;
+        Ok(self)
+    }
+
+    /// also store a checksum of the input of every algorithm, for each block
+    pub fn with_stage_checksums(mut self, with_stage_checksums: bool) -> Self {
+        self.with_stage_checksums = with_stage_checksums;
+        self
+    }
+
+    /// names recorded in the header for the algorithms of the chain
+    pub fn algos(&self) -> Vec<&str> {
+        self.codecs.iter().map(|codec| codec.name()).collect()
+    }
+
+    /// compress the remaining data, write the end of the stream and return the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        self.write_end()?;
+
+        Ok(self.inner.take().expect("the writer is only finished once"))
+    }
+
+    fn inner(&mut self) -> &mut W {
+        self.inner
+            .as_mut()
+            .expect("the writer is not used after being finished")
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+
+        let mut header = Header::new(&self.algos());
+        if self.with_stage_checksums {
+            header.flags |= FLAG_STAGE_CHECKSUMS;
+        }
+
+        let bytes = header.as_bytes();
+        self.inner().write_all(&bytes)?;
+        self.header_written = true;
+
+        Ok(())
+    }
+
+    /// queue the buffered data as a block, the queued blocks are compressed once there is one for each thread
+    fn end_block(&mut self, compress_now: bool) -> Result<()> {
+        if !self.block.is_empty() {
+            let capacity = self.block.capacity();
+            let block = mem::replace(&mut self.block, Vec::with_capacity(capacity));
+            self.pending.push(block);
+        }
+
+        if compress_now || self.pending.len() >= rayon::current_num_threads() {
+            self.write_pending_blocks()?;
+        }
+
+        Ok(())
+    }
+
+    /// compress the queued blocks in parallel and write them in order
+    fn write_pending_blocks(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        self.write_header()?;
+
+        let pending = mem::take(&mut self.pending);
+        let records = pending
+            .par_iter()
+            .map(|block| encode_block(&self.codecs, block, self.with_stage_checksums))
+            .collect::<Result<Vec<_>>>()?;
+
+        for (block, record) in pending.iter().zip(records) {
+            self.inner().write_all(&record)?;
+            self.index.blocks.push(BlockIndexEntry {
+                size: block.len(),
+                record_size: record.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn write_end(&mut self) -> Result<()> {
+        self.end_block(true)?;
+        self.write_header()?;
+
+        let index = self.index.as_bytes();
+        let inner = self.inner();
+        inner.write_all(&BlockHeader::end_of_blocks())?;
+        inner.write_all(&index)?;
+        inner.flush()?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for CompressWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+
+        while !rest.is_empty() {
+            let taken = rest.len().min(self.block_size - self.block.len());
+            self.block.extend_from_slice(&rest[..taken]);
+            rest = &rest[taken..];
+
+            if self.block.len() == self.block_size {
+                self.end_block(false)?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    /// compress the buffered data (the last block can be smaller) and flush the inner writer
+    fn flush(&mut self) -> io::Result<()> {
+        self.end_block(true)?;
+        self.inner().flush()
+    }
+}
+
+impl<W: Write> Drop for CompressWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_end();
+        }
+    }
+}
+
+/// compress a single block
+///
+/// @**returns** Vec<u8> => the block header followed by the compressed data
+fn encode_block(
+    codecs: &[Arc<dyn Codec>],
+    block: &[u8],
+    with_stage_checksums: bool,
+) -> Result<Vec<u8>> {
+    let mut stage_checksums = Vec::with_capacity(codecs.len());
+    let encoded = encode_chain(
+        codecs,
+        block,
+        with_stage_checksums.then_some(&mut stage_checksums),
+    )?;
+
+    let block_header = BlockHeader {
+        size: block.len(),
+        compressed_size: encoded.len(),
+        checksum: crc32(block),
+        stage_checksums: with_stage_checksums.then_some(stage_checksums),
+    };
+
+    let mut record = block_header.as_bytes();
+    record.extend_from_slice(&encoded);
+
+    Ok(record)
+}
+
+/// Uncompress a stream written by a [`CompressWriter`].
+///
+/// The algorithms are read from the header of the stream. As many blocks as there are threads
+/// in the rayon pool are read then uncompressed in parallel. The checksum of every block is
+/// verified before it can be read.
+pub struct DecompressReader<R: Read> {
+    inner: R,
+    header: Header,
+    codecs: Vec<Arc<dyn Codec>>,
+    block: Vec<u8>,
+    /// index of the next byte of the block to be read
+    position: usize,
+    /// uncompressed blocks following the current one
+    ready: VecDeque<Vec<u8>>,
+    /// blocks read so far, to be compared with the block index
+    index: BlockIndex,
+    force: bool,
+    finished: bool,
+}
+
+impl<R: Read> DecompressReader<R> {
+    /// read the header at the beginning of the stream and find its algorithms in the registry
+    pub fn new(mut inner: R, registry: &CodecRegistry) -> Result<Self> {
+        let header = Header::read_from(&mut inner)?;
+        let codecs = header
+            .algos
+            .iter()
+            .map(|algo| registry.get_shared(algo))
+            .collect::<Result<_>>()?;
+
+        Ok(DecompressReader {
+            inner,
+            header,
+            codecs,
+            block: Vec::new(),
+            position: 0,
+            ready: VecDeque::new(),
+            index: BlockIndex::default(),
+            force: false,
+            finished: false,
+        })
+    }
+
+    /// keep going when a checksum does not match, only printing a warning
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// move to the next uncompressed block
+    ///
+    /// @**returns** bool => false once every block was read
+    fn next_block(&mut self) -> Result<bool> {
+        if self.ready.is_empty() && !self.finished {
+            self.read_blocks()?;
+        }
+
+        match self.ready.pop_front() {
+            Some(block) => {
+                self.block = block;
+                self.position = 0;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// read one block for each thread and uncompress them in parallel
+    fn read_blocks(&mut self) -> Result<()> {
+        let first_block = self.index.blocks.len() + 1;
+
+        let mut records = Vec::new();
+        while records.len() < rayon::current_num_threads() {
+            match BlockHeader::read_from(&mut self.inner, &self.header)? {
+                Some(block_header) => {
+                    let mut encoded = Vec::new();
+                    (&mut self.inner)
+                        .take(block_header.compressed_size as u64)
+                        .read_to_end(&mut encoded)?;
+                    if encoded.len() < block_header.compressed_size {
+                        return Err(CompressorError::TruncatedInput);
+                    }
+
+                    self.index.blocks.push(BlockIndexEntry {
+                        size: block_header.size,
+                        record_size: block_header.as_bytes().len() + encoded.len(),
+                    });
+                    records.push((block_header, encoded));
+                }
+                None => {
+                    self.read_index()?;
+                    break;
+                }
+            }
+        }
+
+        let decoded = records
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, (block_header, encoded))| {
+                let location = format!("of block {}", first_block + i);
+                decode_block(&self.codecs, &block_header, &encoded, &location, self.force)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.ready.extend(decoded);
+
+        Ok(())
+    }
+
+    /// read the index following the last block, it must match the blocks that were read
+    fn read_index(&mut self) -> Result<()> {
+        self.finished = true;
+
+        let index = BlockIndex::read_from(&mut self.inner)?;
+        if index != self.index && !self.force {
+            return Err(CompressorError::InvalidData(String::from(
+                "the block index does not match the blocks",
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecompressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.block.len() {
+            if !self.next_block()? {
+                return Ok(0);
+            }
+        }
+
+        let read = buf.len().min(self.block.len() - self.position);
+        buf[..read].copy_from_slice(&self.block[self.position..self.position + read]);
+        self.position += read;
+
+        Ok(read)
+    }
+}
+
+/// uncompress a single block and verify its checksums
+fn decode_block(
+    codecs: &[Arc<dyn Codec>],
+    block_header: &BlockHeader,
+    encoded: &[u8],
+    location: &str,
+    force: bool,
+) -> Result<Vec<u8>> {
+    let decoded = decode_chain(
+        codecs,
+        encoded,
+        block_header.stage_checksums.as_deref(),
+        location,
+        force,
+    )?;
+
+    let what = format!("uncompressed data {location}");
+    verify_checksum(block_header.checksum, &decoded, &what, force)?;
+
+    if decoded.len() != block_header.size && !force {
+        return Err(CompressorError::InvalidData(format!(
+            "expected {} bytes {location}, got {}",
+            block_header.size,
+            decoded.len()
+        )));
+    }
+
+    Ok(decoded)
+}
+
+/// apply the codecs in order, saving the checksum of the input of each of them if asked
+pub(crate) fn encode_chain(
+    codecs: &[Arc<dyn Codec>],
+    input: &[u8],
+    mut stage_checksums: Option<&mut Vec<u32>>,
+) -> Result<Vec<u8>> {
+    let mut encoded = input.to_vec();
+
+    for codec in codecs {
+        if let Some(checksums) = stage_checksums.as_deref_mut() {
+            checksums.push(crc32(&encoded));
+        }
+
+        encoded = codec.encode(&encoded)?;
+    }
+
+    Ok(encoded)
+}
+
+/// apply the codecs in reverse order, verifying the output of each of them if the checksums are given
+///
+/// `location` is added to the error messages to tell where the data comes from
+pub(crate) fn decode_chain(
+    codecs: &[Arc<dyn Codec>],
+    input: &[u8],
+    stage_checksums: Option<&[u32]>,
+    location: &str,
+    force: bool,
+) -> Result<Vec<u8>> {
+    let mut decoded = input.to_vec();
+
+    for (stage, codec) in codecs.iter().enumerate().rev() {
+        decoded = codec.decode(&decoded)?;
+
+        if let Some(checksums) = stage_checksums {
+            let what = format!(
+                "data restored by '{}' (stage {}) {location}",
+                codec.name(),
+                stage + 1
+            );
+            verify_checksum(checksums[stage], &decoded, what.trim_end(), force)?;
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// compare the checksum of the given data with the expected one
+///
+/// fails on mismatch unless forced, in which case only a warning is printed
+fn verify_checksum(expected: u32, data: &[u8], what: &str, force: bool) -> Result<()> {
+    let checksum = crc32(data);
+
+    if checksum == expected {
+        return Ok(());
+    }
+
+    let error = CompressorError::ChecksumMismatch {
+        what: String::from(what),
+        expected,
+        found: checksum,
+    };
+
+    warn_or_fail(error, force)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn compress_in_blocks(input: &[u8], algos: &[&str], block_size: usize) -> Vec<u8> {
+        let registry = CodecRegistry::default();
+        let mut writer = CompressWriter::new(Vec::new(), &registry, algos)
+            .unwrap()
+            .with_block_size(block_size)
+            .unwrap()
+            .with_stage_checksums(true);
+
+        // uneven writes to cross the block boundaries
+        for chunk in input.chunks(5) {
+            writer.write_all(chunk).unwrap();
+        }
+
+        writer.finish().unwrap()
+    }
+
+    fn decompress(compressed: &[u8]) -> Result<Vec<u8>> {
+        let registry = CodecRegistry::default();
+        let mut reader = DecompressReader::new(compressed, &registry)?;
+
+        let mut decompressed = Vec::new();
+        reader.read_to_end(&mut decompressed)?;
+
+        Ok(decompressed)
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let text: Vec<u8> = "AAABBCCDACCAA BANANA\0BANDANA \u{FF}"
+            .repeat(20)
+            .bytes()
+            .collect();
+
+        for block_size in [1, 7, 64, text.len(), DEFAULT_BLOCK_SIZE] {
+            for algos in [&["lzw", "huff"][..], &["bwt", "mtf", "rle", "arith"], &[]] {
+                let compressed = compress_in_blocks(&text, algos, block_size);
+
+                assert_eq!(text, decompress(&compressed).unwrap(), "{algos:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn stream_empty_input() {
+        let compressed = compress_in_blocks(&[], &["huff"], 16);
+
+        let mut expected = Header::new(&["huff"]);
+        expected.flags = FLAG_STAGE_CHECKSUMS;
+        let mut expected = expected.as_bytes();
+        expected.extend_from_slice(&BlockHeader::end_of_blocks());
+        expected.extend_from_slice(&BlockIndex::default().as_bytes());
+
+        assert_eq!(expected, compressed);
+        assert!(decompress(&compressed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stream_block_boundaries() {
+        let text = vec![b'A'; 20];
+        let compressed = compress_in_blocks(&text, &["rle"], 8);
+
+        let header = Header::read_from(&mut &compressed[..]).unwrap();
+        let mut reader = &compressed[header.as_bytes().len()..];
+
+        let mut sizes = Vec::new();
+        while let Some(block) = BlockHeader::read_from(&mut reader, &header).unwrap() {
+            sizes.push(block.size);
+            reader = &reader[block.compressed_size..];
+        }
+
+        assert_eq!(vec![8, 8, 4], sizes);
+
+        let index = BlockIndex::read_from(&mut reader).unwrap();
+        assert!(reader.is_empty());
+
+        let index_sizes: Vec<usize> = index.blocks.iter().map(|block| block.size).collect();
+        assert_eq!(sizes, index_sizes);
+        assert_eq!(
+            index,
+            BlockIndex::read_from_end(&mut io::Cursor::new(&compressed)).unwrap()
+        );
+    }
+
+    #[test]
+    fn stream_invalid_block_size() {
+        let registry = CodecRegistry::default();
+        for block_size in [0, MAX_BLOCK_SIZE + 1] {
+            let writer = CompressWriter::new(Vec::new(), &registry, &["rle"]).unwrap();
+            assert!(matches!(
+                writer.with_block_size(block_size),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn stream_parallel_blocks() {
+        let text: Vec<u8> = (0..100_000u32)
+            .map(|i| ((i % 251) ^ (i % 13)) as u8)
+            .collect();
+
+        for block_size in [1000, 4096, 99_999] {
+            let compressed = compress_in_blocks(&text, &["lzw", "huff"], block_size);
+            let index = BlockIndex::read_from_end(&mut io::Cursor::new(&compressed)).unwrap();
+
+            assert_eq!(text.len().div_ceil(block_size), index.blocks.len());
+            assert_eq!(text, decompress(&compressed).unwrap());
+        }
+    }
+
+    #[test]
+    fn stream_wrong_index() {
+        let text: Vec<u8> = "AAABBCCDACCAA".repeat(4).bytes().collect();
+        let mut compressed = compress_in_blocks(&text, &["huff"], 13);
+
+        // size of the last block in the index
+        let last = compressed.len() - 6;
+        compressed[last] += 1;
+
+        assert!(matches!(
+            decompress(&compressed),
+            Err(CompressorError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn stream_flush_writes_block() {
+        let registry = CodecRegistry::default();
+        let mut writer = CompressWriter::new(Vec::new(), &registry, &["huff"]).unwrap();
+
+        writer.write_all(b"AAABBC").unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"CDACCAA").unwrap();
+        let compressed = writer.finish().unwrap();
+
+        assert_eq!(b"AAABBCCDACCAA", &decompress(&compressed).unwrap()[..]);
+    }
+
+    #[test]
+    fn stream_corrupted_block() {
+        let text: Vec<u8> = "AAABBCCDACCAA".repeat(4).bytes().collect();
+        let mut compressed = compress_in_blocks(&text, &["lzw", "huff"], 13);
+
+        // last byte of the last block, before the end of the blocks and the index
+        let index = BlockIndex::read_from_end(&mut io::Cursor::new(&compressed)).unwrap();
+        let last = compressed.len() - index.as_bytes().len() - 2;
+        compressed[last] ^= 0b1000_0000;
+
+        match decompress(&compressed) {
+            Err(CompressorError::ChecksumMismatch { what, .. }) => {
+                assert_eq!("data restored by 'huff' (stage 2) of block 4", what)
+            }
+            result => panic!("corruption not detected: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn stream_truncated() {
+        let text: Vec<u8> = "AAABBCCDACCAA".repeat(4).bytes().collect();
+        let compressed = compress_in_blocks(&text, &["lzw", "huff"], 13);
+
+        for end in 0..compressed.len() {
+            assert!(decompress(&compressed[..end]).is_err(), "end: {end}");
+        }
+    }
+}
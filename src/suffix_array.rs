@@ -0,0 +1,212 @@
+// Suffix array construction by induced sorting (SA-IS)
+
+/// Marks an empty slot of the suffix array while it is being built
+const EMPTY: usize = usize::MAX;
+
+/// Return the starting index of every suffix of the text, in lexicographic order.
+///
+/// Built in linear time with SA-IS, a sentinel smaller than every byte is added to the
+/// text during the construction, so a suffix comes before the longer suffixes it is a prefix of.
+///
+/// based on the following: <https://ieeexplore.ieee.org/document/5582081>
+///
+/// ## Example:
+///
+/// ```text
+/// BANANA => [5, 3, 1, 0, 4, 2]
+///             A, ANA, ANANA, BANANA, NA, NANA
+/// ```
+pub fn suffix_array(text: &[u8]) -> Vec<usize> {
+    // 0 is kept for the sentinel
+    let mut with_sentinel: Vec<usize> = text.iter().map(|&c| c as usize + 1).collect();
+    with_sentinel.push(0);
+
+    let mut suffixes = sais(&with_sentinel, 257);
+
+    // the sentinel is always the smallest suffix
+    suffixes.remove(0);
+    suffixes
+}
+
+/// SA-IS over a text ending by a unique sentinel (0), with every value smaller than `alphabet_size`
+fn sais(text: &[usize], alphabet_size: usize) -> Vec<usize> {
+    let n = text.len();
+    if n == 1 {
+        return vec![0];
+    }
+
+    // S-type: the suffix is smaller than the following one, L-type otherwise
+    let mut is_s = vec![false; n];
+    is_s[n - 1] = true;
+    for i in (0..n - 1).rev() {
+        is_s[i] = text[i] < text[i + 1] || (text[i] == text[i + 1] && is_s[i + 1]);
+    }
+
+    // leftmost S-type: a S-type following a L-type
+    let is_lms = |i: usize| i > 0 && is_s[i] && !is_s[i - 1];
+
+    let mut bucket_sizes = vec![0; alphabet_size];
+    for &c in text {
+        bucket_sizes[c] += 1;
+    }
+
+    // sorting the LMS substrings
+    let mut suffixes = vec![EMPTY; n];
+    let mut tails = bucket_tails(&bucket_sizes);
+    for i in (0..n).filter(|&i| is_lms(i)) {
+        tails[text[i]] -= 1;
+        suffixes[tails[text[i]]] = i;
+    }
+    induce(text, &mut suffixes, &is_s, &bucket_sizes);
+
+    // naming the LMS substrings, equal substrings get the same name
+    let sorted_lms: Vec<usize> = suffixes.iter().copied().filter(|&i| is_lms(i)).collect();
+    let mut names = vec![EMPTY; n];
+    let mut name = 0;
+    names[sorted_lms[0]] = name;
+    for pair in sorted_lms.windows(2) {
+        if !lms_substrings_equal(text, &is_s, pair[0], pair[1]) {
+            name += 1;
+        }
+        names[pair[1]] = name;
+    }
+
+    // sorting the LMS suffixes with the names as a smaller text
+    let lms_positions: Vec<usize> = (0..n).filter(|&i| is_lms(i)).collect();
+    let reduced: Vec<usize> = lms_positions.iter().map(|&i| names[i]).collect();
+
+    let reduced_suffixes = if name + 1 < reduced.len() {
+        sais(&reduced, name + 1)
+    } else {
+        // every name is unique, the order is already known
+        let mut reduced_suffixes = vec![0; reduced.len()];
+        for (i, &name) in reduced.iter().enumerate() {
+            reduced_suffixes[name] = i;
+        }
+        reduced_suffixes
+    };
+
+    // sorting every suffix from the sorted LMS suffixes
+    suffixes.fill(EMPTY);
+    let mut tails = bucket_tails(&bucket_sizes);
+    for &i in reduced_suffixes.iter().rev() {
+        let position = lms_positions[i];
+        tails[text[position]] -= 1;
+        suffixes[tails[text[position]]] = position;
+    }
+    induce(text, &mut suffixes, &is_s, &bucket_sizes);
+
+    suffixes
+}
+
+/// sort the L-type suffixes from the LMS ones, then the S-type from the L-type ones
+fn induce(text: &[usize], suffixes: &mut [usize], is_s: &[bool], bucket_sizes: &[usize]) {
+    let mut heads = bucket_heads(bucket_sizes);
+    for i in 0..suffixes.len() {
+        let suffix = suffixes[i];
+        if suffix != EMPTY && suffix > 0 && !is_s[suffix - 1] {
+            let c = text[suffix - 1];
+            suffixes[heads[c]] = suffix - 1;
+            heads[c] += 1;
+        }
+    }
+
+    let mut tails = bucket_tails(bucket_sizes);
+    for i in (0..suffixes.len()).rev() {
+        let suffix = suffixes[i];
+        if suffix != EMPTY && suffix > 0 && is_s[suffix - 1] {
+            let c = text[suffix - 1];
+            tails[c] -= 1;
+            suffixes[tails[c]] = suffix - 1;
+        }
+    }
+}
+
+fn lms_substrings_equal(text: &[usize], is_s: &[bool], a: usize, b: usize) -> bool {
+    let is_lms = |i: usize| i > 0 && is_s[i] && !is_s[i - 1];
+
+    // the sentinel is unique, so a difference is always found before the end of the text
+    for i in 0.. {
+        if text[a + i] != text[b + i] || is_s[a + i] != is_s[b + i] {
+            return false;
+        }
+
+        if i > 0 && (is_lms(a + i) || is_lms(b + i)) {
+            return is_lms(a + i) && is_lms(b + i);
+        }
+    }
+
+    unreachable!()
+}
+
+/// index of the first slot of each bucket
+fn bucket_heads(bucket_sizes: &[usize]) -> Vec<usize> {
+    bucket_sizes
+        .iter()
+        .scan(0, |sum, size| {
+            let head = *sum;
+            *sum += size;
+            Some(head)
+        })
+        .collect()
+}
+
+/// index following the last slot of each bucket
+fn bucket_tails(bucket_sizes: &[usize]) -> Vec<usize> {
+    bucket_sizes
+        .iter()
+        .scan(0, |sum, size| {
+            *sum += size;
+            Some(*sum)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::pseudo_random;
+
+    fn naive_suffix_array(text: &[u8]) -> Vec<usize> {
+        let mut suffixes: Vec<usize> = (0..text.len()).collect();
+        suffixes.sort_by_key(|&i| &text[i..]);
+        suffixes
+    }
+
+    #[test]
+    fn suffix_array_banana() {
+        let text: Vec<u8> = "BANANA".bytes().collect();
+        assert_eq!(vec![5, 3, 1, 0, 4, 2], suffix_array(&text));
+
+        assert!(suffix_array(&[]).is_empty());
+        assert_eq!(vec![0], suffix_array(&[0]));
+        assert_eq!(vec![2, 1, 0], suffix_array(&[255, 255, 255]));
+    }
+
+    #[test]
+    fn suffix_array_same_as_sorting() {
+        let texts = [
+            "mississippi",
+            "abracadabra abracadabra",
+            "AAABBCCDACCAAAABBCCDACCA",
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab",
+            "baaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            "abababababababababababababababab",
+        ];
+        for text in texts {
+            let text: Vec<u8> = text.bytes().collect();
+            assert_eq!(naive_suffix_array(&text), suffix_array(&text));
+        }
+
+        // pseudo random bytes over small and full alphabets
+        let mut seeds = pseudo_random(42);
+        for alphabet in [2u32, 4, 256] {
+            let text: Vec<u8> = (&mut seeds)
+                .take(2000)
+                .map(|seed| ((seed >> 16) % alphabet) as u8)
+                .collect();
+            assert_eq!(naive_suffix_array(&text), suffix_array(&text));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Symbols coded by the entropy coders: bytes, or the wider codes of the algorithms finding repeats
+
+use std::{fmt::Debug, hash::Hash};
+
+/// An unsigned integer an entropy coder can give a code to.
+///
+/// ## Example:
+///
+/// ```
+/// use simple_file_compressor::symbol::Symbol;
+///
+/// assert_eq!(Some(300u16), u16::from_usize(300));
+/// assert_eq!(None, u8::from_usize(300));
+/// assert_eq!(300, 300u32.to_usize());
+/// ```
+pub trait Symbol: Copy + Ord + Hash + Debug + Send + Sync + 'static {
+    /// number of bits of the largest symbol
+    const BITS: u32;
+
+    fn to_usize(self) -> usize;
+
+    /// @**returns** Option\<Self\> => the symbol of the value, None if it does not fit
+    fn from_usize(value: usize) -> Option<Self>;
+}
+
+macro_rules! unsigned_symbol {
+    ($($t:ty),*) => {
+        $(
+            impl Symbol for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+
+                fn from_usize(value: usize) -> Option<Self> {
+                    <$t>::try_from(value).ok()
+                }
+            }
+        )*
+    };
+}
+
+unsigned_symbol!(u8, u16, u32);
+
+/// the chars of the Huffman trees written before the canonical codes, all of them bytes
+impl Symbol for char {
+    const BITS: u32 = 21;
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    fn from_usize(value: usize) -> Option<Self> {
+        u32::try_from(value).ok().and_then(char::from_u32)
+    }
+}
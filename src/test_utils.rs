@@ -0,0 +1,41 @@
+//! Inputs and checks shared by the tests of the algorithms.
+
+use crate::error::{CompressorError, Result};
+
+/// pseudo random numbers of a linear congruential generator, always the same for a given seed
+pub fn pseudo_random(mut seed: u32) -> impl Iterator<Item = u32> {
+    std::iter::repeat_with(move || {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        seed
+    })
+}
+
+/// `len` pseudo random bytes, all of them equally likely
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    pseudo_random(42)
+        .take(len)
+        .map(|seed| (seed >> 24) as u8)
+        .collect()
+}
+
+/// the inputs every codec is expected to round trip: empty, a single byte, a long run, a short
+/// text, every byte value, and `random` last
+pub fn sample_texts(random: Vec<u8>) -> Vec<Vec<u8>> {
+    vec![
+        Vec::new(),
+        vec![0],
+        vec![0xFF; 1000],
+        "RGGRRRGGGB\n".bytes().collect(),
+        (0..=255).cycle().take(3 * 256).collect(),
+        random,
+    ]
+}
+
+/// the first half of `encoded` and nothing at all are both reported as truncated
+pub fn assert_truncated<T>(decode: impl Fn(&[u8]) -> Result<T>, encoded: &[u8]) {
+    assert!(matches!(
+        decode(&encoded[..encoded.len() / 2]),
+        Err(CompressorError::TruncatedInput)
+    ));
+    assert!(matches!(decode(&[]), Err(CompressorError::TruncatedInput)));
+}
@@ -1,6 +1,36 @@
 use std::collections::HashMap;
 
-#[allow(dead_code)]
+use crate::error::{CompressorError, Result};
+
+/// Largest block of data compressed at once, the algorithms reject the data they would decode to
+/// more bytes than that
+pub const MAX_BLOCK_SIZE: usize = 1 << 30;
+
+/// accept the input of an encoder if it is at most a block, as the decoders reject anything
+/// larger with [`check_decoded_size`]
+pub fn check_input_size(input: &[u8]) -> Result<()> {
+    if input.len() > MAX_BLOCK_SIZE {
+        return Err(CompressorError::InvalidArgument(format!(
+            "{} bytes, more than a block of {MAX_BLOCK_SIZE} bytes",
+            input.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// return the size read from encoded data if a block can hold that many decoded bytes, checked
+/// before decoding as a few encoded bits can stand for many bytes
+pub fn check_decoded_size(size: usize) -> Result<usize> {
+    if size > MAX_BLOCK_SIZE {
+        return Err(CompressorError::InvalidData(format!(
+            "{size} decoded bytes, more than a block of {MAX_BLOCK_SIZE} bytes"
+        )));
+    }
+
+    Ok(size)
+}
+
 /// display the data compression ratio
 ///
 /// based on the following: <https://en.wikipedia.org/wiki/Data_compression_ratio>
@@ -18,7 +48,6 @@ pub fn display_data_compression_ratio(
     println!("\t space saving: {} %", space_saving * 100.);
 }
 
-#[allow(dead_code)]
 /// get the entropy for given values
 pub fn get_entropy(values: &[u8]) -> f64 {
     let mut freq: HashMap<u8, usize> = HashMap::new();
@@ -46,6 +75,28 @@ pub fn get_entropy(values: &[u8]) -> f64 {
     -entropy
 }
 
+/// parse a size in bytes, with an optional K, M or G suffix (powers of 1024)
+///
+/// ex: "4096" => 4096, "64K" => 65536, "1M" => 1048576
+pub fn parse_size(size: &str) -> Result<usize> {
+    let invalid = || CompressorError::InvalidArgument(format!("invalid size '{size}'"));
+
+    let size = size.trim();
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&size[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&size[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+
+    let number: usize = number.parse().map_err(|_| invalid())?;
+
+    number
+        .checked_mul(multiplier)
+        .filter(|&size| size > 0)
+        .ok_or_else(invalid)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -58,4 +109,39 @@ mod tests {
 
         assert_eq!(2., entropy);
     }
+
+    #[test]
+    fn test_check_decoded_size() {
+        assert_eq!(MAX_BLOCK_SIZE, check_decoded_size(MAX_BLOCK_SIZE).unwrap());
+        for size in [MAX_BLOCK_SIZE + 1, usize::MAX] {
+            assert!(matches!(
+                check_decoded_size(size),
+                Err(CompressorError::InvalidData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_input_size() {
+        assert!(check_input_size(&[]).is_ok());
+        assert!(matches!(
+            check_input_size(&vec![0; MAX_BLOCK_SIZE + 1]),
+            Err(CompressorError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(4096, parse_size("4096").unwrap());
+        assert_eq!(65536, parse_size("64K").unwrap());
+        assert_eq!(1 << 20, parse_size("1M").unwrap());
+        assert_eq!(2 << 30, parse_size("2g").unwrap());
+
+        for size in ["", "M", "0", "-1K", "1.5M", "12T"] {
+            assert!(matches!(
+                parse_size(size),
+                Err(CompressorError::InvalidArgument(_))
+            ));
+        }
+    }
 }
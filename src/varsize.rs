@@ -1,5 +1,12 @@
 // Variable size encoding
 
+use std::io::Read;
+
+use crate::error::{CompressorError, Result};
+
+/// Maximum number of bytes a usize can be encoded with (64 bits / 7 bits per byte)
+const MAX_VARSIZE_LEN: usize = 10;
+
 /// Take a number usize and return the a representation in as little bytes as possible.
 /// The most significant bit of each byte is used to indicated if a following byte awaits.
 ///
@@ -7,7 +14,7 @@
 ///
 /// if given: n => 512 =>  0x00 0x00 0x00 0x00 0x00 0x00 0x02 0x00
 /// would return: [0x84, 0x00]
-/// ```
+/// ```text
 /// would return: [0x84, 0x00]
 ///             : [1000_0100, 0000_0000]
 ///                ^
@@ -76,53 +83,63 @@ pub fn encode_varsize(n: usize) -> Vec<u8> {
 /// assert_eq!(vec![99999], decoded);
 ///
 /// ```
-pub fn decode_varsize(encoded: &[u8]) -> Vec<usize> {
+pub fn decode_varsize(encoded: &[u8]) -> Result<Vec<usize>> {
     let mut decoded = Vec::new();
     let mut i: usize = 0;
 
     while i < encoded.len() {
-        let mut bytes = Vec::new();
-
-        // collecting bytes to reconstruct
-        while 0x80 & encoded[i] == 0x80 {
-            bytes.push(encoded[i]);
-            i += 1;
-        }
-        bytes.push(encoded[i]);
-
-        i += 1;
-
-        let mut usized: usize = 0;
-        for j in 0..bytes.len() {
-            usized |= (bytes[j] as usize & 0x7F) << (7 * (bytes.len() - 1 - j));
-        }
+        let (usized, size) = get_first_decoded(&encoded[i..])?;
         decoded.push(usized);
+
+        i += size;
     }
-    decoded
+
+    Ok(decoded)
 }
 
 /// Return the first encoded usize found
 ///
 /// @**returns** (usize, usize) => (reconstructed value, index of the last byte it was found)
-pub fn get_first_decoded(encoded: &[u8]) -> (usize, usize) {
-    let mut bytes = Vec::new();
+pub fn get_first_decoded(encoded: &[u8]) -> Result<(usize, usize)> {
+    let mut usized: usize = 0;
 
-    // collecting bytes to reconstruct
-    let mut i: usize = 0;
-    while 0x80 & encoded[i] == 0x80 {
-        bytes.push(encoded[i]);
-        i += 1;
+    for (i, &byte) in encoded.iter().enumerate() {
+        if i == MAX_VARSIZE_LEN || usized.leading_zeros() < 7 {
+            return Err(CompressorError::InvalidVarsize);
+        }
+
+        usized = (usized << 7) | (byte as usize & 0x7F);
+
+        // no byte follows
+        if 0x80 & byte == 0 {
+            return Ok((usized, i + 1));
+        }
     }
-    bytes.push(encoded[i]);
 
-    i += 1;
+    Err(CompressorError::TruncatedInput)
+}
 
+/// Read a single encoded usize from a reader, one byte at a time
+pub fn read_varsize(reader: &mut impl Read) -> Result<usize> {
     let mut usized: usize = 0;
-    for j in 0..bytes.len() {
-        usized |= (bytes[j] as usize & 0x7F) << (7 * (bytes.len() - 1 - j));
+
+    for i in 0..=MAX_VARSIZE_LEN {
+        if i == MAX_VARSIZE_LEN || usized.leading_zeros() < 7 {
+            return Err(CompressorError::InvalidVarsize);
+        }
+
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+
+        usized = (usized << 7) | (byte[0] as usize & 0x7F);
+
+        // no byte follows
+        if 0x80 & byte[0] == 0 {
+            break;
+        }
     }
 
-    (usized, i)
+    Ok(usized)
 }
 
 #[cfg(test)]
@@ -155,23 +172,23 @@ mod tests {
     #[test]
     fn test_decode_variable_width_code() {
         let n = vec![0x01];
-        let decoded = decode_varsize(&n);
+        let decoded = decode_varsize(&n).unwrap();
         assert_eq!(vec![1], decoded);
 
         let n = vec![0x84, 0x00];
-        let decoded = decode_varsize(&n);
+        let decoded = decode_varsize(&n).unwrap();
         assert_eq!(vec![512], decoded);
 
         // 1024           => 0x04 0x00
         // 1024 (encoded) => 0x82 0x83 0x00
         let n = vec![0x88, 0x00];
-        let decoded = decode_varsize(&n);
+        let decoded = decode_varsize(&n).unwrap();
         assert_eq!(vec![1024], decoded);
 
         // 99999 =>           0x01 0x86 0x9F
         // 99999 (encoded) => 0x86 0x8D 0x0F
         let n = vec![0x86, 0x8D, 0x1F];
-        let decoded = decode_varsize(&n);
+        let decoded = decode_varsize(&n).unwrap();
         assert_eq!(vec![99999], decoded);
     }
 
@@ -180,11 +197,51 @@ mod tests {
         let num = 7850;
         let encoded = encode_varsize(num);
 
-        let decoded = decode_varsize(&encoded);
+        let decoded = decode_varsize(&encoded).unwrap();
 
         assert_eq!(num, decoded[0]);
 
-        let (decoded, _) = get_first_decoded(&encoded);
+        let (decoded, _) = get_first_decoded(&encoded).unwrap();
         assert_eq!(num, decoded);
     }
+
+    #[test]
+    fn decode_invalid_varsize() {
+        // last byte indicates that another one follows
+        let n = vec![0x86, 0x8D];
+        assert!(matches!(
+            get_first_decoded(&n),
+            Err(CompressorError::TruncatedInput)
+        ));
+        assert!(matches!(
+            get_first_decoded(&[]),
+            Err(CompressorError::TruncatedInput)
+        ));
+
+        // too big to fit in a usize
+        let n = vec![0xFF; 12];
+        assert!(matches!(
+            decode_varsize(&n),
+            Err(CompressorError::InvalidVarsize)
+        ));
+    }
+
+    #[test]
+    fn read_variable_width_code() {
+        let encoded = [0x84, 0x00, 0x86, 0x8D, 0x1F, 0x01];
+        let mut reader = &encoded[..];
+
+        assert_eq!(512, read_varsize(&mut reader).unwrap());
+        assert_eq!(99999, read_varsize(&mut reader).unwrap());
+        assert_eq!(1, read_varsize(&mut reader).unwrap());
+
+        assert!(matches!(
+            read_varsize(&mut reader),
+            Err(CompressorError::TruncatedInput)
+        ));
+        assert!(matches!(
+            read_varsize(&mut &[0xFF; 11][..]),
+            Err(CompressorError::InvalidVarsize)
+        ));
+    }
 }